@@ -3,11 +3,11 @@ use std::collections::BTreeSet;
 use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
 use rand::Rng;
 
-use rbtset::RBTreeSet;
+use rbtset::{ArenaRBTreeSet, RBTreeSet};
 
 fn make_data(size: usize) -> Vec<i64> {
     let mut rng = rand::thread_rng();
-    let low = -1 * (size as i64);
+    let low = -(size as i64);
     let high = size as i64;
     let mut data = Vec::with_capacity(size);
     for _ in 0..size {
@@ -25,7 +25,7 @@ fn sv_insert(sv: &mut Vec<i64>, data: &[i64]) {
     }
 }
 
-fn sv_contains(sv: &Vec<i64>, values: &[i64]) {
+fn sv_contains(sv: &[i64], values: &[i64]) {
     for value in values {
         assert!(sv.contains(value));
     }
@@ -74,6 +74,24 @@ fn rbt_delete(rbt: &mut RBTreeSet<i64>, values: &[i64]) {
     }
 }
 
+fn arena_insert(arena: &mut ArenaRBTreeSet<i64>, data: &[i64]) {
+    for v in data {
+        arena.insert(*v);
+    }
+}
+
+fn arena_contains(arena: &ArenaRBTreeSet<i64>, values: &[i64]) {
+    for value in values {
+        assert!(arena.contains(value));
+    }
+}
+
+fn arena_delete(arena: &mut ArenaRBTreeSet<i64>, values: &[i64]) {
+    for value in values {
+        arena.remove(value);
+    }
+}
+
 fn op_insert(c: &mut Criterion) {
     let mut group = c.benchmark_group("insert");
     for size in SAMPLE_SIZES {
@@ -90,7 +108,11 @@ fn op_insert(c: &mut Criterion) {
         });
         group.bench_with_input(BenchmarkId::new("rbtree set", size), &data, |b, d| {
             let mut rbt = RBTreeSet::new();
-            b.iter(|| rbt_insert(&mut rbt, &d));
+            b.iter(|| rbt_insert(&mut rbt, d));
+        });
+        group.bench_with_input(BenchmarkId::new("arena set", size), &data, |b, d| {
+            let mut arena = ArenaRBTreeSet::new();
+            b.iter(|| arena_insert(&mut arena, d));
         });
     }
 }
@@ -102,17 +124,22 @@ fn op_contains(c: &mut Criterion) {
         group.bench_with_input(BenchmarkId::new("sorted vec", size), &data, |b, d| {
             let mut sv = Vec::new();
             sv_insert(&mut sv, d);
-            b.iter(|| sv_contains(&mut sv, &d[..5]));
+            b.iter(|| sv_contains(&sv, &d[..5]));
         });
         group.bench_with_input(BenchmarkId::new("btree set", size), &data, |b, d| {
             let mut bts = BTreeSet::new();
             bts_insert(&mut bts, d);
-            b.iter(|| bts_contains(&mut bts, &d[..5]));
+            b.iter(|| bts_contains(&bts, &d[..5]));
         });
         group.bench_with_input(BenchmarkId::new("rbtree set", size), &data, |b, d| {
             let mut rbt = RBTreeSet::new();
             rbt_insert(&mut rbt, d);
-            b.iter(|| rbt_contains(&mut rbt, &d[..5]));
+            b.iter(|| rbt_contains(&rbt, &d[..5]));
+        });
+        group.bench_with_input(BenchmarkId::new("arena set", size), &data, |b, d| {
+            let mut arena = ArenaRBTreeSet::new();
+            arena_insert(&mut arena, d);
+            b.iter(|| arena_contains(&arena, &d[..5]));
         });
     }
 }
@@ -128,10 +155,7 @@ fn op_clone(c: &mut Criterion) {
                     sv_insert(&mut sv, d);
                     sv
                 },
-                |sv| {
-                    let cloned = sv.clone();
-                    cloned
-                },
+                |sv| sv.clone(),
                 BatchSize::SmallInput,
             );
         });
@@ -142,10 +166,7 @@ fn op_clone(c: &mut Criterion) {
                     bts_insert(&mut bts, d);
                     bts
                 },
-                |bts| {
-                    let cloned = bts.clone();
-                    cloned
-                },
+                |bts| bts.clone(),
                 BatchSize::SmallInput,
             );
         });
@@ -156,10 +177,18 @@ fn op_clone(c: &mut Criterion) {
                     rbt_insert(&mut rbt, d);
                     rbt
                 },
-                |rbt| {
-                    let cloned = rbt.clone();
-                    cloned
+                |rbt| rbt.clone(),
+                BatchSize::LargeInput,
+            );
+        });
+        group.bench_with_input(BenchmarkId::new("arena set", size), &data, |b, d| {
+            b.iter_batched(
+                || {
+                    let mut arena = ArenaRBTreeSet::new();
+                    arena_insert(&mut arena, d);
+                    arena
                 },
+                |arena| arena.clone(),
                 BatchSize::LargeInput,
             );
         });
@@ -197,8 +226,50 @@ fn op_delete(c: &mut Criterion) {
                 BatchSize::LargeInput,
             );
         });
+        group.bench_with_input(BenchmarkId::new("arena set", size), &data, |b, d| {
+            let mut arena = ArenaRBTreeSet::new();
+            arena_insert(&mut arena, d);
+            b.iter_batched_ref(
+                || arena.clone(),
+                |arena| arena_delete(arena, &d[1..5]),
+                BatchSize::LargeInput,
+            );
+        });
+    }
+}
+
+fn op_build_sorted(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build sorted");
+    for size in SAMPLE_SIZES {
+        let mut data = make_data(*size);
+        data.sort_unstable();
+        data.dedup();
+        group.bench_with_input(BenchmarkId::new("btree set", size), &data, |b, d| {
+            b.iter(|| d.iter().cloned().collect::<BTreeSet<_>>());
+        });
+        group.bench_with_input(
+            BenchmarkId::new("rbtree set insert", size),
+            &data,
+            |b, d| {
+                b.iter(|| {
+                    let mut rbt = RBTreeSet::new();
+                    rbt_insert(&mut rbt, d);
+                    rbt
+                });
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("rbtree set", size), &data, |b, d| {
+            b.iter(|| RBTreeSet::<i64>::from_sorted_iter(d.iter().cloned()));
+        });
     }
 }
 
-criterion_group!(benches, op_insert, op_contains, op_clone, op_delete);
+criterion_group!(
+    benches,
+    op_insert,
+    op_contains,
+    op_clone,
+    op_delete,
+    op_build_sorted
+);
 criterion_main!(benches);
@@ -0,0 +1,200 @@
+//! Derive macro for the `Consecutive` trait of [rbtset].
+//!
+//! Enabled in `rbtset` with the `derive` feature, see [Consecutive] for the details.
+//!
+//! [rbtset]: https://docs.rs/rbtset
+//! [Consecutive]: derive.Consecutive.html
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Field, Fields, Ident, LitStr, Path};
+
+/// Derives `Consecutive`, `Splittable`, `Ord`, `PartialOrd`, `PartialEq` and `Eq` for a
+/// struct with `start` and `end` fields describing a half-open range `start..end`.
+///
+/// The generated implementations are consistent with each other:
+/// - values are ordered by `start` then `end`, and equal when both are equal, the
+///   other fields being ignored,
+/// - two values are consecutive when the first one ends where the second one starts,
+///   the merge keeping the other fields of the first one,
+/// - the parts left by a split keep the other fields of the split value.
+///
+/// Use the `Containment` comparator to look a value up with a range it covers.
+///
+/// The struct must implement `Clone` and the type of `start` and `end` must implement
+/// `Ord` and `Clone`.
+///
+/// The implementations refer to the traits through `::rbtset`, which can be changed
+/// with the `#[consecutive(crate = "path::to::rbtset")]` attribute when the crate is
+/// renamed or re-exported.
+///
+/// # Examples
+///
+/// ```
+/// use rbtset::{Containment, RBTreeSet};
+/// use rbtset_derive::Consecutive;
+///
+/// #[derive(Debug, Clone, Consecutive)]
+/// struct Seq {
+///     start: usize,
+///     end: usize,
+/// }
+///
+/// let mut set = RBTreeSet::with_comparator(Containment);
+/// set.insert(Seq { start: 1, end: 3 });
+/// set.insert(Seq { start: 3, end: 5 });
+/// set.repack();
+///
+/// let merged = set.get(&Seq { start: 4, end: 5 }).unwrap();
+/// assert_eq!((merged.start, merged.end), (1, 5));
+/// ```
+#[proc_macro_derive(Consecutive, attributes(consecutive))]
+pub fn derive_consecutive(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    &input.ident,
+                    "Consecutive can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "Consecutive can only be derived for structs",
+            ))
+        }
+    };
+    let start = find_field(&input.ident, fields.iter(), "start")?;
+    let end = find_field(&input.ident, fields.iter(), "end")?;
+    let krate = crate_path(&input)?;
+
+    let name = &input.ident;
+    let mut generics = input.generics.clone();
+    let where_clause = generics.make_where_clause();
+    for ty in &[&start.ty, &end.ty] {
+        where_clause
+            .predicates
+            .push(syn::parse_quote! { #ty: ::std::cmp::Ord + ::std::clone::Clone });
+    }
+    where_clause
+        .predicates
+        .push(syn::parse_quote! { Self: ::std::clone::Clone });
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::std::cmp::Ord for #name #ty_generics #where_clause {
+            fn cmp(&self, other: &Self) -> ::std::cmp::Ordering {
+                ::std::cmp::Ord::cmp(&self.start, &other.start)
+                    .then_with(|| ::std::cmp::Ord::cmp(&self.end, &other.end))
+            }
+        }
+
+        impl #impl_generics ::std::cmp::PartialOrd for #name #ty_generics #where_clause {
+            fn partial_cmp(&self, other: &Self) -> ::std::option::Option<::std::cmp::Ordering> {
+                ::std::option::Option::Some(::std::cmp::Ord::cmp(self, other))
+            }
+        }
+
+        impl #impl_generics ::std::cmp::PartialEq for #name #ty_generics #where_clause {
+            fn eq(&self, other: &Self) -> bool {
+                self.start == other.start && self.end == other.end
+            }
+        }
+
+        impl #impl_generics ::std::cmp::Eq for #name #ty_generics #where_clause {}
+
+        impl #impl_generics #krate::Consecutive for #name #ty_generics #where_clause {
+            fn consecutive(&self, other: &Self) -> bool {
+                self.end == other.start
+            }
+
+            fn merged(&self, other: &Self) -> Self {
+                #name {
+                    end: ::std::clone::Clone::clone(&other.end),
+                    ..::std::clone::Clone::clone(self)
+                }
+            }
+        }
+
+        impl #impl_generics #krate::Splittable for #name #ty_generics #where_clause {
+            fn split(
+                &self,
+                other: &Self,
+            ) -> ::std::option::Option<(::std::option::Option<Self>, ::std::option::Option<Self>)> {
+                // an empty value overlaps nothing
+                if other.start >= other.end {
+                    return ::std::option::Option::None;
+                }
+                if self.end <= other.start || other.end <= self.start {
+                    return ::std::option::Option::None;
+                }
+                let before = if self.start < other.start {
+                    ::std::option::Option::Some(#name {
+                        end: ::std::clone::Clone::clone(&other.start),
+                        ..::std::clone::Clone::clone(self)
+                    })
+                } else {
+                    ::std::option::Option::None
+                };
+                let after = if other.end < self.end {
+                    ::std::option::Option::Some(#name {
+                        start: ::std::clone::Clone::clone(&other.end),
+                        ..::std::clone::Clone::clone(self)
+                    })
+                } else {
+                    ::std::option::Option::None
+                };
+                ::std::option::Option::Some((before, after))
+            }
+        }
+    })
+}
+
+/// Returns the path of the `rbtset` crate, `::rbtset` unless overridden with
+/// `#[consecutive(crate = "...")]`.
+fn crate_path(input: &DeriveInput) -> syn::Result<Path> {
+    let mut path = syn::parse_quote! { ::rbtset };
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|a| a.path().is_ident("consecutive"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("crate") {
+                path = meta.value()?.parse::<LitStr>()?.parse()?;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported consecutive attribute"))
+            }
+        })?;
+    }
+    Ok(path)
+}
+
+fn find_field<'a>(
+    ident: &Ident,
+    mut fields: impl Iterator<Item = &'a Field>,
+    name: &str,
+) -> syn::Result<&'a Field> {
+    fields
+        .find(|f| f.ident.as_ref().is_some_and(|i| i == name))
+        .ok_or_else(|| {
+            Error::new_spanned(
+                ident,
+                format!("Consecutive requires a field named `{}`", name),
+            )
+        })
+}
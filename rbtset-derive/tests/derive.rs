@@ -0,0 +1,82 @@
+use rbtset::{Consecutive as _, Containment, RBTreeSet, Splittable as _};
+use rbtset_derive::Consecutive;
+
+mod reexport {
+    pub use rbtset as inner;
+}
+
+#[derive(Debug, Clone, Consecutive)]
+struct Seq {
+    start: usize,
+    end: usize,
+}
+
+#[derive(Debug, Clone, Consecutive)]
+struct Tagged<T, U> {
+    tag: U,
+    start: T,
+    end: T,
+}
+
+#[derive(Debug, Clone, Consecutive)]
+#[consecutive(crate = "reexport::inner")]
+struct Renamed {
+    start: u8,
+    end: u8,
+}
+
+#[test]
+fn consistent() {
+    let a = Seq { start: 1, end: 3 };
+    let b = Seq { start: 3, end: 5 };
+    assert!(a < b);
+    assert!(a < Seq { start: 1, end: 4 });
+    assert!(a.consecutive(&b));
+    assert!(!b.consecutive(&a));
+    assert_eq!(Seq { start: 1, end: 3 }, a);
+    assert_ne!(Seq { start: 2, end: 3 }, a);
+    assert_ne!(b, a);
+
+    let merged = a.merged(&b);
+    assert_eq!((merged.start, merged.end), (1, 5));
+
+    let (before, after) = merged.split(&Seq { start: 2, end: 3 }).unwrap();
+    assert_eq!(before, Some(Seq { start: 1, end: 2 }));
+    assert_eq!(after, Some(Seq { start: 3, end: 5 }));
+    assert_eq!(merged.split(&Seq { start: 5, end: 7 }), None);
+    assert_eq!(merged.split(&Seq { start: 3, end: 3 }), None);
+
+    let renamed = Renamed { start: 1, end: 2 }.merged(&Renamed { start: 2, end: 4 });
+    assert_eq!((renamed.start, renamed.end), (1, 4));
+}
+
+#[test]
+fn repack() {
+    let mut set = RBTreeSet::new();
+    for i in (0..20).rev() {
+        set.insert(Tagged {
+            tag: i,
+            start: i * 2,
+            end: i * 2 + 2,
+        });
+    }
+    set.insert(Tagged {
+        tag: 0,
+        start: 50,
+        end: 60,
+    });
+    set.repack();
+
+    let values: Vec<_> = set.values().map(|t| (t.tag, t.start, t.end)).collect();
+    assert_eq!(values, vec![(0, 0, 40), (0, 50, 60)]);
+}
+
+#[test]
+fn containment() {
+    let mut set = RBTreeSet::with_comparator(Containment);
+    set.insert(Seq { start: 1, end: 5 });
+    set.insert(Seq { start: 8, end: 10 });
+    assert!(set.contains(&Seq { start: 2, end: 3 }));
+    assert!(!set.contains(&Seq { start: 4, end: 9 }));
+    assert!(set.insert(Seq { start: 1, end: 2 }).is_none());
+}
@@ -0,0 +1,1742 @@
+use std::borrow::Borrow;
+use std::fmt;
+use std::iter::{FromIterator, Rev};
+use std::ops::{Bound, RangeBounds};
+
+use crate::node::Colour;
+use crate::raw::{self, RawTree, Storage};
+use crate::{Augment, Compare, Consecutive, Natural, Splittable};
+
+/// A lightweight reference to a value of an [ArenaRBTreeSet].
+///
+/// A handle stays valid until its value is removed from the set. A stale handle is
+/// detected and never resolves to another value, even when its slot gets reused: a
+/// slot is reused at most `u32::MAX` times, then it is retired.
+///
+/// A handle does not record the set that issued it. It is only meaningful for that set
+/// and its clones, in which it designates the cloned value: given to an unrelated set,
+/// it may resolve to any of its values or to none.
+///
+/// [ArenaRBTreeSet]: struct.ArenaRBTreeSet.html
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Handle {
+    index: usize,
+    generation: u32,
+}
+
+#[derive(Clone)]
+struct NodeData<T, A> {
+    colour: Colour,
+    parent: Option<usize>,
+    left: Option<usize>,
+    right: Option<usize>,
+    size: usize,
+    summary: A,
+    data: T,
+}
+
+#[derive(Clone)]
+struct Slot<T, A> {
+    generation: u32,
+    node: Option<NodeData<T, A>>,
+}
+
+/// A set based on a RB-Tree whose nodes are stored in an arena.
+///
+/// The nodes are kept in a `Vec` and linked with indices, avoiding the reference
+/// counting and the dynamic borrow checks of [RBTreeSet]. Removed slots are recycled
+/// through a free list. Values are designated by [Handle]s, lightweight `Copy` values
+/// which, unlike a [Node], need the set to be resolved.
+///
+/// The arena shares the algorithms of [RBTreeSet], relinking its indices instead of
+/// the [Node]s, and offers the same operations: augmentation, order
+/// statistics, repacking, set operations, bulk removals, split and join. The values
+/// of a set stay in its arena though: [split_off], [join] and [append] move the values
+/// of one set to the arena of the other, in `O(k)` for `k` moved values, and the
+/// handles of the moved values are no longer valid.
+///
+/// [RBTreeSet]: struct.RBTreeSet.html
+/// [Node]: struct.Node.html
+/// [Handle]: struct.Handle.html
+/// [split_off]: #method.split_off
+/// [join]: #method.join
+/// [append]: #method.append
+///
+/// # Examples
+///
+/// ```
+/// use rbtset::ArenaRBTreeSet;
+///
+/// let mut set = ArenaRBTreeSet::new();
+/// let handle = set.insert(2).unwrap();
+/// set.insert(1);
+/// set.insert(3);
+///
+/// assert_eq!(set.get(handle), Some(&2));
+/// assert_eq!(set.iter().collect::<Vec<_>>(), vec![&1, &2, &3]);
+///
+/// assert_eq!(set.remove_node(handle), Some(2));
+/// assert_eq!(set.get(handle), None);
+/// ```
+pub struct ArenaRBTreeSet<T, A = (), C = Natural> {
+    raw: RawTree<Slots<T, A>, C>,
+}
+
+/// The slots of an arena along with the free list of the vacant ones, see [Storage].
+///
+/// [Storage]: ../raw/trait.Storage.html
+#[derive(Clone)]
+struct Slots<T, A> {
+    slots: Vec<Slot<T, A>>,
+    free: Vec<usize>,
+}
+
+impl<T, A> Slots<T, A> {
+    fn new() -> Slots<T, A> {
+        Slots {
+            slots: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    fn node(&self, index: usize) -> &NodeData<T, A> {
+        self.slots[index]
+            .node
+            .as_ref()
+            .expect("link to a vacant slot")
+    }
+
+    fn node_mut(&mut self, index: usize) -> &mut NodeData<T, A> {
+        self.slots[index]
+            .node
+            .as_mut()
+            .expect("link to a vacant slot")
+    }
+}
+
+impl<T, A> Storage for Slots<T, A> {
+    type Value = T;
+    type Summary = A;
+    type Link = usize;
+    /// The value of the node, its slot being vacant.
+    type Released = T;
+    type Data<'a>
+        = &'a T
+    where
+        Self: 'a;
+    type SummaryRef<'a>
+        = &'a A
+    where
+        Self: 'a;
+
+    fn duplicate(link: &usize) -> usize {
+        *link
+    }
+
+    fn alloc(&mut self, data: T, summary: A) -> usize {
+        let node = NodeData {
+            colour: Colour::Red,
+            parent: None,
+            left: None,
+            right: None,
+            size: 1,
+            summary,
+            data,
+        };
+        match self.free.pop() {
+            Some(index) => {
+                self.slots[index].node = Some(node);
+                index
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    node: Some(node),
+                });
+                self.slots.len() - 1
+            }
+        }
+    }
+
+    fn release(&mut self, index: usize) -> T {
+        let slot = &mut self.slots[index];
+        let node = slot.node.take().expect("release of a vacant slot");
+        // a slot whose generation saturates is retired, stale handles never match again
+        if let Some(generation) = slot.generation.checked_add(1) {
+            slot.generation = generation;
+            self.free.push(index);
+        }
+        node.data
+    }
+
+    fn data<'a>(&'a self, node: &'a usize) -> &'a T {
+        &self.node(*node).data
+    }
+
+    fn set_data(&mut self, node: &usize, data: T) {
+        self.node_mut(*node).data = data;
+    }
+
+    fn summary<'a>(&'a self, node: &'a usize) -> &'a A {
+        &self.node(*node).summary
+    }
+
+    fn size(&self, node: &usize) -> usize {
+        self.node(*node).size
+    }
+
+    fn set_subtree(&mut self, node: &usize, size: usize, summary: A) {
+        let nd = self.node_mut(*node);
+        nd.size = size;
+        nd.summary = summary;
+    }
+
+    fn parent(&self, node: &usize) -> Option<usize> {
+        self.node(*node).parent
+    }
+
+    fn set_parent(&mut self, node: &usize, parent: Option<usize>) {
+        self.node_mut(*node).parent = parent;
+    }
+
+    fn left(&self, node: &usize) -> Option<usize> {
+        self.node(*node).left
+    }
+
+    fn set_left(&mut self, node: &usize, left: Option<usize>) {
+        self.node_mut(*node).left = left;
+    }
+
+    fn right(&self, node: &usize) -> Option<usize> {
+        self.node(*node).right
+    }
+
+    fn set_right(&mut self, node: &usize, right: Option<usize>) {
+        self.node_mut(*node).right = right;
+    }
+
+    fn colour(&self, node: &usize) -> Colour {
+        self.node(*node).colour
+    }
+
+    fn set_colour(&mut self, node: &usize, colour: Colour) {
+        self.node_mut(*node).colour = colour;
+    }
+}
+
+impl<T, A, C> ArenaRBTreeSet<T, A, C> {
+    fn handle(&self, index: usize) -> Handle {
+        Handle {
+            index,
+            generation: self.raw.nodes.slots[index].generation,
+        }
+    }
+
+    fn resolve(&self, handle: Handle) -> Option<usize> {
+        let slot = self.raw.nodes.slots.get(handle.index)?;
+        if slot.generation == handle.generation && slot.node.is_some() {
+            Some(handle.index)
+        } else {
+            None
+        }
+    }
+
+    /// Empties the set, returning its values in ascending order in `O(n)`.
+    fn take_values(&mut self) -> Vec<T> {
+        let indices = self.raw.take_links();
+        indices
+            .into_iter()
+            .map(|index| self.raw.nodes.release(index))
+            .collect()
+    }
+
+    /// Returns the value designated by the handle, if it is still in the set.
+    ///
+    /// The handle must have been issued by this set, or by the set it was cloned from,
+    /// see [Handle].
+    ///
+    /// [Handle]: struct.Handle.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let mut set = ArenaRBTreeSet::new();
+    /// let handle = set.insert("a").unwrap();
+    /// assert_eq!(set.get(handle), Some(&"a"));
+    /// set.remove(&"a");
+    /// assert_eq!(set.get(handle), None);
+    /// ```
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        Some(&self.raw.nodes.node(self.resolve(handle)?).data)
+    }
+
+    /// Returns the handle of the next value in ascending order, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let set: ArenaRBTreeSet<_> = (1..3).collect();
+    /// let first = set.first().unwrap();
+    /// assert_eq!(set.next(first).and_then(|h| set.get(h)), Some(&2));
+    /// ```
+    pub fn next(&self, handle: Handle) -> Option<Handle> {
+        let index = self.raw.next(&self.resolve(handle)?)?;
+        Some(self.handle(index))
+    }
+
+    /// Returns the handle of the previous value in ascending order, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let set: ArenaRBTreeSet<_> = (1..3).collect();
+    /// let last = set.last().unwrap();
+    /// assert_eq!(set.prev(last).and_then(|h| set.get(h)), Some(&1));
+    /// ```
+    pub fn prev(&self, handle: Handle) -> Option<Handle> {
+        let index = self.raw.prev(&self.resolve(handle)?)?;
+        Some(self.handle(index))
+    }
+
+    /// Returns the handle of the first value of the set, if any.
+    pub fn first(&self) -> Option<Handle> {
+        self.raw.first.map(|index| self.handle(index))
+    }
+
+    /// Returns the handle of the last value of the set, if any.
+    pub fn last(&self) -> Option<Handle> {
+        self.raw.last.map(|index| self.handle(index))
+    }
+
+    /// Returns a reference to the smallest value of the set, if not empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let set: ArenaRBTreeSet<_> = vec![3, 1, 2].into_iter().collect();
+    /// assert_eq!(set.peek_first(), Some(&1));
+    /// assert_eq!(set.peek_last(), Some(&3));
+    /// ```
+    pub fn peek_first(&self) -> Option<&T> {
+        Some(&self.raw.nodes.node(self.raw.first?).data)
+    }
+
+    /// Returns a reference to the greatest value of the set, if not empty.
+    pub fn peek_last(&self) -> Option<&T> {
+        Some(&self.raw.nodes.node(self.raw.last?).data)
+    }
+
+    /// Returns the handle of the value at the given position in the set, in ascending
+    /// order, if any.
+    ///
+    /// This operation is `O(log n)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let set: ArenaRBTreeSet<_> = vec![10, 30, 20].into_iter().collect();
+    /// assert_eq!(set.nth(1).and_then(|h| set.get(h)), Some(&20));
+    /// assert_eq!(set.nth(3), None);
+    /// ```
+    pub fn nth(&self, index: usize) -> Option<Handle> {
+        self.raw.nth(index).map(|index| self.handle(index))
+    }
+
+    /// Returns the summary of all the values of the set, if not empty.
+    ///
+    /// See [Augment] for more informations.
+    ///
+    /// [Augment]: trait.Augment.html
+    pub fn summary(&self) -> Option<A>
+    where
+        A: Clone,
+    {
+        self.raw.summary()
+    }
+
+    /// Clears the set, removing all values.
+    ///
+    /// This operation is `O(n)` for `n` values whatever the size of the arena, whose
+    /// memory is kept for the next insertions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let mut set: ArenaRBTreeSet<_> = (1..4).collect();
+    /// let handle = set.first().unwrap();
+    /// set.clear();
+    /// assert!(set.is_empty());
+    /// assert_eq!(set.get(handle), None);
+    /// ```
+    pub fn clear(&mut self) {
+        self.raw.clear();
+    }
+
+    /// Returns the number of values in the set.
+    pub fn len(&self) -> usize {
+        self.raw.length
+    }
+
+    /// Returns true if the set contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.raw.length == 0
+    }
+
+    /// Gets an iterator that visits the values in ascending order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let set: ArenaRBTreeSet<_> = vec![3, 1, 2].into_iter().collect();
+    /// assert_eq!(set.iter().collect::<Vec<_>>(), vec![&1, &2, &3]);
+    /// assert_eq!(set.iter().rev().collect::<Vec<_>>(), vec![&3, &2, &1]);
+    /// ```
+    pub fn iter(&self) -> ArenaIter<'_, T, A, C> {
+        ArenaIter {
+            set: self,
+            cursor: self.cursor(),
+        }
+    }
+
+    /// Returns a cursor over all the values of the set.
+    pub(crate) fn cursor(&self) -> Cursor {
+        Cursor {
+            front: self.raw.first,
+            back: self.raw.last,
+        }
+    }
+
+    /// Gets an iterator that visits the values in ascending order, starting from the
+    /// value designated by the handle. The iterator is empty for a stale handle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let set: ArenaRBTreeSet<_> = (1..5).collect();
+    /// let handle = set.get_node(&2).unwrap();
+    /// assert_eq!(set.iter_from(handle).collect::<Vec<_>>(), vec![&2, &3, &4]);
+    /// ```
+    pub fn iter_from(&self, handle: Handle) -> ArenaIter<'_, T, A, C> {
+        let front = self.resolve(handle);
+        ArenaIter {
+            set: self,
+            cursor: Cursor {
+                front,
+                back: front.and(self.raw.last),
+            },
+        }
+    }
+
+    /// Gets an iterator that visits the values in descending order, starting from the
+    /// value designated by the handle. The iterator is empty for a stale handle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let set: ArenaRBTreeSet<_> = (1..5).collect();
+    /// let handle = set.get_node(&3).unwrap();
+    /// assert_eq!(set.iter_rev_from(handle).collect::<Vec<_>>(), vec![&3, &2, &1]);
+    /// ```
+    pub fn iter_rev_from(&self, handle: Handle) -> Rev<ArenaIter<'_, T, A, C>> {
+        let back = self.resolve(handle);
+        ArenaIter {
+            set: self,
+            cursor: Cursor {
+                front: back.and(self.raw.first),
+                back,
+            },
+        }
+        .rev()
+    }
+}
+
+impl<T, A: Augment<T>, C> ArenaRBTreeSet<T, A, C> {
+    /// Removes the value designated by the handle from the set and returns it, if it
+    /// is still in the set.
+    ///
+    /// The handles of the other values remain valid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let mut set: ArenaRBTreeSet<_> = (1..4).collect();
+    /// let handle = set.get_node(&2).unwrap();
+    /// assert_eq!(set.remove_node(handle), Some(2));
+    /// assert_eq!(set.remove_node(handle), None);
+    /// assert_eq!(set.len(), 2);
+    /// ```
+    pub fn remove_node(&mut self, handle: Handle) -> Option<T> {
+        let index = self.resolve(handle)?;
+        Some(self.raw.remove(index))
+    }
+
+    /// Removes the smallest value from the set and returns it, if not empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let mut set: ArenaRBTreeSet<_> = vec![3, 1, 2].into_iter().collect();
+    /// assert_eq!(set.pop_first(), Some(1));
+    /// assert_eq!(set.pop_last(), Some(3));
+    /// assert_eq!(set.len(), 1);
+    /// ```
+    pub fn pop_first(&mut self) -> Option<T> {
+        let index = self.raw.first?;
+        Some(self.raw.remove(index))
+    }
+
+    /// Removes the greatest value from the set and returns it, if not empty.
+    pub fn pop_last(&mut self) -> Option<T> {
+        let index = self.raw.last?;
+        Some(self.raw.remove(index))
+    }
+
+    /// Retains only the values specified by the predicate, removing the values for
+    /// which `f` returns false. The values are visited in ascending order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let mut set: ArenaRBTreeSet<_> = (0..10).collect();
+    /// set.retain(|v| v % 3 == 0);
+    /// assert_eq!(set.iter().collect::<Vec<_>>(), vec![&0, &3, &6, &9]);
+    /// ```
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.raw.retain(f)
+    }
+
+    /// Removes all the values of the set, returning them in ascending order.
+    ///
+    /// The set is left empty even if the iterator is dropped before being consumed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let mut set: ArenaRBTreeSet<_> = (0..5).collect();
+    /// assert_eq!(set.drain().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+    /// assert!(set.is_empty());
+    /// ```
+    pub fn drain(&mut self) -> ArenaDrain<'_, T, A, C> {
+        ArenaDrain {
+            next: self.raw.first,
+            remaining: self.raw.length,
+            set: self,
+        }
+    }
+
+    /// Creates an iterator removing and returning the values for which the predicate
+    /// returns true, in ascending order.
+    ///
+    /// The values not visited when the iterator is dropped are kept in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let mut set: ArenaRBTreeSet<_> = (0..10).collect();
+    /// let evens: Vec<_> = set.extract_if(|v| v % 2 == 0).collect();
+    /// assert_eq!(evens, vec![0, 2, 4, 6, 8]);
+    /// assert_eq!(set.len(), 5);
+    /// ```
+    pub fn extract_if<F>(&mut self, pred: F) -> ArenaExtractIf<'_, T, A, C, F>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        ArenaExtractIf {
+            next: self.raw.first,
+            set: self,
+            pred,
+        }
+    }
+}
+
+impl<T: Ord> ArenaRBTreeSet<T> {
+    /// Makes a new `ArenaRBTreeSet`.
+    pub fn new() -> ArenaRBTreeSet<T> {
+        Self::default()
+    }
+
+    /// Makes a new `ArenaRBTreeSet` able to hold `capacity` values without reallocating.
+    pub fn with_capacity(capacity: usize) -> ArenaRBTreeSet<T> {
+        let mut set = Self::default();
+        set.raw.nodes.slots.reserve(capacity);
+        set
+    }
+}
+
+impl<T: Ord, A: Augment<T>> ArenaRBTreeSet<T, A> {
+    /// Makes a new `ArenaRBTreeSet` maintaining a summary of its values.
+    ///
+    /// See [Augment] for more informations.
+    ///
+    /// [Augment]: trait.Augment.html
+    pub fn new_augmented() -> ArenaRBTreeSet<T, A> {
+        Self::default()
+    }
+}
+
+impl<T, C: Compare<T>> ArenaRBTreeSet<T, (), C> {
+    /// Makes a new `ArenaRBTreeSet` ordering its values with the given comparator.
+    ///
+    /// See [Compare] for more informations.
+    ///
+    /// [Compare]: trait.Compare.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let mut set = ArenaRBTreeSet::with_comparator(|a: &i32, b: &i32| b.cmp(a));
+    /// set.extend(vec![1, 3, 2]);
+    /// assert_eq!(set.iter().collect::<Vec<_>>(), vec![&3, &2, &1]);
+    /// ```
+    pub fn with_comparator(comparator: C) -> ArenaRBTreeSet<T, (), C> {
+        ArenaRBTreeSet {
+            raw: RawTree::new(Slots::new(), comparator),
+        }
+    }
+}
+
+impl<T, A: Augment<T>, C: Compare<T>> ArenaRBTreeSet<T, A, C> {
+    /// Makes a new `ArenaRBTreeSet` from values in ascending order.
+    ///
+    /// The tree is built balanced at once, making this operation `O(n)` instead of the
+    /// `O(n log n)` of successive insertions. Consecutive equivalent values are kept
+    /// once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the values are not in ascending order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let set: ArenaRBTreeSet<_> = ArenaRBTreeSet::from_sorted_iter(0..1000);
+    /// assert_eq!(set.len(), 1000);
+    /// assert_eq!(set.nth(500).and_then(|h| set.get(h)), Some(&500));
+    /// ```
+    pub fn from_sorted_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        C: Default,
+    {
+        let mut set = Self::default();
+        match set.raw.collect_sorted(iter) {
+            Some(values) => set.raw.assign(values),
+            None => panic!("values are not sorted in ArenaRBTreeSet::from_sorted_iter"),
+        }
+        set
+    }
+
+    /// Makes a new empty set ordered by a clone of the comparator of `self`.
+    pub(crate) fn new_like(&self) -> ArenaRBTreeSet<T, A, C>
+    where
+        C: Clone,
+    {
+        ArenaRBTreeSet {
+            raw: RawTree::new(Slots::new(), self.raw.comparator.clone()),
+        }
+    }
+
+    pub(crate) fn comparator(&self) -> &C {
+        &self.raw.comparator
+    }
+
+    /// Adds a value to the set.
+    ///
+    /// Returns the handle of the new value, or `None` if the value was already present.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let mut set = ArenaRBTreeSet::new();
+    /// assert!(set.insert(1).is_some());
+    /// assert!(set.insert(1).is_none());
+    /// ```
+    pub fn insert(&mut self, data: T) -> Option<Handle> {
+        let index = self.raw.insert(data)?;
+        Some(self.handle(index))
+    }
+
+    /// Returns the handle of the value matching the given value, if present.
+    ///
+    /// The value may be any borrowed form of the set's value type, but the ordering
+    /// on the borrowed form must match the ordering on the value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let set: ArenaRBTreeSet<_> = (1..4).collect();
+    /// let handle = set.get_node(&2).unwrap();
+    /// assert_eq!(set.get(handle), Some(&2));
+    /// assert_eq!(set.get_node(&4), None);
+    /// ```
+    pub fn get_node<Q>(&self, data: &Q) -> Option<Handle>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.raw.get(data).map(|index| self.handle(index))
+    }
+
+    /// Returns true if the set contains a value matching the given value.
+    ///
+    /// The value may be any borrowed form of the set's value type, but the ordering
+    /// on the borrowed form must match the ordering on the value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let set: ArenaRBTreeSet<String> = ["a", "b"].iter().map(|s| s.to_string()).collect();
+    /// assert!(set.contains("a"));
+    /// assert!(!set.contains("c"));
+    /// ```
+    pub fn contains<Q>(&self, data: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.get_node(data).is_some()
+    }
+
+    /// Removes a value from the set. Returns whether the value was present.
+    ///
+    /// The value may be any borrowed form of the set's value type, but the ordering
+    /// on the borrowed form must match the ordering on the value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let mut set: ArenaRBTreeSet<_> = (1..4).collect();
+    /// assert_eq!(set.remove(&2), true);
+    /// assert_eq!(set.remove(&2), false);
+    /// ```
+    pub fn remove<Q>(&mut self, data: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        match self.get_node(data) {
+            Some(handle) => self.remove_node(handle).is_some(),
+            None => false,
+        }
+    }
+
+    /// Returns the handle of the first value of the set matching the given lower
+    /// bound, if any.
+    ///
+    /// The value may be any borrowed form of the set's value type, but the ordering
+    /// on the borrowed form must match the ordering on the value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::ops::Bound;
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let set: ArenaRBTreeSet<_> = vec![1, 3, 5].into_iter().collect();
+    /// let get = |h| set.get(h);
+    /// assert_eq!(set.lower_bound(Bound::Included(&3)).and_then(get), Some(&3));
+    /// assert_eq!(set.lower_bound(Bound::Excluded(&3)).and_then(get), Some(&5));
+    /// assert_eq!(set.lower_bound(Bound::Excluded(&5)), None);
+    /// ```
+    pub fn lower_bound<Q>(&self, bound: Bound<&Q>) -> Option<Handle>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.raw.lower_bound(bound).map(|index| self.handle(index))
+    }
+
+    /// Returns the handle of the last value of the set matching the given upper bound,
+    /// if any.
+    ///
+    /// The value may be any borrowed form of the set's value type, but the ordering
+    /// on the borrowed form must match the ordering on the value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::ops::Bound;
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let set: ArenaRBTreeSet<_> = vec![1, 3, 5].into_iter().collect();
+    /// let get = |h| set.get(h);
+    /// assert_eq!(set.upper_bound(Bound::Included(&3)).and_then(get), Some(&3));
+    /// assert_eq!(set.upper_bound(Bound::Excluded(&3)).and_then(get), Some(&1));
+    /// assert_eq!(set.upper_bound(Bound::Excluded(&1)), None);
+    /// ```
+    pub fn upper_bound<Q>(&self, bound: Bound<&Q>) -> Option<Handle>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.raw.upper_bound(bound).map(|index| self.handle(index))
+    }
+
+    /// Returns the handle of the smallest value greater than or equal to the given
+    /// value, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let set: ArenaRBTreeSet<_> = vec![1, 3, 5].into_iter().collect();
+    /// let get = |h| set.get(h);
+    /// assert_eq!(set.ceiling(&2).and_then(get), Some(&3));
+    /// assert_eq!(set.floor(&2).and_then(get), Some(&1));
+    /// assert_eq!(set.successor_of(&3).and_then(get), Some(&5));
+    /// assert_eq!(set.predecessor_of(&3).and_then(get), Some(&1));
+    /// ```
+    pub fn ceiling<Q>(&self, data: &Q) -> Option<Handle>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.lower_bound(Bound::Included(data))
+    }
+
+    /// Returns the handle of the greatest value less than or equal to the given value,
+    /// if any.
+    pub fn floor<Q>(&self, data: &Q) -> Option<Handle>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.upper_bound(Bound::Included(data))
+    }
+
+    /// Returns the handle of the smallest value strictly greater than the given value,
+    /// if any. The value does not need to be present in the set.
+    pub fn successor_of<Q>(&self, data: &Q) -> Option<Handle>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.lower_bound(Bound::Excluded(data))
+    }
+
+    /// Returns the handle of the greatest value strictly less than the given value, if
+    /// any. The value does not need to be present in the set.
+    pub fn predecessor_of<Q>(&self, data: &Q) -> Option<Handle>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.upper_bound(Bound::Excluded(data))
+    }
+
+    /// Returns the number of values in the set that are strictly less than the given
+    /// value. The value does not need to be present in the set.
+    ///
+    /// The value may be any borrowed form of the set's value type, but the ordering
+    /// on the borrowed form must match the ordering on the value type.
+    ///
+    /// This operation is `O(log n)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let set: ArenaRBTreeSet<_> = vec![10, 30, 20].into_iter().collect();
+    /// assert_eq!(set.rank(&5), 0);
+    /// assert_eq!(set.rank(&25), 2);
+    /// ```
+    pub fn rank<Q>(&self, data: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.raw.rank(data)
+    }
+
+    /// Returns the combined summary of the values within the given range, if any.
+    ///
+    /// This operation is `O(log n)`, see [Augment] for more informations.
+    ///
+    /// [Augment]: trait.Augment.html
+    ///
+    /// # Panics
+    ///
+    /// Panics if range `start > end`.
+    /// Panics if range `start == end` and both bounds are `Excluded`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::{Augment, ArenaRBTreeSet};
+    ///
+    /// #[derive(Debug, Clone, PartialEq)]
+    /// struct Sum(u32);
+    ///
+    /// impl Augment<u32> for Sum {
+    ///     fn summarize(data: &u32) -> Sum {
+    ///         Sum(*data)
+    ///     }
+    ///
+    ///     fn combine(&self, other: &Sum) -> Sum {
+    ///         Sum(self.0 + other.0)
+    ///     }
+    /// }
+    ///
+    /// let set: ArenaRBTreeSet<u32, Sum> = (1..=10).collect();
+    /// assert_eq!(set.summary(), Some(Sum(55)));
+    /// assert_eq!(set.fold_range(3..6), Some(Sum(12)));
+    /// assert_eq!(set.fold_range(20..), None);
+    /// ```
+    pub fn fold_range<R>(&self, range: R) -> Option<A>
+    where
+        R: RangeBounds<T>,
+    {
+        self.raw.fold_range(range)
+    }
+
+    /// Gets an iterator that visits the values in ascending order, restricted to the
+    /// values within the given range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if range `start > end`.
+    /// Panics if range `start == end` and both bounds are `Excluded`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let set: ArenaRBTreeSet<_> = vec![1, 3, 5, 7].into_iter().collect();
+    /// assert_eq!(set.range(2..7).collect::<Vec<_>>(), vec![&3, &5]);
+    /// assert_eq!(set.range(4..5).next(), None);
+    /// ```
+    pub fn range<R>(&self, range: R) -> ArenaIter<'_, T, A, C>
+    where
+        R: RangeBounds<T>,
+    {
+        let bounds = self.raw.range_bounds(&range);
+        ArenaIter {
+            set: self,
+            cursor: Cursor {
+                front: bounds.map(|(first, _)| first),
+                back: bounds.map(|(_, last)| last),
+            },
+        }
+    }
+
+    /// Removes the values of the given range from the set, returning them in ascending
+    /// order.
+    ///
+    /// The values of the range are removed even if the iterator is dropped before
+    /// being consumed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if range `start > end`.
+    /// Panics if range `start == end` and both bounds are `Excluded`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let mut set: ArenaRBTreeSet<_> = (0..10).collect();
+    /// assert_eq!(set.drain_range(3..6).collect::<Vec<_>>(), vec![3, 4, 5]);
+    /// set.drain_range(8..);
+    /// assert_eq!(set.iter().collect::<Vec<_>>(), vec![&0, &1, &2, &6, &7]);
+    /// ```
+    pub fn drain_range<R>(&mut self, range: R) -> ArenaDrain<'_, T, A, C>
+    where
+        R: RangeBounds<T>,
+    {
+        let bounds = self.raw.range_bounds(&range);
+        ArenaDrain {
+            next: bounds.map(|(first, _)| first),
+            remaining: bounds.map_or(0, |(first, last)| self.raw.count_between(&first, &last)),
+            set: self,
+        }
+    }
+
+    /// Optimize the set by merging values where applicable while keeping the ordering.
+    ///
+    /// Two values can be merged together when [consecutive], see
+    /// [RBTreeSet::repack].
+    ///
+    /// [consecutive]: trait.Consecutive.html
+    /// [RBTreeSet::repack]: struct.RBTreeSet.html#method.repack
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::{ArenaRBTreeSet, Span};
+    ///
+    /// let mut set = ArenaRBTreeSet::new();
+    /// set.insert(Span(1..3));
+    /// set.insert(Span(3..5));
+    /// set.insert(Span(7..9));
+    ///
+    /// set.repack();
+    /// assert_eq!(set.iter().map(|s| s.0.clone()).collect::<Vec<_>>(), vec![1..5, 7..9]);
+    /// ```
+    pub fn repack(&mut self)
+    where
+        T: Consecutive,
+    {
+        self.repack_counted();
+    }
+
+    /// Optimize the set by merging values where applicable, like [repack]. Returns the
+    /// number of merged values, that is the decrease of the length of the set.
+    ///
+    /// [repack]: #method.repack
+    pub fn repack_counted(&mut self) -> usize
+    where
+        T: Consecutive,
+    {
+        self.raw.repack_counted()
+    }
+
+    /// Merges the [consecutive] values within the given range, leaving the rest of the
+    /// set untouched. Returns the number of merged values.
+    ///
+    /// See [RBTreeSet::repack_range].
+    ///
+    /// [consecutive]: trait.Consecutive.html
+    /// [RBTreeSet::repack_range]: struct.RBTreeSet.html#method.repack_range
+    ///
+    /// # Panics
+    ///
+    /// Panics if range `start > end`.
+    /// Panics if range `start == end` and both bounds are `Excluded`.
+    pub fn repack_range<R>(&mut self, range: R) -> usize
+    where
+        R: RangeBounds<T>,
+        T: Consecutive,
+    {
+        self.raw.repack_range(range)
+    }
+
+    /// Merges the value designated by the handle with its [consecutive] predecessors
+    /// and successors. Returns the number of merged values, `0` for a stale handle.
+    ///
+    /// See [RBTreeSet::repack_around].
+    ///
+    /// [consecutive]: trait.Consecutive.html
+    /// [RBTreeSet::repack_around]: struct.RBTreeSet.html#method.repack_around
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::{ArenaRBTreeSet, Span};
+    ///
+    /// let mut set = ArenaRBTreeSet::new();
+    /// set.insert(Span(1..3));
+    /// set.insert(Span(5..8));
+    /// let handle = set.insert(Span(3..5)).unwrap();
+    ///
+    /// assert_eq!(set.repack_around(handle), 2);
+    /// assert_eq!(set.peek_first().unwrap().0, 1..8);
+    /// ```
+    pub fn repack_around(&mut self, handle: Handle) -> usize
+    where
+        T: Consecutive,
+    {
+        match self.resolve(handle) {
+            Some(index) => self.raw.repack_around(index),
+            None => 0,
+        }
+    }
+
+    /// Adds a value to the set, merging it with its [consecutive] and overlapping
+    /// neighbours.
+    ///
+    /// Returns the handle of the merged value, or `None` if the value was already
+    /// covered by a value of the set or is empty. See [RBTreeSet::insert_coalescing].
+    ///
+    /// [consecutive]: trait.Consecutive.html
+    /// [RBTreeSet::insert_coalescing]: struct.RBTreeSet.html#method.insert_coalescing
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::{ArenaRBTreeSet, Span};
+    ///
+    /// let mut set = ArenaRBTreeSet::new();
+    /// set.insert_coalescing(Span(1..3));
+    /// set.insert_coalescing(Span(5..8));
+    ///
+    /// let handle = set.insert_coalescing(Span(2..6)).unwrap();
+    /// assert_eq!(set.get(handle).unwrap().0, 1..8);
+    /// assert!(set.insert_coalescing(Span(4..7)).is_none());
+    /// ```
+    pub fn insert_coalescing(&mut self, data: T) -> Option<Handle>
+    where
+        T: Consecutive + Splittable,
+    {
+        let index = self.raw.insert_coalescing(data)?;
+        Some(self.handle(index))
+    }
+
+    /// Removes the parts of the values of the set covered by the given range. Returns
+    /// whether a value was overlapping the range.
+    ///
+    /// See [RBTreeSet::remove_range].
+    ///
+    /// [RBTreeSet::remove_range]: struct.RBTreeSet.html#method.remove_range
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::{ArenaRBTreeSet, Span};
+    ///
+    /// let mut set = ArenaRBTreeSet::new();
+    /// set.insert(Span(1..5));
+    /// set.insert(Span(7..9));
+    ///
+    /// assert!(set.remove_range(&Span(3..8)));
+    /// assert_eq!(set.iter().map(|s| s.0.clone()).collect::<Vec<_>>(), vec![1..3, 8..9]);
+    /// ```
+    pub fn remove_range(&mut self, range: &T) -> bool
+    where
+        T: Splittable,
+    {
+        self.raw.remove_range(range)
+    }
+
+    /// Makes a new `ArenaRBTreeSet` from the values of `left`, the pivot and the values
+    /// of `right`, keeping the comparator of `left`.
+    ///
+    /// The values of the smallest set are moved to the arena of the other one, making
+    /// this operation `O(min(n, m) + log(n + m))`. The handles of the moved values are
+    /// no longer valid.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the values of `left` are not all less than the pivot or the values of
+    /// `right` not all greater than the pivot.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let left: ArenaRBTreeSet<_> = (0..10).collect();
+    /// let right: ArenaRBTreeSet<_> = (11..100).collect();
+    /// let set = ArenaRBTreeSet::join(left, 10, right);
+    /// assert_eq!(set.len(), 100);
+    /// assert_eq!(set.nth(10).and_then(|h| set.get(h)), Some(&10));
+    /// ```
+    pub fn join(mut left: Self, pivot: T, mut right: Self) -> Self {
+        if !left.raw.ordered_around(&pivot, &right.raw) {
+            panic!("values are not ordered around the pivot in ArenaRBTreeSet::join");
+        }
+        if left.len() >= right.len() {
+            let values = right.take_values();
+            let subtree = left.raw.build(values);
+            let pivot = left.raw.alloc(pivot);
+            let lower = left.raw.take_root();
+            left.raw.join(lower, pivot, subtree);
+            left
+        } else {
+            let values = left.take_values();
+            let subtree = right.raw.build(values);
+            let pivot = right.raw.alloc(pivot);
+            let upper = right.raw.take_root();
+            right.raw.join(subtree, pivot, upper);
+            std::mem::swap(&mut right.raw.comparator, &mut left.raw.comparator);
+            right
+        }
+    }
+
+    /// Splits the set in two at the given value. Returns everything after the given
+    /// value, including the value, the set keeping the values before it.
+    ///
+    /// The tree is split in `O(log n)`, then the `k` values of the returned set are
+    /// moved to its own arena in `O(k)`. The handles of the moved values are no longer
+    /// valid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let mut a: ArenaRBTreeSet<_> = (0..10).collect();
+    /// let b = a.split_off(&4);
+    /// assert_eq!(a.iter().collect::<Vec<_>>(), vec![&0, &1, &2, &3]);
+    /// assert_eq!(b.iter().collect::<Vec<_>>(), vec![&4, &5, &6, &7, &8, &9]);
+    /// ```
+    pub fn split_off(&mut self, data: &T) -> Self
+    where
+        C: Clone,
+    {
+        let mut moved = Vec::new();
+        for (pivot, subtree) in self.raw.split_off(data) {
+            moved.push(self.raw.nodes.release(pivot));
+            for index in raw::in_order(&self.raw.nodes, subtree) {
+                moved.push(self.raw.nodes.release(index));
+            }
+        }
+        let mut other = self.new_like();
+        other.raw.assign(moved);
+        other
+    }
+
+    /// Splits the set in two at the given value, the first set holding the values
+    /// before it and the second one the values after it, including the value.
+    ///
+    /// See [split_off](#method.split_off).
+    pub fn split_at(mut self, data: &T) -> (Self, Self)
+    where
+        C: Clone,
+    {
+        let other = self.split_off(data);
+        (self, other)
+    }
+
+    /// Moves all the values of `other` into the set, leaving `other` empty.
+    ///
+    /// When the values of one set are all less than the values of the other one, the
+    /// values of `other` are joined to the set in `O(m + log n)`. Otherwise they are
+    /// inserted one by one, the ones matching a value of the set being dropped. The
+    /// handles of the values of `other` are no longer valid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let mut a: ArenaRBTreeSet<_> = (0..5).collect();
+    /// let mut b: ArenaRBTreeSet<_> = (5..10).collect();
+    /// a.append(&mut b);
+    /// assert_eq!(a.len(), 10);
+    /// assert!(b.is_empty());
+    /// ```
+    pub fn append(&mut self, other: &mut Self) {
+        let values = other.take_values();
+        self.raw.append_run(values);
+    }
+}
+
+impl<T: Clone, A: Clone, C: Clone> Clone for ArenaRBTreeSet<T, A, C> {
+    fn clone(&self) -> Self {
+        // the indices stay valid in a copy of the slots
+        let raw = &self.raw;
+        ArenaRBTreeSet {
+            raw: RawTree {
+                nodes: raw.nodes.clone(),
+                root: raw.root,
+                first: raw.first,
+                last: raw.last,
+                length: raw.length,
+                comparator: raw.comparator.clone(),
+            },
+        }
+    }
+}
+
+impl<T, A, C: Default> Default for ArenaRBTreeSet<T, A, C> {
+    fn default() -> Self {
+        ArenaRBTreeSet {
+            raw: RawTree::new(Slots::new(), C::default()),
+        }
+    }
+}
+
+impl<T, A, C> fmt::Debug for ArenaRBTreeSet<T, A, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ArenaRBTreeSet {{ length: {} }}", self.raw.length)
+    }
+}
+
+impl<T, A: Augment<T>, C: Compare<T> + Default> FromIterator<T> for ArenaRBTreeSet<T, A, C> {
+    /// Builds each run of ascending values at once, like [from_sorted_iter], and
+    /// joins the runs when they do not interleave, inserting their values otherwise.
+    ///
+    /// [from_sorted_iter]: struct.ArenaRBTreeSet.html#method.from_sorted_iter
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut s = Self::default();
+        s.raw.extend_from_iter(iter.into_iter());
+        s
+    }
+}
+
+impl<T, A: Augment<T>, C: Compare<T>> Extend<T> for ArenaRBTreeSet<T, A, C> {
+    /// Builds each run of ascending values at once, like [from_sorted_iter], and
+    /// joins it to the set when its values are all less or greater than the values of
+    /// the set, inserting them otherwise.
+    ///
+    /// [from_sorted_iter]: struct.ArenaRBTreeSet.html#method.from_sorted_iter
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.raw.extend_from_iter(iter.into_iter());
+    }
+}
+
+/// Created with the method [iter] or with [iter_from], [iter_rev_from] and [range] for
+/// partial iterations.
+///
+/// [iter]: struct.ArenaRBTreeSet.html#method.iter
+/// [iter_from]: struct.ArenaRBTreeSet.html#method.iter_from
+/// [iter_rev_from]: struct.ArenaRBTreeSet.html#method.iter_rev_from
+/// [range]: struct.ArenaRBTreeSet.html#method.range
+pub struct ArenaIter<'a, T, A = (), C = Natural> {
+    set: &'a ArenaRBTreeSet<T, A, C>,
+    cursor: Cursor,
+}
+
+impl<'a, T, A, C> Iterator for ArenaIter<'a, T, A, C> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.cursor.next(self.set)
+    }
+}
+
+impl<'a, T, A, C> DoubleEndedIterator for ArenaIter<'a, T, A, C> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        self.cursor.next_back(self.set)
+    }
+}
+
+/// The values left to visit by an iteration, from `front` to `back` included. The
+/// set is given at each step, so that it can be held behind a lock guard.
+pub(crate) struct Cursor {
+    front: Option<usize>,
+    back: Option<usize>,
+}
+
+impl Cursor {
+    pub(crate) fn next<'a, T, A, C>(&mut self, set: &'a ArenaRBTreeSet<T, A, C>) -> Option<&'a T> {
+        let index = self.front.take()?;
+        if self.back == Some(index) {
+            self.back = None;
+        } else {
+            self.front = set.raw.next(&index);
+        }
+        Some(&set.raw.nodes.node(index).data)
+    }
+
+    pub(crate) fn next_back<'a, T, A, C>(
+        &mut self,
+        set: &'a ArenaRBTreeSet<T, A, C>,
+    ) -> Option<&'a T> {
+        let index = self.back.take()?;
+        if self.front == Some(index) {
+            self.front = None;
+        } else {
+            self.back = set.raw.prev(&index);
+        }
+        Some(&set.raw.nodes.node(index).data)
+    }
+}
+
+/// Created with the method [drain] or with [drain_range] to remove a range of values.
+///
+/// [drain]: struct.ArenaRBTreeSet.html#method.drain
+/// [drain_range]: struct.ArenaRBTreeSet.html#method.drain_range
+pub struct ArenaDrain<'a, T, A: Augment<T> = (), C = Natural> {
+    set: &'a mut ArenaRBTreeSet<T, A, C>,
+    next: Option<usize>,
+    remaining: usize,
+}
+
+impl<T, A: Augment<T>, C> Iterator for ArenaDrain<'_, T, A, C> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.set.raw.drain_next(&mut self.next, &mut self.remaining)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T, A: Augment<T>, C> Drop for ArenaDrain<'_, T, A, C> {
+    fn drop(&mut self) {
+        self.for_each(drop);
+    }
+}
+
+/// Created with the method [extract_if].
+///
+/// [extract_if]: struct.ArenaRBTreeSet.html#method.extract_if
+pub struct ArenaExtractIf<'a, T, A, C, F> {
+    set: &'a mut ArenaRBTreeSet<T, A, C>,
+    next: Option<usize>,
+    pred: F,
+}
+
+impl<T, A, C, F> Iterator for ArenaExtractIf<'_, T, A, C, F>
+where
+    A: Augment<T>,
+    F: FnMut(&T) -> bool,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.set.raw.extract_next(&mut self.next, &mut self.pred)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::{Containment, Span};
+
+    fn size<T, A, C>(set: &ArenaRBTreeSet<T, A, C>, index: Option<usize>) -> usize {
+        index.map_or(0, |i| set.raw.nodes.node(i).size)
+    }
+
+    fn is_red<T, A, C>(set: &ArenaRBTreeSet<T, A, C>, index: Option<usize>) -> bool {
+        matches!(index, Some(i) if set.raw.nodes.node(i).colour == Colour::Red)
+    }
+
+    /// Returns the black height of the subtree, checking the RB-Tree invariants and
+    /// the cached sizes.
+    fn validate_subtree<T: Ord, A, C>(
+        set: &ArenaRBTreeSet<T, A, C>,
+        index: Option<usize>,
+    ) -> usize {
+        let index = match index {
+            Some(i) => i,
+            None => return 1,
+        };
+        let node = set.raw.nodes.node(index);
+        for child in [node.left, node.right].iter().flatten() {
+            assert_eq!(set.raw.nodes.node(*child).parent, Some(index));
+            assert!(!(is_red(set, Some(index)) && is_red(set, Some(*child))));
+        }
+        if let Some(left) = node.left {
+            assert!(set.raw.nodes.node(left).data < node.data);
+        }
+        if let Some(right) = node.right {
+            assert!(set.raw.nodes.node(right).data > node.data);
+        }
+        assert_eq!(node.size, size(set, node.left) + size(set, node.right) + 1);
+        let left = validate_subtree(set, node.left);
+        let right = validate_subtree(set, node.right);
+        assert_eq!(left, right);
+        left + if is_red(set, Some(index)) { 0 } else { 1 }
+    }
+
+    fn validate_tree<T: Ord, A, C>(set: &ArenaRBTreeSet<T, A, C>) {
+        assert!(!is_red(set, set.raw.root));
+        assert_eq!(size(set, set.raw.root), set.len());
+        validate_subtree(set, set.raw.root);
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Sum(i64);
+
+    impl Augment<i64> for Sum {
+        fn summarize(data: &i64) -> Sum {
+            Sum(*data)
+        }
+
+        fn combine(&self, other: &Sum) -> Sum {
+            Sum(self.0 + other.0)
+        }
+    }
+
+    #[test]
+    fn insert_remove() {
+        let mut set = ArenaRBTreeSet::new();
+        let mut oracle = BTreeSet::new();
+        for i in 0..200 {
+            let value = (i * 37) % 101;
+            assert_eq!(set.insert(value).is_some(), oracle.insert(value));
+            validate_tree(&set);
+        }
+        for i in 0..150 {
+            let value = (i * 53) % 113;
+            assert_eq!(set.remove(&value), oracle.remove(&value));
+            validate_tree(&set);
+        }
+        assert_eq!(set.len(), oracle.len());
+        assert!(set.iter().eq(oracle.iter()));
+        assert!(set.iter().rev().eq(oracle.iter().rev()));
+    }
+
+    #[test]
+    fn handles() {
+        let mut set: ArenaRBTreeSet<_> = (0..10).collect();
+        let handles: Vec<_> = (0..10).map(|i| set.get_node(&i).unwrap()).collect();
+        assert_eq!(set.remove_node(handles[4]), Some(4));
+
+        // the slot gets reused but the old handle stays invalid
+        let new = set.insert(42).unwrap();
+        assert_eq!(new.index, handles[4].index);
+        assert_eq!(set.get(handles[4]), None);
+        assert_eq!(set.next(handles[4]), None);
+        assert_eq!(set.get(new), Some(&42));
+
+        // the other handles are untouched by the rotations
+        for (i, handle) in handles.iter().enumerate().filter(|(i, _)| *i != 4) {
+            assert_eq!(set.get(*handle), Some(&i));
+        }
+        assert_eq!(set.next(handles[3]), Some(handles[5]));
+        assert_eq!(set.prev(handles[5]), Some(handles[3]));
+        validate_tree(&set);
+
+        // a clone resolves the handles to its own copies of the values
+        let mut copy = set.clone();
+        assert_eq!(copy.remove_node(handles[3]), Some(3));
+        assert_eq!(set.get(handles[3]), Some(&3));
+        assert_eq!(copy.get(new), Some(&42));
+    }
+
+    #[test]
+    fn bounds() {
+        let values = vec![2, 6, 7, 8, 10, 11, 13, 18, 22, 26];
+        let set: ArenaRBTreeSet<_> = values.iter().cloned().collect();
+        let oracle: BTreeSet<_> = values.iter().cloned().collect();
+        let data = |h: Option<Handle>| h.and_then(|h| set.get(h)).cloned();
+
+        for i in 0..30 {
+            assert_eq!(
+                data(set.lower_bound(Bound::Included(&i))),
+                oracle.range(i..).next().cloned()
+            );
+            assert_eq!(
+                data(set.upper_bound(Bound::Excluded(&i))),
+                oracle.range(..i).next_back().cloned()
+            );
+            for j in i..30 {
+                assert!(set.range(i..j).eq(oracle.range(i..j)));
+                assert!(set.range(i..=j).rev().eq(oracle.range(i..=j).rev()));
+            }
+            if let Some(handle) = set.get_node(&i) {
+                assert!(set.iter_from(handle).eq(oracle.range(i..)));
+                assert!(set.iter_rev_from(handle).eq(oracle.range(..=i).rev()));
+            }
+        }
+
+        let mut words = ArenaRBTreeSet::with_comparator(|a: &String, b: &String| {
+            a.to_lowercase().cmp(&b.to_lowercase())
+        });
+        words.extend(vec!["b".to_owned(), "A".to_owned(), "a".to_owned()]);
+        assert_eq!(words.iter().collect::<Vec<_>>(), vec!["A", "b"]);
+        let stale = words.get_node(&"b".to_owned()).unwrap();
+        words.remove(&"B".to_owned());
+        assert_eq!(words.iter_from(stale).next(), None);
+
+        let mut spans = ArenaRBTreeSet::with_comparator(Containment);
+        spans.insert(Span(0..10));
+        spans.extend(vec![Span(2..3)]);
+        spans.extend(vec![Span(0..1)]);
+        validate_tree(&spans);
+        assert_eq!(spans.iter().collect::<Vec<_>>(), vec![&Span(0..10)]);
+    }
+
+    #[test]
+    fn clear() {
+        let mut set: ArenaRBTreeSet<_> = (0..100).collect();
+        for i in 10..100 {
+            set.remove(&i);
+        }
+        let handles: Vec<_> = (0..10).map(|i| set.get_node(&i).unwrap()).collect();
+        set.clear();
+        assert!(set.is_empty());
+        assert_eq!(set.raw.nodes.free.len(), 100);
+
+        let new: Vec<_> = (0..100).map(|i| set.insert(i).unwrap()).collect();
+        assert_eq!(set.raw.nodes.slots.len(), 100);
+        assert!(handles.iter().all(|h| set.get(*h).is_none()));
+        assert!(new.iter().all(|h| set.get(*h).is_some()));
+        validate_tree(&set);
+    }
+
+    #[test]
+    fn saturated_generation() {
+        let mut set = ArenaRBTreeSet::new();
+        set.insert(1);
+        set.raw.nodes.slots[0].generation = u32::MAX - 1;
+        let old = set.get_node(&1).unwrap();
+        set.remove(&1);
+        let last = set.insert(2).unwrap();
+        assert_eq!(set.raw.nodes.slots[0].generation, u32::MAX);
+        set.remove(&2);
+        assert!(set.raw.nodes.free.is_empty());
+
+        let new = set.insert(3).unwrap();
+        assert_eq!(set.raw.nodes.slots.len(), 2);
+        assert_eq!(set.get(old), None);
+        assert_eq!(set.get(last), None);
+        assert_eq!(set.get(new), Some(&3));
+    }
+
+    #[test]
+    fn order_statistics() {
+        let mut set: ArenaRBTreeSet<i64, Sum> = ArenaRBTreeSet::new_augmented();
+        let mut oracle = BTreeSet::new();
+        for i in 0..100 {
+            let value = (i * 37) % 101;
+            set.insert(value);
+            oracle.insert(value);
+            if i % 3 == 0 {
+                let value = (i * 53) % 101;
+                set.remove(&value);
+                oracle.remove(&value);
+            }
+        }
+        validate_tree(&set);
+        let values: Vec<i64> = oracle.iter().cloned().collect();
+        for (i, value) in values.iter().enumerate() {
+            assert_eq!(set.nth(i).and_then(|h| set.get(h)), Some(value));
+            assert_eq!(set.rank(value), i);
+        }
+        assert_eq!(set.nth(values.len()), None);
+        assert_eq!(set.summary(), Some(Sum(values.iter().sum())));
+        for (start, end) in [(0, 101), (10, 20), (33, 34), (50, 50), (90, 200)] {
+            let expected: i64 = oracle.range(start..end).sum();
+            let folded = set.fold_range(start..end).map(|s| s.0);
+            assert_eq!(folded.unwrap_or(0), expected);
+        }
+    }
+
+    #[test]
+    fn bulk_removals() {
+        let mut set: ArenaRBTreeSet<i64, Sum> = (0..50).collect();
+        set.retain(|v| v % 5 != 0);
+        validate_tree(&set);
+        assert_eq!(set.len(), 40);
+        assert_eq!(set.drain_range(10..20).count(), 8);
+        let odds: Vec<_> = set.extract_if(|v| v % 2 == 1).collect();
+        assert_eq!(odds.len(), 16);
+        validate_tree(&set);
+        assert_eq!(set.pop_first(), Some(2));
+        assert_eq!(set.pop_last(), Some(48));
+        assert_eq!(set.summary().map(|s| s.0), Some(set.iter().sum()));
+
+        // dropping a drain still empties the set, the slots are reused
+        let slots = set.raw.nodes.slots.len();
+        drop(set.drain());
+        assert!(set.is_empty());
+        set.extend(0..slots as i64);
+        assert_eq!(set.raw.nodes.slots.len(), slots);
+        validate_tree(&set);
+    }
+
+    #[test]
+    fn join_split() {
+        for left_length in [0, 1, 2, 7, 100] {
+            for right_length in [0, 1, 3, 40, 300] {
+                let left: ArenaRBTreeSet<i64, Sum> = (0..left_length).rev().collect();
+                let right: ArenaRBTreeSet<i64, Sum> =
+                    (left_length + 1..left_length + 1 + right_length).collect();
+                let mut set = ArenaRBTreeSet::join(left, left_length, right);
+                validate_tree(&set);
+                let length = left_length + 1 + right_length;
+                assert!(set.iter().cloned().eq(0..length));
+                assert_eq!(set.summary(), Some(Sum((0..length).sum())));
+
+                for at in [0, left_length / 2, length] {
+                    let mut left = set.clone();
+                    let right = left.split_off(&at);
+                    validate_tree(&left);
+                    validate_tree(&right);
+                    assert!(left.iter().cloned().eq(0..at));
+                    assert!(right.iter().cloned().eq(at..length));
+                }
+
+                let mut other: ArenaRBTreeSet<i64, Sum> = (length..length + 10).collect();
+                set.append(&mut other);
+                validate_tree(&set);
+                assert!(other.is_empty());
+                assert_eq!(set.len() as i64, length + 10);
+            }
+        }
+
+        let runs: ArenaRBTreeSet<i64> = (100..200).chain(0..50).chain(150..300).collect();
+        validate_tree(&runs);
+        assert!(runs.iter().cloned().eq((0..50).chain(100..300)));
+    }
+
+    #[test]
+    fn repacking() {
+        let mut set = ArenaRBTreeSet::new();
+        let mut covered = [false; 64];
+        for i in 0..64 {
+            let start = (i * 23) % 64;
+            set.insert_coalescing(Span(start..start + 1));
+            covered[start] = true;
+            let values: Vec<_> = set.iter().map(|s| s.0.clone()).collect();
+            assert!(values.windows(2).all(|w| w[0].end < w[1].start));
+            assert_eq!(values.iter().map(|r| r.len()).sum::<usize>(), i + 1);
+        }
+        assert_eq!(set.len(), 1);
+
+        assert!(set.remove_range(&Span(10..20)));
+        assert!(!set.remove_range(&Span(12..14)));
+        assert_eq!(
+            set.iter().map(|s| s.0.clone()).collect::<Vec<_>>(),
+            vec![0..10, 20..64]
+        );
+
+        let spans = (0..10).filter(|i| *i != 6).map(|i| Span(i..i + 1));
+        let mut set: ArenaRBTreeSet<_> = spans.collect();
+        assert_eq!(set.repack_range(Span(2..3)..Span(5..6)), 2);
+        assert_eq!(set.len(), 7);
+        let handle = set.get_node(&Span(8..9)).unwrap();
+        assert_eq!(set.repack_around(handle), 2);
+        assert_eq!(set.repack_counted(), 3);
+        assert_eq!(
+            set.iter().map(|s| s.0.clone()).collect::<Vec<_>>(),
+            vec![0..6, 7..10]
+        );
+        validate_tree(&set);
+    }
+}
@@ -0,0 +1,343 @@
+use crate::node::Colour;
+
+/// The links of the nodes of a red-black tree, whatever their storage.
+///
+/// The balancing algorithms of this module only walk and relink the nodes through
+/// this interface, implemented by [RawTree] for every storage of the nodes.
+///
+/// [RawTree]: ../raw/struct.RawTree.html
+pub(crate) trait Links {
+    /// A reference to a node.
+    type Link: PartialEq;
+
+    fn duplicate(link: &Self::Link) -> Self::Link;
+
+    fn root(&self) -> Option<Self::Link>;
+    fn set_root(&mut self, root: Option<Self::Link>);
+
+    fn parent(&self, node: &Self::Link) -> Option<Self::Link>;
+    fn set_parent(&mut self, node: &Self::Link, parent: Option<Self::Link>);
+    fn left(&self, node: &Self::Link) -> Option<Self::Link>;
+    fn set_left(&mut self, node: &Self::Link, left: Option<Self::Link>);
+    fn right(&self, node: &Self::Link) -> Option<Self::Link>;
+    fn set_right(&mut self, node: &Self::Link, right: Option<Self::Link>);
+
+    fn colour(&self, node: &Self::Link) -> Colour;
+    fn set_colour(&mut self, node: &Self::Link, colour: Colour);
+
+    /// Recomputes the informations cached by the node from its children.
+    fn refresh(&mut self, node: &Self::Link);
+    /// Recomputes the informations cached by the node and all its ancestors.
+    fn refresh_upward(&mut self, node: Option<Self::Link>);
+}
+
+fn is_red<L: Links>(tree: &L, node: Option<&L::Link>) -> bool {
+    matches!(node, Some(n) if tree.colour(n) == Colour::Red)
+}
+
+fn set_colour<L: Links>(tree: &mut L, node: Option<&L::Link>, colour: Colour) {
+    if let Some(n) = node {
+        tree.set_colour(n, colour);
+    }
+}
+
+fn is_left_child<L: Links>(tree: &L, node: &L::Link, parent: &L::Link) -> bool {
+    tree.left(parent).as_ref() == Some(node)
+}
+
+/// Makes `new` take the place of `old` in the children of `parent`.
+fn replace_child<L: Links>(
+    tree: &mut L,
+    parent: Option<&L::Link>,
+    old: &L::Link,
+    new: Option<L::Link>,
+) {
+    match parent {
+        None => tree.set_root(new),
+        Some(p) if is_left_child(tree, old, p) => tree.set_left(p, new),
+        Some(p) => tree.set_right(p, new),
+    }
+}
+
+/// Replaces the subtree rooted at `old` by the one rooted at `new`.
+fn transplant<L: Links>(tree: &mut L, old: &L::Link, new: Option<L::Link>) {
+    let parent = tree.parent(old);
+    if let Some(ref n) = new {
+        tree.set_parent(n, parent.as_ref().map(L::duplicate));
+    }
+    replace_child(tree, parent.as_ref(), old, new);
+}
+
+pub(crate) fn rotate_left<L: Links>(tree: &mut L, node: &L::Link) {
+    let pivot = tree.right(node).expect("rotation without pivot");
+    let inner = tree.left(&pivot);
+    if let Some(ref inner) = inner {
+        tree.set_parent(inner, Some(L::duplicate(node)));
+    }
+    tree.set_right(node, inner);
+    transplant(tree, node, Some(L::duplicate(&pivot)));
+    tree.set_left(&pivot, Some(L::duplicate(node)));
+    tree.set_parent(node, Some(L::duplicate(&pivot)));
+    tree.refresh(node);
+    tree.refresh(&pivot);
+}
+
+pub(crate) fn rotate_right<L: Links>(tree: &mut L, node: &L::Link) {
+    let pivot = tree.left(node).expect("rotation without pivot");
+    let inner = tree.right(&pivot);
+    if let Some(ref inner) = inner {
+        tree.set_parent(inner, Some(L::duplicate(node)));
+    }
+    tree.set_left(node, inner);
+    transplant(tree, node, Some(L::duplicate(&pivot)));
+    tree.set_right(&pivot, Some(L::duplicate(node)));
+    tree.set_parent(node, Some(L::duplicate(&pivot)));
+    tree.refresh(node);
+    tree.refresh(&pivot);
+}
+
+/// Restores the colours of the tree after the linking of the red node.
+///
+/// The informations cached by the node and its ancestors must be up to date.
+pub(crate) fn insert_fixup<L: Links>(tree: &mut L, node: L::Link) {
+    let mut node = node;
+    while let Some(parent) = tree.parent(&node) {
+        if tree.colour(&parent) == Colour::Black {
+            break;
+        }
+        // a red node is never the root
+        let grandparent = tree.parent(&parent).expect("red root");
+        let parent_is_left = is_left_child(tree, &parent, &grandparent);
+        let uncle = if parent_is_left {
+            tree.right(&grandparent)
+        } else {
+            tree.left(&grandparent)
+        };
+        if is_red(tree, uncle.as_ref()) {
+            tree.set_colour(&parent, Colour::Black);
+            set_colour(tree, uncle.as_ref(), Colour::Black);
+            tree.set_colour(&grandparent, Colour::Red);
+            node = grandparent;
+            continue;
+        }
+        let mut parent = parent;
+        if parent_is_left {
+            if !is_left_child(tree, &node, &parent) {
+                rotate_left(tree, &parent);
+                parent = node;
+            }
+            tree.set_colour(&parent, Colour::Black);
+            tree.set_colour(&grandparent, Colour::Red);
+            rotate_right(tree, &grandparent);
+        } else {
+            if is_left_child(tree, &node, &parent) {
+                rotate_right(tree, &parent);
+                parent = node;
+            }
+            tree.set_colour(&parent, Colour::Black);
+            tree.set_colour(&grandparent, Colour::Red);
+            rotate_left(tree, &grandparent);
+        }
+        break;
+    }
+    let root = tree.root();
+    set_colour(tree, root.as_ref(), Colour::Black);
+}
+
+/// Removes the node from the tree, relinking the other nodes without moving their
+/// values. The links of the removed node are left as is.
+pub(crate) fn unlink<L: Links>(tree: &mut L, node: &L::Link) {
+    let removed_colour;
+    let child;
+    let child_parent;
+    match (tree.left(node), tree.right(node)) {
+        (Some(left), Some(right)) => {
+            let mut next = L::duplicate(&right);
+            while let Some(left) = tree.left(&next) {
+                next = left;
+            }
+            removed_colour = tree.colour(&next);
+            child = tree.right(&next);
+            if next == right {
+                child_parent = Some(L::duplicate(&next));
+            } else {
+                child_parent = tree.parent(&next);
+                transplant(tree, &next, child.as_ref().map(L::duplicate));
+                tree.set_parent(&right, Some(L::duplicate(&next)));
+                tree.set_right(&next, Some(right));
+            }
+            transplant(tree, node, Some(L::duplicate(&next)));
+            tree.set_parent(&left, Some(L::duplicate(&next)));
+            tree.set_left(&next, Some(left));
+            let colour = tree.colour(node);
+            tree.set_colour(&next, colour);
+        }
+        (left, right) => {
+            removed_colour = tree.colour(node);
+            child = left.or(right);
+            child_parent = tree.parent(node);
+            transplant(tree, node, child.as_ref().map(L::duplicate));
+        }
+    }
+    tree.refresh_upward(child_parent.as_ref().map(L::duplicate));
+    if removed_colour == Colour::Black {
+        delete_fixup(tree, child, child_parent);
+    }
+}
+
+/// Restores the colours of the tree after the removal of a black node, `node` being
+/// the possibly missing child of `parent` short of a black node.
+fn delete_fixup<L: Links>(tree: &mut L, node: Option<L::Link>, parent: Option<L::Link>) {
+    let mut node = node;
+    let mut parent = parent;
+    while !is_red(tree, node.as_ref()) {
+        let p = match parent {
+            Some(p) => p,
+            None => break,
+        };
+        // the sibling exists since `node` is short of a black node
+        let node_is_left = tree.left(&p) == node;
+        let sibling_of = |tree: &L, p: &L::Link| {
+            if node_is_left {
+                tree.right(p)
+            } else {
+                tree.left(p)
+            }
+            .expect("missing sibling")
+        };
+        let rotate_towards_node = |tree: &mut L, n: &L::Link| {
+            if node_is_left {
+                rotate_left(tree, n)
+            } else {
+                rotate_right(tree, n)
+            }
+        };
+        let rotate_away_from_node = |tree: &mut L, n: &L::Link| {
+            if node_is_left {
+                rotate_right(tree, n)
+            } else {
+                rotate_left(tree, n)
+            }
+        };
+
+        let mut sibling = sibling_of(tree, &p);
+        if tree.colour(&sibling) == Colour::Red {
+            tree.set_colour(&sibling, Colour::Black);
+            tree.set_colour(&p, Colour::Red);
+            rotate_towards_node(tree, &p);
+            sibling = sibling_of(tree, &p);
+        }
+        let (near, far) = if node_is_left {
+            (tree.left(&sibling), tree.right(&sibling))
+        } else {
+            (tree.right(&sibling), tree.left(&sibling))
+        };
+        if !is_red(tree, near.as_ref()) && !is_red(tree, far.as_ref()) {
+            tree.set_colour(&sibling, Colour::Red);
+            parent = tree.parent(&p);
+            node = Some(p);
+            continue;
+        }
+        if !is_red(tree, far.as_ref()) {
+            set_colour(tree, near.as_ref(), Colour::Black);
+            tree.set_colour(&sibling, Colour::Red);
+            rotate_away_from_node(tree, &sibling);
+            sibling = sibling_of(tree, &p);
+        }
+        let colour = tree.colour(&p);
+        tree.set_colour(&sibling, colour);
+        tree.set_colour(&p, Colour::Black);
+        let far = if node_is_left {
+            tree.right(&sibling)
+        } else {
+            tree.left(&sibling)
+        };
+        set_colour(tree, far.as_ref(), Colour::Black);
+        rotate_towards_node(tree, &p);
+        node = tree.root();
+        break;
+    }
+    set_colour(tree, node.as_ref(), Colour::Black);
+}
+
+/// Returns the number of black nodes from the given node down to its leftmost leaf.
+fn black_height<L: Links>(tree: &L, node: Option<&L::Link>) -> usize {
+    let mut height = 0;
+    let mut tmp = node.map(L::duplicate);
+    while let Some(n) = tmp {
+        if tree.colour(&n) == Colour::Black {
+            height += 1;
+        }
+        tmp = tree.left(&n);
+    }
+    height
+}
+
+/// Joins two standalone subtrees with the pivot in between, making the result the
+/// root of the tree. The values of `left` are less than the pivot and the pivot less
+/// than the values of `right`, the pivot being a lone red node.
+///
+/// This operation is `O(log n)`.
+pub(crate) fn join<L: Links>(
+    tree: &mut L,
+    left: Option<L::Link>,
+    pivot: L::Link,
+    right: Option<L::Link>,
+) {
+    // Standalone subtrees are given black roots to compare their black heights.
+    set_colour(tree, left.as_ref(), Colour::Black);
+    set_colour(tree, right.as_ref(), Colour::Black);
+    let left_height = black_height(tree, left.as_ref());
+    let right_height = black_height(tree, right.as_ref());
+
+    // Descend the spine of the highest tree down to a black node of the same black
+    // height as the other tree, and replace it by the red pivot.
+    let left_is_higher = left_height >= right_height;
+    let (mut cursor, mut height, target) = if left_is_higher {
+        (left.as_ref().map(L::duplicate), left_height, right_height)
+    } else {
+        (right.as_ref().map(L::duplicate), right_height, left_height)
+    };
+    let mut parent = None;
+    while let Some(node) = cursor.as_ref().map(L::duplicate) {
+        if tree.colour(&node) == Colour::Black {
+            if height == target {
+                break;
+            }
+            height -= 1;
+        }
+        cursor = if left_is_higher {
+            tree.right(&node)
+        } else {
+            tree.left(&node)
+        };
+        parent = Some(node);
+    }
+    let (inner_left, inner_right, root) = if left_is_higher {
+        (cursor, right, left)
+    } else {
+        (left, cursor, right)
+    };
+    for child in inner_left.iter().chain(inner_right.iter()) {
+        tree.set_parent(child, Some(L::duplicate(&pivot)));
+    }
+    tree.set_left(&pivot, inner_left);
+    tree.set_right(&pivot, inner_right);
+    match parent {
+        Some(ref parent) => {
+            if left_is_higher {
+                tree.set_right(parent, Some(L::duplicate(&pivot)));
+            } else {
+                tree.set_left(parent, Some(L::duplicate(&pivot)));
+            }
+            tree.set_parent(&pivot, Some(L::duplicate(parent)));
+            tree.set_root(root);
+        }
+        None => {
+            tree.set_parent(&pivot, None);
+            tree.set_root(Some(L::duplicate(&pivot)));
+        }
+    }
+    tree.refresh_upward(Some(L::duplicate(&pivot)));
+    insert_fixup(tree, pivot);
+}
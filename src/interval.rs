@@ -0,0 +1,447 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::Range;
+
+use crate::node::Node;
+use crate::tree::{Iter, RBTreeSet};
+use crate::Augment;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Interval<T> {
+    start: T,
+    end: T,
+}
+
+impl<T: Ord> Ord for Interval<T> {
+    fn cmp(&self, other: &Interval<T>) -> Ordering {
+        self.start
+            .cmp(&other.start)
+            .then_with(|| self.end.cmp(&other.end))
+    }
+}
+
+impl<T: Ord> PartialOrd for Interval<T> {
+    fn partial_cmp(&self, other: &Interval<T>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Clone> Interval<T> {
+    fn to_range(&self) -> Range<T> {
+        self.start.clone()..self.end.clone()
+    }
+}
+
+/// The greatest end of the intervals of a subtree.
+#[derive(Clone, Debug, PartialEq)]
+struct MaxEnd<T>(T);
+
+impl<T: Ord + Clone> Augment<Interval<T>> for MaxEnd<T> {
+    fn summarize(data: &Interval<T>) -> MaxEnd<T> {
+        MaxEnd(data.end.clone())
+    }
+
+    fn combine(&self, other: &MaxEnd<T>) -> MaxEnd<T> {
+        if self.0 < other.0 {
+            other.clone()
+        } else {
+            self.clone()
+        }
+    }
+}
+
+type IntervalNode<T> = Node<Interval<T>, MaxEnd<T>>;
+
+/// A set of half-open intervals supporting overlap queries.
+///
+/// This is an [RBTreeSet] of intervals sorted by start, where each node caches the
+/// greatest end of its subtree. Insert and delete are `O(log n)`. Overlap queries are
+/// `O(log n + k)` for `k` matching intervals starting within the queried range, plus up
+/// to `O(log n)` per matching interval starting before it.
+///
+/// Overlapping intervals are stored independently, empty intervals are ignored.
+///
+/// [RBTreeSet]: struct.RBTreeSet.html
+///
+/// # Examples
+///
+/// ```
+/// use rbtset::IntervalSet;
+///
+/// let mut set = IntervalSet::new();
+/// set.insert(1..5);
+/// set.insert(3..8);
+/// set.insert(10..12);
+///
+/// assert_eq!(set.overlapping(4..11).collect::<Vec<_>>(), vec![1..5, 3..8, 10..12]);
+/// assert_eq!(set.containing(&6).collect::<Vec<_>>(), vec![3..8]);
+/// assert_eq!(set.stabbing(&9), None);
+/// ```
+pub struct IntervalSet<T> {
+    set: RBTreeSet<Interval<T>, MaxEnd<T>>,
+}
+
+impl<T: Ord + Clone> IntervalSet<T> {
+    /// Makes a new `IntervalSet`.
+    pub fn new() -> IntervalSet<T> {
+        IntervalSet {
+            set: RBTreeSet::new_augmented(),
+        }
+    }
+
+    /// Adds an interval to the set. Returns whether the interval was newly inserted.
+    ///
+    /// Empty intervals are not inserted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::IntervalSet;
+    ///
+    /// let mut set = IntervalSet::new();
+    /// assert_eq!(set.insert(1..3), true);
+    /// assert_eq!(set.insert(1..3), false);
+    /// assert_eq!(set.insert(2..2), false);
+    /// assert_eq!(set.len(), 1);
+    /// ```
+    pub fn insert(&mut self, range: Range<T>) -> bool {
+        if range.start >= range.end {
+            return false;
+        }
+        self.set
+            .insert(Interval {
+                start: range.start,
+                end: range.end,
+            })
+            .is_some()
+    }
+
+    /// Removes an interval from the set. Returns whether the interval was present.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::IntervalSet;
+    ///
+    /// let mut set = IntervalSet::new();
+    /// set.insert(1..3);
+    /// assert_eq!(set.remove(&(1..3)), true);
+    /// assert_eq!(set.remove(&(1..3)), false);
+    /// ```
+    pub fn remove(&mut self, range: &Range<T>) -> bool {
+        match self.get_node(range) {
+            Some(ref node) => {
+                self.set.unlink(node);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns true if the set contains the given interval.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::IntervalSet;
+    ///
+    /// let mut set = IntervalSet::new();
+    /// set.insert(1..3);
+    /// assert_eq!(set.contains(&(1..3)), true);
+    /// assert_eq!(set.contains(&(1..2)), false);
+    /// ```
+    pub fn contains(&self, range: &Range<T>) -> bool {
+        self.get_node(range).is_some()
+    }
+
+    fn get_node(&self, range: &Range<T>) -> Option<IntervalNode<T>> {
+        self.set.find_node(|i| {
+            range
+                .start
+                .cmp(&i.start)
+                .then_with(|| range.end.cmp(&i.end))
+        })
+    }
+
+    /// Clears the set, removing all intervals.
+    pub fn clear(&mut self) {
+        self.set.clear();
+    }
+
+    /// Returns the number of intervals in the set.
+    pub fn len(&self) -> usize {
+        self.set.len()
+    }
+
+    /// Returns true if the set contains no intervals.
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty()
+    }
+
+    /// Gets an iterator that visits the intervals in ascending order of start, then end.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::IntervalSet;
+    ///
+    /// let set: IntervalSet<_> = vec![3..4, 1..5, 1..2].into_iter().collect();
+    /// assert_eq!(set.iter().collect::<Vec<_>>(), vec![1..2, 1..5, 3..4]);
+    /// ```
+    pub fn iter(&self) -> Intervals<T> {
+        Intervals {
+            inner: self.set.iter(),
+        }
+    }
+
+    /// Gets an iterator that visits the intervals overlapping the given range, in
+    /// ascending order. An empty range overlaps no interval.
+    ///
+    /// The `k` intervals starting within the range follow each other in the set and are
+    /// visited in `O(log n + k)`. The intervals starting before the range are found by
+    /// walking down the subtrees whose greatest end reaches it, up to `O(log n)` each.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::IntervalSet;
+    ///
+    /// let set: IntervalSet<_> = vec![1..3, 3..5, 6..9].into_iter().collect();
+    /// assert_eq!(set.overlapping(2..4).collect::<Vec<_>>(), vec![1..3, 3..5]);
+    /// assert_eq!(set.overlapping(5..6).next(), None);
+    /// assert_eq!(set.overlapping(4..4).next(), None);
+    /// ```
+    pub fn overlapping(&self, range: Range<T>) -> Overlapping<T> {
+        let root = if range.start < range.end {
+            self.set.root()
+        } else {
+            None
+        };
+        Overlapping::new(root, range.start, range.end, false)
+    }
+
+    /// Gets an iterator that visits the intervals containing the given point, in
+    /// ascending order.
+    ///
+    /// The `k` intervals starting at the point are visited in `O(log n + k)`. The
+    /// intervals starting before the point are found by walking down the subtrees whose
+    /// greatest end reaches it, up to `O(log n)` each.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::IntervalSet;
+    ///
+    /// let set: IntervalSet<_> = vec![1..3, 2..5, 6..9].into_iter().collect();
+    /// assert_eq!(set.containing(&2).collect::<Vec<_>>(), vec![1..3, 2..5]);
+    /// assert_eq!(set.containing(&5).next(), None);
+    /// ```
+    pub fn containing(&self, point: &T) -> Overlapping<T> {
+        Overlapping::new(self.set.root(), point.clone(), point.clone(), true)
+    }
+
+    /// Returns an interval containing the given point, if any.
+    ///
+    /// This query is `O(log n)`, use [containing] to get all the matching intervals.
+    ///
+    /// [containing]: #method.containing
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::IntervalSet;
+    ///
+    /// let set: IntervalSet<_> = vec![1..3, 6..9].into_iter().collect();
+    /// assert_eq!(set.stabbing(&7), Some(6..9));
+    /// assert_eq!(set.stabbing(&4), None);
+    /// ```
+    pub fn stabbing(&self, point: &T) -> Option<Range<T>> {
+        let mut tmp = self.set.root();
+        while let Some(n) = tmp {
+            if n.data().start <= *point && *point < n.data().end {
+                return Some(n.data().to_range());
+            }
+            tmp = match n.left() {
+                Some(left) if left.summary().0 > *point => Some(left),
+                _ => n.right(),
+            };
+        }
+        None
+    }
+}
+
+impl<T: Ord + Clone> Default for IntervalSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord + Clone> fmt::Debug for IntervalSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "IntervalSet {{ length: {} }}", self.set.len())
+    }
+}
+
+impl<T: Ord + Clone> Clone for IntervalSet<T> {
+    fn clone(&self) -> Self {
+        IntervalSet {
+            set: self.set.clone(),
+        }
+    }
+}
+
+impl<T: Ord + Clone> FromIterator<Range<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> Self {
+        let mut s = Self::new();
+        for i in iter {
+            s.insert(i);
+        }
+        s
+    }
+}
+
+/// Created with the method [iter].
+///
+/// [iter]: struct.IntervalSet.html#method.iter
+pub struct Intervals<T> {
+    inner: Iter<Interval<T>, MaxEnd<T>>,
+}
+
+impl<T: Ord + Clone> Iterator for Intervals<T> {
+    type Item = Range<T>;
+
+    fn next(&mut self) -> Option<Range<T>> {
+        self.inner.next().map(|n| n.data().to_range())
+    }
+}
+
+impl<T: Ord + Clone> DoubleEndedIterator for Intervals<T> {
+    fn next_back(&mut self) -> Option<Range<T>> {
+        self.inner.next_back().map(|n| n.data().to_range())
+    }
+}
+
+/// Created with the methods [overlapping] and [containing].
+///
+/// [overlapping]: struct.IntervalSet.html#method.overlapping
+/// [containing]: struct.IntervalSet.html#method.containing
+pub struct Overlapping<T> {
+    low: T,
+    high: T,
+    high_included: bool,
+    stack: Vec<IntervalNode<T>>,
+    next: Option<IntervalNode<T>>,
+}
+
+impl<T: Ord + Clone> Overlapping<T> {
+    fn new(root: Option<IntervalNode<T>>, low: T, high: T, high_included: bool) -> Overlapping<T> {
+        let mut iter = Overlapping {
+            low,
+            high,
+            high_included,
+            stack: Vec::new(),
+            next: None,
+        };
+        iter.push_left_path(root);
+        iter
+    }
+
+    fn starts_before_high(&self, node: &IntervalNode<T>) -> bool {
+        match node.data().start.cmp(&self.high) {
+            Ordering::Less => true,
+            Ordering::Equal => self.high_included,
+            Ordering::Greater => false,
+        }
+    }
+
+    /// Stacks the left path of the subtree, skipping subtrees ending before `low`.
+    fn push_left_path(&mut self, node: Option<IntervalNode<T>>) {
+        let mut tmp = node;
+        while let Some(n) = tmp {
+            if n.summary().0 <= self.low {
+                break;
+            }
+            tmp = n.left();
+            self.stack.push(n);
+        }
+    }
+}
+
+impl<T: Ord + Clone> Iterator for Overlapping<T> {
+    type Item = Range<T>;
+
+    fn next(&mut self) -> Option<Range<T>> {
+        while let Some(node) = self.stack.pop() {
+            if node.data().start >= self.low {
+                // the skipped subtrees all start before `low`, this is the first interval
+                // starting within the range and the following ones are its successors
+                self.stack.clear();
+                self.next = Some(node);
+                break;
+            }
+            self.push_left_path(node.right());
+            if node.data().end > self.low {
+                return Some(node.data().to_range());
+            }
+        }
+        let node = self.next.take()?;
+        if !self.starts_before_high(&node) {
+            return None;
+        }
+        self.next = node.next();
+        let range = node.data().to_range();
+        Some(range)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queries() {
+        let mut set = IntervalSet::new();
+        let mut oracle = Vec::new();
+        for i in 0..60 {
+            let start = (i * 37) % 53;
+            let range = start..start + 1 + (i * 11) % 9;
+            if set.insert(range.clone()) {
+                oracle.push(range);
+            }
+        }
+        for i in 0..20 {
+            let range = oracle[(i * 7) % oracle.len()].clone();
+            if set.remove(&range) {
+                oracle.retain(|r| *r != range);
+            }
+        }
+        oracle.sort_by(|a, b| a.start.cmp(&b.start).then(a.end.cmp(&b.end)));
+        assert_eq!(set.iter().collect::<Vec<_>>(), oracle);
+
+        for low in 0..65 {
+            assert_eq!(set.overlapping(low..low).next(), None);
+            assert_eq!(set.overlapping(low + 3..low).next(), None);
+            for high in low + 1..65 {
+                assert_eq!(
+                    set.overlapping(low..high).collect::<Vec<_>>(),
+                    oracle
+                        .iter()
+                        .filter(|r| r.start < high && low < r.end)
+                        .cloned()
+                        .collect::<Vec<_>>()
+                );
+            }
+            let containing: Vec<_> = oracle
+                .iter()
+                .filter(|r| r.contains(&low))
+                .cloned()
+                .collect();
+            assert_eq!(set.containing(&low).collect::<Vec<_>>(), containing);
+            match set.stabbing(&low) {
+                Some(range) => assert!(containing.contains(&range)),
+                None => assert!(containing.is_empty()),
+            }
+        }
+    }
+}
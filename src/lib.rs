@@ -4,17 +4,51 @@
 //!
 //! [main struct documentation]: struct.RBTreeSet.html
 
+use std::cmp::Ordering;
+
+mod arena;
+mod balance;
+mod interval;
+mod map;
+mod multiset;
 mod node;
+mod ops;
+mod persistent;
+mod range_set;
+mod raw;
+mod span;
+mod sync;
 mod tree;
 
+pub use arena::{ArenaDrain, ArenaExtractIf, ArenaIter, ArenaRBTreeSet, Handle};
+pub use interval::{IntervalSet, Intervals, Overlapping};
+pub use map::{
+    Entry, KeyValue, Keys, OccupiedEntry, RBTreeMap, VacantEntry, ValueMut, ValueRef, Values,
+};
+pub use multiset::{Counted, Occurrences, RBTreeMultiSet};
 pub use node::Node;
-pub use tree::{Iter, IterValues, RBTreeSet};
+pub use ops::{
+    ArenaDifference, ArenaIntersection, ArenaSymmetricDifference, ArenaUnion, Difference,
+    Intersection, SymmetricDifference, Union,
+};
+pub use persistent::{PersistentIntoIter, PersistentIter, PersistentRBTreeSet};
+pub use range_set::RangeSet;
+pub use span::{Discrete, Span, SpanInclusive};
+pub use sync::{Snapshot, SnapshotRBTreeSet, SnapshotValues, SyncRBTreeSet, SyncValues};
+pub use tree::{Drain, ExtractIf, Iter, IterValues, RBTreeSet, RemovedValues};
+
+#[cfg(feature = "derive")]
+pub use rbtset_derive::Consecutive;
 
 /// An interface for dealing with consecutive data.
 ///
 /// Provides a way to determine if two values are consecutive and how to merge
 /// it. See [repack] for the main usage of this interface.
 ///
+/// With the `derive` feature, `#[derive(Consecutive)]` generates this implementation
+/// and the matching `Splittable`, `Ord` and `PartialEq` ones for structs with `start`
+/// and `end` fields.
+///
 /// [repack]: struct.RBTreeSet.html#method.repack
 ///
 /// # Examples
@@ -66,3 +100,203 @@ pub trait Consecutive {
     /// `other` is consecutive to `self`.
     fn merged(&self, other: &Self) -> Self;
 }
+
+/// An interface for carving a part out of a value.
+///
+/// The inverse of [Consecutive::merged], see [remove_range] for the main usage of
+/// this interface.
+///
+/// [Consecutive::merged]: trait.Consecutive.html#tymethod.merged
+/// [remove_range]: struct.RBTreeSet.html#method.remove_range
+///
+/// # Examples
+///
+/// ```
+/// use rbtset::Splittable;
+///
+/// #[derive(Debug, Clone, PartialEq)]
+/// struct Seq(std::ops::Range<usize>);
+///
+/// impl Splittable for Seq {
+///     fn split(&self, other: &Seq) -> Option<(Option<Seq>, Option<Seq>)> {
+///         if other.0.is_empty() || self.0.end <= other.0.start || other.0.end <= self.0.start {
+///             return None;
+///         }
+///         let before = Some(Seq(self.0.start..other.0.start)).filter(|s| !s.0.is_empty());
+///         let after = Some(Seq(other.0.end..self.0.end)).filter(|s| !s.0.is_empty());
+///         Some((before, after))
+///     }
+/// }
+///
+/// assert_eq!(Seq(1..9).split(&Seq(3..5)), Some((Some(Seq(1..3)), Some(Seq(5..9)))));
+/// assert_eq!(Seq(1..9).split(&Seq(0..5)), Some((None, Some(Seq(5..9)))));
+/// assert_eq!(Seq(1..9).split(&Seq(9..12)), None);
+/// assert_eq!(Seq(1..9).split(&Seq(3..3)), None);
+/// ```
+pub trait Splittable: Sized {
+    /// Returns the parts of `self` preceding and following `other` once `other` is
+    /// removed from it, or `None` if `self` and `other` do not overlap. An empty `other`
+    /// overlaps nothing.
+    fn split(&self, other: &Self) -> Option<(Option<Self>, Option<Self>)>;
+}
+
+/// An interface for caching a summary of the values of each subtree.
+///
+/// An `RBTreeSet<T, A>` stores in every node the summary of all the values of its
+/// subtree, kept up to date through insertions, deletions and rotations. It allows to
+/// compute aggregates over a range of values in `O(log n)`, see [fold_range].
+///
+/// `combine` must be associative, the summary of a subtree being the combination of
+/// the summaries of its values in ascending order.
+///
+/// The default summary `()` holds no information.
+///
+/// [fold_range]: struct.RBTreeSet.html#method.fold_range
+///
+/// # Examples
+///
+/// ```
+/// use rbtset::{Augment, RBTreeSet};
+///
+/// #[derive(Debug, Clone, PartialEq)]
+/// struct Sum(i64);
+///
+/// impl Augment<i64> for Sum {
+///     fn summarize(data: &i64) -> Sum {
+///         Sum(*data)
+///     }
+///
+///     fn combine(&self, other: &Sum) -> Sum {
+///         Sum(self.0 + other.0)
+///     }
+/// }
+///
+/// let mut set: RBTreeSet<i64, Sum> = RBTreeSet::new_augmented();
+/// set.insert(3);
+/// set.insert(1);
+/// set.insert(2);
+///
+/// assert_eq!(set.summary(), Some(Sum(6)));
+/// assert_eq!(set.fold_range(2..), Some(Sum(5)));
+/// ```
+pub trait Augment<T>: Clone {
+    /// Returns the summary of a single value.
+    fn summarize(data: &T) -> Self;
+    /// Returns the combination of `self` and `other`, `self` summarizing values
+    /// preceding the ones summarized by `other`.
+    fn combine(&self, other: &Self) -> Self;
+}
+
+impl<T> Augment<T> for () {
+    fn summarize(_data: &T) {}
+
+    fn combine(&self, _other: &()) {}
+}
+
+/// An interface for ordering the values of a set.
+///
+/// An `RBTreeSet<T, A, C>` orders its values with the comparator `C`, which defaults
+/// to [Natural], the ordering given by `Ord`. Closures taking two references and
+/// returning an `Ordering` are comparators too, see [with_comparator].
+///
+/// [Natural]: struct.Natural.html
+/// [with_comparator]: struct.RBTreeSet.html#method.with_comparator
+///
+/// # Examples
+///
+/// ```
+/// use std::cmp::Ordering;
+/// use rbtset::{Compare, RBTreeSet};
+///
+/// struct Reverse;
+///
+/// impl Compare<u32> for Reverse {
+///     fn compare(&self, a: &u32, b: &u32) -> Ordering {
+///         b.cmp(a)
+///     }
+/// }
+///
+/// let mut set = RBTreeSet::with_comparator(Reverse);
+/// set.insert(1);
+/// set.insert(3);
+/// set.insert(2);
+///
+/// assert_eq!(set.values().collect::<Vec<_>>(), vec![3, 2, 1]);
+/// ```
+pub trait Compare<T: ?Sized> {
+    /// Returns the ordering of `a` compared to `b`.
+    fn compare(&self, a: &T, b: &T) -> Ordering;
+
+    /// Returns true if `a` and `b` are the same value for the set, `a` being the value
+    /// looked up or inserted and `b` a value of the set.
+    ///
+    /// Defaults to `compare` returning `Ordering::Equal`.
+    fn equivalent(&self, a: &T, b: &T) -> bool {
+        self.compare(a, b) == Ordering::Equal
+    }
+}
+
+/// The comparator ordering values by their `Ord` implementation.
+///
+/// Values are matched with their `PartialEq` implementation, which must be consistent
+/// with `Ord`. See [Containment] to match a range of values.
+///
+/// [Containment]: struct.Containment.html
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Natural;
+
+impl<T: Ord + ?Sized> Compare<T> for Natural {
+    #[inline]
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        a.cmp(b)
+    }
+
+    #[inline]
+    fn equivalent(&self, a: &T, b: &T) -> bool {
+        a == b
+    }
+}
+
+/// The comparator ordering values by their `Ord` implementation and matching a value
+/// with the value of the set covering it.
+///
+/// A value `a` matches a value `b` of the set when `a` overlaps `b` and nothing of `a`
+/// is left once `b` is removed from it, see [Splittable]. The values of the set are
+/// expected not to overlap each other, like the values of a [RangeSet].
+///
+/// [Splittable]: trait.Splittable.html
+/// [RangeSet]: struct.RangeSet.html
+///
+/// # Examples
+///
+/// ```
+/// use rbtset::{Containment, RBTreeSet, Span};
+///
+/// let mut set = RBTreeSet::with_comparator(Containment);
+/// set.insert(Span(1..5));
+/// set.insert(Span(8..10));
+///
+/// assert_eq!(set.get(&Span(2..4)), Some(Span(1..5)));
+/// assert!(set.contains(&Span(8..9)));
+/// assert!(!set.contains(&Span(4..6)));
+/// assert!(set.insert(Span(1..2)).is_none());
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Containment;
+
+impl<T: Ord + Splittable> Compare<T> for Containment {
+    #[inline]
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        a.cmp(b)
+    }
+
+    fn equivalent(&self, a: &T, b: &T) -> bool {
+        matches!(a.split(b), Some((None, None)))
+    }
+}
+
+impl<T: ?Sized, F: Fn(&T, &T) -> Ordering> Compare<T> for F {
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self(a, b)
+    }
+}
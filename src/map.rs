@@ -0,0 +1,964 @@
+use std::borrow::Borrow;
+use std::cell::{Ref, RefMut};
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::FromIterator;
+use std::marker::PhantomData;
+
+use crate::node::Node;
+use crate::tree::{Iter, RBTreeSet};
+
+type EntryNode<K, V> = Node<KeyValue<K, V>>;
+
+/// Type of the data stored in the nodes of an [RBTreeMap].
+///
+/// Entries are ordered and compared by key only.
+///
+/// [RBTreeMap]: struct.RBTreeMap.html
+#[derive(Clone, Debug)]
+pub struct KeyValue<K, V> {
+    key: K,
+    value: V,
+}
+
+impl<K, V> KeyValue<K, V> {
+    /// Returns a reference to the key of the entry.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Returns a reference to the value of the entry.
+    pub fn value(&self) -> &V {
+        &self.value
+    }
+
+    /// Returns a mutable reference to the value of the entry.
+    pub fn value_mut(&mut self) -> &mut V {
+        &mut self.value
+    }
+}
+
+impl<K: Ord, V> Ord for KeyValue<K, V> {
+    fn cmp(&self, other: &KeyValue<K, V>) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+impl<K: Ord, V> PartialOrd for KeyValue<K, V> {
+    fn partial_cmp(&self, other: &KeyValue<K, V>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Ord, V> PartialEq for KeyValue<K, V> {
+    fn eq(&self, other: &KeyValue<K, V>) -> bool {
+        self.key == other.key
+    }
+}
+
+impl<K: Ord, V> Eq for KeyValue<K, V> {}
+
+/// A map based on a RB-Tree for efficient operations.
+///
+/// This is the map counterpart of [RBTreeSet] and shares its implementation: insert,
+/// delete and search are `O(log n)` and partial iterations are possible providing a
+/// `Node` reference using [iter_from].
+///
+/// [RBTreeSet]: struct.RBTreeSet.html
+/// [iter_from]: #method.iter_from
+///
+/// # Examples
+///
+/// ```
+/// use rbtset::RBTreeMap;
+///
+/// let mut ages = RBTreeMap::new();
+/// ages.insert("Joe", 32);
+/// ages.insert("Amy", 27);
+/// ages.insert("Bob", 45);
+///
+/// assert_eq!(ages.get(&"Amy"), Some(27));
+///
+/// *ages.entry("Amy").or_insert(0).get_mut() += 1;
+/// assert_eq!(ages.get(&"Amy"), Some(28));
+///
+/// ages.remove(&"Joe");
+/// assert_eq!(ages.keys().collect::<Vec<_>>(), vec!["Amy", "Bob"]);
+/// ```
+pub struct RBTreeMap<K, V> {
+    set: RBTreeSet<KeyValue<K, V>>,
+}
+
+impl<K: Ord, V> RBTreeMap<K, V> {
+    /// Makes a new `RBTreeMap`.
+    pub fn new() -> RBTreeMap<K, V> {
+        RBTreeMap {
+            set: RBTreeSet::new(),
+        }
+    }
+
+    /// Returns the node in the map, if any, that is matching the given key.
+    ///
+    /// The key may be any borrowed form of the map's key type, but the ordering on the
+    /// borrowed form must match the ordering on the key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMap;
+    ///
+    /// let mut map = RBTreeMap::new();
+    /// map.insert(1, "a");
+    /// assert_eq!(*map.get_node(&1).unwrap().data().value(), "a");
+    /// assert_eq!(map.get_node(&2), None);
+    /// ```
+    pub fn get_node<Q>(&self, key: &Q) -> Option<Node<KeyValue<K, V>>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.set.find_node(|kv| key.cmp(kv.key.borrow()))
+    }
+
+    /// Returns a clone of the value matching the given key, if any.
+    ///
+    /// The key may be any borrowed form of the map's key type, but the ordering on the
+    /// borrowed form must match the ordering on the key type.
+    ///
+    /// Use [get_node] in pair with [Node::data] if you want to avoid value cloning.
+    ///
+    /// [get_node]: #method.get_node
+    /// [Node::data]: struct.Node.html#method.data
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMap;
+    ///
+    /// let mut map = RBTreeMap::new();
+    /// map.insert(1, "a");
+    /// assert_eq!(map.get(&1), Some("a"));
+    /// assert_eq!(map.get(&2), None);
+    /// ```
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        V: Clone,
+    {
+        self.get_node(key).map(|n| n.data().value.clone())
+    }
+
+    /// Returns a mutable handle on the value matching the given key, if any.
+    ///
+    /// The key may be any borrowed form of the map's key type, but the ordering on the
+    /// borrowed form must match the ordering on the key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMap;
+    ///
+    /// let mut map = RBTreeMap::new();
+    /// map.insert(1, "a");
+    /// if let Some(mut x) = map.get_mut(&1) {
+    ///     *x.get_mut() = "b";
+    /// }
+    /// assert_eq!(map.get(&1), Some("b"));
+    /// ```
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<ValueMut<'_, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get_node(key).map(ValueMut::new)
+    }
+
+    /// Returns true if the map contains a value for the given key.
+    ///
+    /// The key may be any borrowed form of the map's key type, but the ordering on the
+    /// borrowed form must match the ordering on the key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMap;
+    ///
+    /// let mut map = RBTreeMap::new();
+    /// map.insert(1, "a");
+    /// assert_eq!(map.contains_key(&1), true);
+    /// assert_eq!(map.contains_key(&2), false);
+    ///
+    /// let mut map = RBTreeMap::new();
+    /// map.insert(String::from("a"), 1);
+    /// assert_eq!(map.contains_key("a"), true);
+    /// ```
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get_node(key).is_some()
+    }
+
+    /// Inserts a key-value pair into the map.
+    ///
+    /// If the map did not have this key present, `None` is returned.
+    ///
+    /// If the map did have this key present, the value is updated, and the old value is
+    /// returned. The key is not updated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMap;
+    ///
+    /// let mut map = RBTreeMap::new();
+    /// assert_eq!(map.insert(37, "a"), None);
+    /// assert_eq!(map.is_empty(), false);
+    ///
+    /// map.insert(37, "b");
+    /// assert_eq!(map.insert(37, "c"), Some("b"));
+    /// assert_eq!(map.get(&37), Some("c"));
+    /// ```
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => Some(entry.insert(value)),
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                None
+            }
+        }
+    }
+
+    /// Removes a key from the map, returning its value if the key was previously in the
+    /// map.
+    ///
+    /// The key may be any borrowed form of the map's key type, but the ordering on the
+    /// borrowed form must match the ordering on the key type.
+    /// The value is moved out of its node. When handles on the node are still held, the
+    /// detached node is returned instead and keeps the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMap;
+    ///
+    /// let mut map = RBTreeMap::new();
+    /// map.insert(1, "a");
+    /// assert_eq!(map.remove(&1), Some(Ok("a")));
+    /// assert_eq!(map.remove(&1), None);
+    ///
+    /// map.insert(2, "b");
+    /// let node = map.get_node(&2).unwrap();
+    /// assert_eq!(map.remove(&2), Some(Err(node)));
+    /// ```
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<Result<V, EntryNode<K, V>>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let node = self.get_node(key)?;
+        self.set.unlink(&node);
+        Some(node.try_into_data().map(|kv| kv.value))
+    }
+
+    /// Removes a key from the map, returning the stored key and value if the key was
+    /// previously in the map.
+    ///
+    /// The key may be any borrowed form of the map's key type, but the ordering on the
+    /// borrowed form must match the ordering on the key type.
+    /// The key and value are moved out of their node, see [remove](#method.remove).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMap;
+    ///
+    /// let mut map = RBTreeMap::new();
+    /// map.insert(1, "a");
+    /// assert_eq!(map.remove_entry(&1), Some(Ok((1, "a"))));
+    /// assert_eq!(map.remove_entry(&1), None);
+    /// ```
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<Result<(K, V), EntryNode<K, V>>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let node = self.get_node(key)?;
+        self.set.unlink(&node);
+        Some(node.try_into_data().map(|kv| (kv.key, kv.value)))
+    }
+
+    /// Removes a node from the map.
+    ///
+    /// Nothing is done if the node was already removed or belongs to another map, see
+    /// [RBTreeSet::remove_node].
+    ///
+    /// [RBTreeSet::remove_node]: struct.RBTreeSet.html#method.remove_node
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMap;
+    ///
+    /// let mut map = RBTreeMap::new();
+    /// map.insert(1, "a");
+    /// let mut node = map.get_node(&1).unwrap();
+    /// map.remove_node(&mut node);
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn remove_node(&mut self, node: &mut Node<KeyValue<K, V>>) {
+        self.set.remove_node(node);
+    }
+
+    /// Gets the given key's corresponding entry in the map for in-place manipulation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMap;
+    ///
+    /// let mut count = RBTreeMap::new();
+    /// for x in vec!["a", "b", "a", "c", "a", "b"] {
+    ///     *count.entry(x).or_insert(0).get_mut() += 1;
+    /// }
+    ///
+    /// assert_eq!(count.get(&"a"), Some(3));
+    /// assert_eq!(count.get(&"b"), Some(2));
+    /// assert_eq!(count.get(&"c"), Some(1));
+    /// ```
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        match self.get_node(&key) {
+            Some(node) => Entry::Occupied(OccupiedEntry { map: self, node }),
+            None => Entry::Vacant(VacantEntry { map: self, key }),
+        }
+    }
+
+    /// Clears the map, removing all entries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMap;
+    ///
+    /// let mut map = RBTreeMap::new();
+    /// map.insert(1, "a");
+    /// map.clear();
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.set.clear();
+    }
+
+    /// Returns the first node of the map if not empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMap;
+    ///
+    /// let mut map = RBTreeMap::new();
+    /// map.insert(2, "b");
+    /// map.insert(1, "a");
+    /// assert_eq!(*map.first().unwrap().data().key(), 1);
+    /// ```
+    pub fn first(&self) -> Option<Node<KeyValue<K, V>>> {
+        self.set.first()
+    }
+
+    /// Returns the last node of the map if not empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMap;
+    ///
+    /// let mut map = RBTreeMap::new();
+    /// map.insert(2, "b");
+    /// map.insert(1, "a");
+    /// assert_eq!(*map.last().unwrap().data().key(), 2);
+    /// ```
+    pub fn last(&self) -> Option<Node<KeyValue<K, V>>> {
+        self.set.last()
+    }
+
+    /// Returns the number of elements in the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMap;
+    ///
+    /// let mut map = RBTreeMap::new();
+    /// assert_eq!(map.len(), 0);
+    /// map.insert(1, "a");
+    /// assert_eq!(map.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.set.len()
+    }
+
+    /// Returns true if the map contains no elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMap;
+    ///
+    /// let mut map = RBTreeMap::new();
+    /// assert!(map.is_empty());
+    /// map.insert(1, "a");
+    /// assert!(!map.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty()
+    }
+
+    /// Gets an iterator that visits the nodes in the RBTreeMap in ascending key order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMap;
+    ///
+    /// let mut map = RBTreeMap::new();
+    /// map.insert(3, "c");
+    /// map.insert(1, "a");
+    /// let mut map_iter = map.iter();
+    ///
+    /// assert_eq!(*map_iter.next().unwrap().data().value(), "a");
+    /// assert_eq!(*map_iter.next().unwrap().data().value(), "c");
+    /// assert_eq!(map_iter.next(), None);
+    /// ```
+    pub fn iter(&self) -> Iter<KeyValue<K, V>> {
+        self.set.iter()
+    }
+
+    /// Gets an iterator that visits the nodes in the RBTreeMap in ascending key order,
+    /// starting at the given node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMap;
+    ///
+    /// let map: RBTreeMap<_, _> = vec![(1, "a"), (2, "b"), (3, "c")].into_iter().collect();
+    /// let node = map.get_node(&2).unwrap();
+    /// let mut map_iter = map.iter_from(&node);
+    ///
+    /// assert_eq!(*map_iter.next().unwrap().data().value(), "b");
+    /// assert_eq!(*map_iter.next().unwrap().data().value(), "c");
+    /// assert_eq!(map_iter.next(), None);
+    /// ```
+    pub fn iter_from(&self, node: &Node<KeyValue<K, V>>) -> Iter<KeyValue<K, V>> {
+        self.set.iter_from(node)
+    }
+
+    /// Gets an iterator that visits the keys in the RBTreeMap in ascending order.
+    ///
+    /// This iterator clones the keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMap;
+    ///
+    /// let map: RBTreeMap<_, _> = vec![(2, "b"), (1, "a")].into_iter().collect();
+    /// assert_eq!(map.keys().collect::<Vec<_>>(), vec![1, 2]);
+    /// ```
+    pub fn keys(&self) -> Keys<K, V>
+    where
+        K: Clone,
+    {
+        Keys { inner: self.iter() }
+    }
+
+    /// Gets an iterator that visits the values in the RBTreeMap in ascending key order.
+    ///
+    /// The values are borrowed through [ValueRef] handles.
+    ///
+    /// [ValueRef]: struct.ValueRef.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMap;
+    ///
+    /// let map: RBTreeMap<_, _> = vec![(2, "b"), (1, "a")].into_iter().collect();
+    /// assert_eq!(map.values().map(|v| *v.get()).collect::<Vec<_>>(), vec!["a", "b"]);
+    /// ```
+    pub fn values(&self) -> Values<K, V> {
+        Values { inner: self.iter() }
+    }
+
+    /// Gets an iterator that visits the values in the RBTreeMap in ascending key order,
+    /// starting at the given node.
+    ///
+    /// The values are borrowed through [ValueRef] handles.
+    ///
+    /// [ValueRef]: struct.ValueRef.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMap;
+    ///
+    /// let map: RBTreeMap<_, _> = vec![(1, "a"), (2, "b"), (3, "c")].into_iter().collect();
+    /// let node = map.get_node(&2).unwrap();
+    /// let values: Vec<_> = map.values_from(&node).map(|v| *v.get()).collect();
+    /// assert_eq!(values, vec!["b", "c"]);
+    /// ```
+    pub fn values_from(&self, node: &Node<KeyValue<K, V>>) -> Values<K, V> {
+        Values {
+            inner: self.iter_from(node),
+        }
+    }
+}
+
+impl<K: Ord, V> Default for RBTreeMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V> fmt::Debug for RBTreeMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RBTreeMap {{ length: {} }}", self.set.len())
+    }
+}
+
+impl<K: Clone, V: Clone> Clone for RBTreeMap<K, V> {
+    fn clone(&self) -> Self {
+        RBTreeMap {
+            set: self.set.clone(),
+        }
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for RBTreeMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut m = Self::new();
+        for (k, v) in iter {
+            m.insert(k, v);
+        }
+        m
+    }
+}
+
+/// A view into a single entry in a map, which may either be vacant or occupied.
+///
+/// This enum is constructed from the [entry] method on [RBTreeMap].
+///
+/// [entry]: struct.RBTreeMap.html#method.entry
+/// [RBTreeMap]: struct.RBTreeMap.html
+pub enum Entry<'a, K, V> {
+    /// A vacant entry.
+    Vacant(VacantEntry<'a, K, V>),
+    /// An occupied entry.
+    Occupied(OccupiedEntry<'a, K, V>),
+}
+
+impl<'a, K: Ord, V> Entry<'a, K, V> {
+    /// Ensures a value is in the entry by inserting the default if empty, and returns
+    /// a mutable handle on the value in the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMap;
+    ///
+    /// let mut map = RBTreeMap::new();
+    /// map.entry("poneyland").or_insert(12);
+    /// assert_eq!(map.get(&"poneyland"), Some(12));
+    /// ```
+    pub fn or_insert(self, default: V) -> ValueMut<'a, K, V> {
+        self.or_insert_with(|| default)
+    }
+
+    /// Ensures a value is in the entry by inserting the result of the default function
+    /// if empty, and returns a mutable handle on the value in the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMap;
+    ///
+    /// let mut map = RBTreeMap::new();
+    /// map.entry("poneyland").or_insert_with(|| "hoho".to_string());
+    /// assert_eq!(map.get(&"poneyland"), Some("hoho".to_string()));
+    /// ```
+    pub fn or_insert_with<F>(self, default: F) -> ValueMut<'a, K, V>
+    where
+        F: FnOnce() -> V,
+    {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Ensures a value is in the entry by inserting the default value if empty, and
+    /// returns a mutable handle on the value in the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMap;
+    ///
+    /// let mut map: RBTreeMap<&str, Option<usize>> = RBTreeMap::new();
+    /// map.entry("poneyland").or_default();
+    /// assert_eq!(map.get(&"poneyland"), Some(None));
+    /// ```
+    pub fn or_default(self) -> ValueMut<'a, K, V>
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    /// Provides in-place mutable access to an occupied entry before any potential
+    /// inserts into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMap;
+    ///
+    /// let mut map = RBTreeMap::new();
+    /// map.entry("poneyland").and_modify(|e| *e += 1).or_insert(42);
+    /// assert_eq!(map.get(&"poneyland"), Some(42));
+    /// map.entry("poneyland").and_modify(|e| *e += 1).or_insert(42);
+    /// assert_eq!(map.get(&"poneyland"), Some(43));
+    /// ```
+    pub fn and_modify<F>(self, f: F) -> Self
+    where
+        F: FnOnce(&mut V),
+    {
+        match self {
+            Entry::Occupied(mut entry) => {
+                f(&mut entry.get_mut());
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+}
+
+/// A view into a vacant entry in a [RBTreeMap]. It is part of the [Entry] enum.
+///
+/// [RBTreeMap]: struct.RBTreeMap.html
+/// [Entry]: enum.Entry.html
+pub struct VacantEntry<'a, K, V> {
+    map: &'a mut RBTreeMap<K, V>,
+    key: K,
+}
+
+impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
+    /// Gets a reference to the key that would be used when inserting a value through
+    /// the `VacantEntry`.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Takes ownership of the key.
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Sets the value of the entry with the `VacantEntry`'s key, and returns a mutable
+    /// handle on it.
+    pub fn insert(self, value: V) -> ValueMut<'a, K, V> {
+        let node = self
+            .map
+            .set
+            .insert(KeyValue {
+                key: self.key,
+                value,
+            })
+            .expect("insert vacant entry");
+        ValueMut::new(node)
+    }
+}
+
+/// A view into an occupied entry in a [RBTreeMap]. It is part of the [Entry] enum.
+///
+/// [RBTreeMap]: struct.RBTreeMap.html
+/// [Entry]: enum.Entry.html
+pub struct OccupiedEntry<'a, K, V> {
+    map: &'a mut RBTreeMap<K, V>,
+    node: Node<KeyValue<K, V>>,
+}
+
+impl<'a, K: Ord, V> OccupiedEntry<'a, K, V> {
+    /// Gets a reference to the key in the entry.
+    pub fn key(&self) -> Ref<'_, K> {
+        Ref::map(self.node.data_ref(), |kv| &kv.key)
+    }
+
+    /// Returns the node of the entry.
+    pub fn node(&self) -> Node<KeyValue<K, V>> {
+        self.node.duplicate()
+    }
+
+    /// Gets a reference to the value in the entry.
+    pub fn get(&self) -> Ref<'_, V> {
+        Ref::map(self.node.data_ref(), |kv| &kv.value)
+    }
+
+    /// Gets a mutable reference to the value in the entry.
+    pub fn get_mut(&mut self) -> RefMut<'_, V> {
+        RefMut::map(self.node.data_mut(), |kv| &mut kv.value)
+    }
+
+    /// Converts the entry into a mutable handle on its value.
+    pub fn into_mut(self) -> ValueMut<'a, K, V> {
+        ValueMut::new(self.node)
+    }
+
+    /// Sets the value of the entry, and returns the entry's old value.
+    pub fn insert(&mut self, value: V) -> V {
+        std::mem::replace(&mut self.node.data_mut().value, value)
+    }
+
+    /// Takes the value of the entry out of the map, and returns it.
+    ///
+    /// The value is moved out of its node. When handles on the node are still held, the
+    /// detached node is returned instead and keeps the entry.
+    pub fn remove(self) -> Result<V, EntryNode<K, V>> {
+        let OccupiedEntry { map, node } = self;
+        map.set.unlink(&node);
+        node.try_into_data().map(|kv| kv.value)
+    }
+
+    /// Takes the key and value of the entry out of the map, and returns them.
+    ///
+    /// The key and value are moved out of their node, see [remove](#method.remove).
+    pub fn remove_entry(self) -> Result<(K, V), EntryNode<K, V>> {
+        let OccupiedEntry { map, node } = self;
+        map.set.unlink(&node);
+        node.try_into_data().map(|kv| (kv.key, kv.value))
+    }
+}
+
+/// A mutable handle on a value of a [RBTreeMap], returned by [get_mut] and the
+/// [Entry] methods.
+///
+/// The handle keeps the map mutably borrowed, the value being borrowed through [get]
+/// and [get_mut].
+///
+/// [RBTreeMap]: struct.RBTreeMap.html
+/// [get_mut]: struct.RBTreeMap.html#method.get_mut
+/// [Entry]: enum.Entry.html
+/// [get]: #method.get
+/// [get_mut]: #method.get_mut
+///
+/// # Examples
+///
+/// ```
+/// use rbtset::RBTreeMap;
+///
+/// let mut map = RBTreeMap::new();
+/// let mut value = map.entry(1).or_insert(String::from("a"));
+/// value.get_mut().push('b');
+/// assert_eq!(*value.get(), "ab");
+/// ```
+pub struct ValueMut<'a, K, V> {
+    node: Node<KeyValue<K, V>>,
+    marker: PhantomData<&'a mut V>,
+}
+
+impl<K, V> ValueMut<'_, K, V> {
+    fn new(node: Node<KeyValue<K, V>>) -> Self {
+        ValueMut {
+            node,
+            marker: PhantomData,
+        }
+    }
+
+    /// Gets a reference to the value.
+    pub fn get(&self) -> Ref<'_, V> {
+        Ref::map(self.node.data_ref(), |kv| &kv.value)
+    }
+
+    /// Gets a mutable reference to the value.
+    pub fn get_mut(&mut self) -> RefMut<'_, V> {
+        RefMut::map(self.node.data_mut(), |kv| &mut kv.value)
+    }
+
+    /// Returns the node holding the value.
+    pub fn node(&self) -> Node<KeyValue<K, V>> {
+        self.node.duplicate()
+    }
+}
+
+/// A handle on a value of a [RBTreeMap], yielded by [Values].
+///
+/// [RBTreeMap]: struct.RBTreeMap.html
+/// [Values]: struct.Values.html
+pub struct ValueRef<K, V> {
+    node: Node<KeyValue<K, V>>,
+}
+
+impl<K, V> ValueRef<K, V> {
+    /// Gets a reference to the value.
+    pub fn get(&self) -> Ref<'_, V> {
+        Ref::map(self.node.data_ref(), |kv| &kv.value)
+    }
+
+    /// Returns the node holding the value.
+    pub fn node(&self) -> Node<KeyValue<K, V>> {
+        self.node.duplicate()
+    }
+}
+
+/// Created with the method [keys].
+///
+/// [keys]: struct.RBTreeMap.html#method.keys
+pub struct Keys<K, V> {
+    inner: Iter<KeyValue<K, V>>,
+}
+
+impl<K: Clone + Ord, V> Iterator for Keys<K, V> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        self.inner.next().map(|n| n.data().key.clone())
+    }
+}
+
+impl<K: Clone + Ord, V> DoubleEndedIterator for Keys<K, V> {
+    fn next_back(&mut self) -> Option<K> {
+        self.inner.next_back().map(|n| n.data().key.clone())
+    }
+}
+
+/// Created with the method [values] or with [values_from] for partial iterations.
+///
+/// [values]: struct.RBTreeMap.html#method.values
+/// [values_from]: struct.RBTreeMap.html#method.values_from
+pub struct Values<K, V> {
+    inner: Iter<KeyValue<K, V>>,
+}
+
+impl<K: Ord, V> Iterator for Values<K, V> {
+    type Item = ValueRef<K, V>;
+
+    fn next(&mut self) -> Option<ValueRef<K, V>> {
+        self.inner.next().map(|node| ValueRef { node })
+    }
+}
+
+impl<K: Ord, V> DoubleEndedIterator for Values<K, V> {
+    fn next_back(&mut self) -> Option<ValueRef<K, V>> {
+        self.inner.next_back().map(|node| ValueRef { node })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_remove() {
+        let mut map = RBTreeMap::new();
+        for i in 0..50 {
+            assert_eq!(map.insert(i, i * 10), None);
+        }
+        for i in (0..50).step_by(2) {
+            assert_eq!(map.insert(i, i * 100), Some(i * 10));
+        }
+        for i in (0..50).step_by(3) {
+            assert!(map.remove(&i).is_some());
+        }
+
+        let expected: Vec<(i32, i32)> = (0..50)
+            .filter(|i| i % 3 != 0)
+            .map(|i| (i, if i % 2 == 0 { i * 100 } else { i * 10 }))
+            .collect();
+        assert_eq!(map.len(), expected.len());
+        assert_eq!(
+            map.iter()
+                .map(|n| (*n.data().key(), *n.data().value()))
+                .collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[test]
+    fn remove_shared_node() {
+        let mut map = RBTreeMap::new();
+        map.insert(1, String::from("a"));
+        map.insert(2, String::from("b"));
+        map.insert(3, String::from("c"));
+        let node = map.get_node(&2).unwrap();
+        let removed = map.remove(&2).unwrap().unwrap_err();
+        assert_eq!(removed, node);
+        assert!(!node.is_attached());
+        assert_eq!(*node.data().value(), "b");
+        drop(node);
+        let kv = removed.try_into_data().ok().unwrap();
+        assert_eq!((kv.key, kv.value), (2, String::from("b")));
+
+        let value = map.values().next().unwrap();
+        assert!(map.remove_entry(&1).unwrap().is_err());
+        assert_eq!(*value.get(), "a");
+
+        let node = map.iter().next().unwrap();
+        match map.entry(3) {
+            Entry::Occupied(entry) => assert!(entry.remove().is_err()),
+            Entry::Vacant(_) => unreachable!(),
+        }
+        assert_eq!(*node.data().value(), "c");
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn entry() {
+        let mut map = RBTreeMap::new();
+        match map.entry(1) {
+            Entry::Vacant(entry) => {
+                assert_eq!(*entry.key(), 1);
+                *entry.insert(String::from("a")).get_mut() += "b";
+            }
+            Entry::Occupied(_) => unreachable!(),
+        }
+        match map.entry(1) {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(*entry.key(), 1);
+                assert_eq!(*entry.get(), "ab");
+                assert_eq!(entry.insert(String::from("c")), "ab");
+                assert_eq!(entry.remove_entry(), Ok((1, String::from("c"))));
+            }
+            Entry::Vacant(_) => unreachable!(),
+        }
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn values_without_clone() {
+        struct Counter(u32);
+
+        let mut map = RBTreeMap::new();
+        for key in "abcab".chars() {
+            map.entry(key).or_insert(Counter(0)).get_mut().0 += 1;
+        }
+        if let Some(mut counter) = map.get_mut(&'c') {
+            counter.get_mut().0 *= 10;
+        }
+        let counts: Vec<_> = map.values().map(|v| v.get().0).collect();
+        assert_eq!(counts, vec![2, 2, 10]);
+        let mut node = map.get_node(&'a').unwrap();
+        map.remove_node(&mut node);
+        assert_eq!(node.data().value().0, 2);
+        assert_eq!(map.remove(&'b').and_then(Result::ok).map(|v| v.0), Some(2));
+        match map.entry('c') {
+            Entry::Occupied(entry) => assert_eq!(
+                entry.remove_entry().ok().map(|(k, v)| (k, v.0)),
+                Some(('c', 10))
+            ),
+            Entry::Vacant(_) => unreachable!(),
+        }
+        assert!(map.is_empty());
+    }
+}
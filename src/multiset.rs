@@ -0,0 +1,410 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::FromIterator;
+
+use crate::node::Node;
+use crate::tree::{Iter, RBTreeSet};
+
+/// Type of the data stored in the nodes of an [RBTreeMultiSet].
+///
+/// Entries are ordered and compared by value only.
+///
+/// [RBTreeMultiSet]: struct.RBTreeMultiSet.html
+#[derive(Clone, Debug)]
+pub struct Counted<T> {
+    value: T,
+    count: usize,
+}
+
+impl<T> Counted<T> {
+    /// Returns a reference to the value of the entry.
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns the number of occurrences of the value.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl<T: Ord> Ord for Counted<T> {
+    fn cmp(&self, other: &Counted<T>) -> Ordering {
+        self.value.cmp(&other.value)
+    }
+}
+
+impl<T: Ord> PartialOrd for Counted<T> {
+    fn partial_cmp(&self, other: &Counted<T>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Ord> PartialEq for Counted<T> {
+    fn eq(&self, other: &Counted<T>) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T: Ord> Eq for Counted<T> {}
+
+/// A multiset based on a RB-Tree for efficient operations.
+///
+/// Equal values share a single node holding their number of occurrences, so insert,
+/// delete and search stay `O(log n)` for `n` distinct values. Partial iterations are
+/// possible providing a `Node` reference using [iter_from] or [values_from].
+///
+/// [iter_from]: #method.iter_from
+/// [values_from]: #method.values_from
+///
+/// # Examples
+///
+/// ```
+/// use rbtset::RBTreeMultiSet;
+///
+/// let mut events = RBTreeMultiSet::new();
+/// events.insert(10);
+/// events.insert(12);
+/// events.insert(10);
+///
+/// assert_eq!(events.count(&10), 2);
+/// assert_eq!(events.len(), 3);
+/// assert_eq!(events.values().collect::<Vec<_>>(), vec![10, 10, 12]);
+///
+/// events.remove_one(&10);
+/// assert_eq!(events.values().collect::<Vec<_>>(), vec![10, 12]);
+/// ```
+pub struct RBTreeMultiSet<T> {
+    set: RBTreeSet<Counted<T>>,
+    length: usize,
+}
+
+impl<T: Ord> RBTreeMultiSet<T> {
+    /// Makes a new `RBTreeMultiSet`.
+    pub fn new() -> RBTreeMultiSet<T> {
+        RBTreeMultiSet {
+            set: RBTreeSet::new(),
+            length: 0,
+        }
+    }
+
+    /// Adds an occurrence of a value to the multiset, returning the node holding it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMultiSet;
+    ///
+    /// let mut set = RBTreeMultiSet::new();
+    /// set.insert("a");
+    /// let node = set.insert("a");
+    /// assert_eq!(node.data().count(), 2);
+    /// ```
+    pub fn insert(&mut self, value: T) -> Node<Counted<T>> {
+        self.length += 1;
+        match self.get_node(&value) {
+            Some(node) => {
+                node.data_mut().count += 1;
+                node
+            }
+            None => self
+                .set
+                .insert(Counted { value, count: 1 })
+                .expect("value not present"),
+        }
+    }
+
+    /// Returns the node in the multiset, if any, that is matching the given value.
+    ///
+    /// The value may be any borrowed form of the multiset's value type, but the
+    /// ordering on the borrowed form must match the ordering on the value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMultiSet;
+    ///
+    /// let set: RBTreeMultiSet<_> = vec![1, 1, 2].into_iter().collect();
+    /// assert_eq!(set.get_node(&1).unwrap().data().count(), 2);
+    /// assert_eq!(set.get_node(&3), None);
+    /// ```
+    pub fn get_node<Q>(&self, value: &Q) -> Option<Node<Counted<T>>>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.set.find_node(|c| value.cmp(c.value.borrow()))
+    }
+
+    /// Returns the number of occurrences of the given value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMultiSet;
+    ///
+    /// let set: RBTreeMultiSet<_> = vec![1, 1, 2].into_iter().collect();
+    /// assert_eq!(set.count(&1), 2);
+    /// assert_eq!(set.count(&3), 0);
+    /// ```
+    pub fn count<Q>(&self, value: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get_node(value).map_or(0, |n| n.data().count)
+    }
+
+    /// Returns true if the multiset contains at least one occurrence of the value.
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get_node(value).is_some()
+    }
+
+    /// Removes one occurrence of a value. Returns whether the value was present.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMultiSet;
+    ///
+    /// let mut set: RBTreeMultiSet<_> = vec![1, 1].into_iter().collect();
+    /// assert_eq!(set.remove_one(&1), true);
+    /// assert_eq!(set.remove_one(&1), true);
+    /// assert_eq!(set.remove_one(&1), false);
+    /// ```
+    pub fn remove_one<Q>(&mut self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let node = match self.get_node(value) {
+            Some(n) => n,
+            None => return false,
+        };
+        self.length -= 1;
+        let count = node.data().count;
+        if count > 1 {
+            node.data_mut().count = count - 1;
+        } else {
+            self.set.unlink(&node);
+        }
+        true
+    }
+
+    /// Removes all the occurrences of a value. Returns the number of removed
+    /// occurrences.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMultiSet;
+    ///
+    /// let mut set: RBTreeMultiSet<_> = vec![1, 1, 2].into_iter().collect();
+    /// assert_eq!(set.remove_all(&1), 2);
+    /// assert_eq!(set.remove_all(&1), 0);
+    /// assert_eq!(set.len(), 1);
+    /// ```
+    pub fn remove_all<Q>(&mut self, value: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let node = match self.get_node(value) {
+            Some(n) => n,
+            None => return 0,
+        };
+        let count = node.data().count;
+        self.length -= count;
+        self.set.unlink(&node);
+        count
+    }
+
+    /// Clears the multiset, removing all values.
+    pub fn clear(&mut self) {
+        self.set.clear();
+        self.length = 0;
+    }
+
+    /// Returns the node holding the smallest value, if not empty.
+    pub fn first(&self) -> Option<Node<Counted<T>>> {
+        self.set.first()
+    }
+
+    /// Returns the node holding the greatest value, if not empty.
+    pub fn last(&self) -> Option<Node<Counted<T>>> {
+        self.set.last()
+    }
+
+    /// Returns the number of occurrences in the multiset.
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    /// Returns the number of distinct values in the multiset.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMultiSet;
+    ///
+    /// let set: RBTreeMultiSet<_> = vec![1, 1, 2].into_iter().collect();
+    /// assert_eq!(set.len(), 3);
+    /// assert_eq!(set.distinct_len(), 2);
+    /// ```
+    pub fn distinct_len(&self) -> usize {
+        self.set.len()
+    }
+
+    /// Returns true if the multiset contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Gets an iterator that visits the nodes of the distinct values in ascending order.
+    pub fn iter(&self) -> Iter<Counted<T>> {
+        self.set.iter()
+    }
+
+    /// Gets an iterator that visits the nodes of the distinct values in ascending order,
+    /// starting at the given node.
+    pub fn iter_from(&self, node: &Node<Counted<T>>) -> Iter<Counted<T>> {
+        self.set.iter_from(node)
+    }
+
+    /// Gets an iterator that visits every occurrence of the values in ascending order.
+    pub fn values(&self) -> Occurrences<T>
+    where
+        T: Clone,
+    {
+        Occurrences {
+            inner: self.iter(),
+            current: None,
+        }
+    }
+
+    /// Gets an iterator that visits every occurrence of the values in ascending order,
+    /// starting at the given node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeMultiSet;
+    ///
+    /// let set: RBTreeMultiSet<_> = vec![3, 1, 2, 2].into_iter().collect();
+    /// let node = set.get_node(&2).unwrap();
+    /// assert_eq!(set.values_from(&node).collect::<Vec<_>>(), vec![2, 2, 3]);
+    /// ```
+    pub fn values_from(&self, node: &Node<Counted<T>>) -> Occurrences<T>
+    where
+        T: Clone,
+    {
+        Occurrences {
+            inner: self.iter_from(node),
+            current: None,
+        }
+    }
+}
+
+impl<T: Ord> Default for RBTreeMultiSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for RBTreeMultiSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RBTreeMultiSet {{ length: {} }}", self.length)
+    }
+}
+
+impl<T: Clone> Clone for RBTreeMultiSet<T> {
+    fn clone(&self) -> Self {
+        RBTreeMultiSet {
+            set: self.set.clone(),
+            length: self.length,
+        }
+    }
+}
+
+impl<T: Ord> FromIterator<T> for RBTreeMultiSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut s = Self::new();
+        for i in iter {
+            s.insert(i);
+        }
+        s
+    }
+}
+
+/// Created with the method [values] or with [values_from] for partial iterations.
+///
+/// [values]: struct.RBTreeMultiSet.html#method.values
+/// [values_from]: struct.RBTreeMultiSet.html#method.values_from
+pub struct Occurrences<T> {
+    inner: Iter<Counted<T>>,
+    current: Option<(T, usize)>,
+}
+
+impl<T: Clone> Iterator for Occurrences<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        match self.current.take() {
+            Some((value, remaining)) if remaining > 1 => {
+                self.current = Some((value.clone(), remaining - 1));
+                Some(value)
+            }
+            Some((value, _)) => Some(value),
+            None => {
+                let node = self.inner.next()?;
+                let data = node.data();
+                self.current = Some((data.value.clone(), data.count));
+                self.next()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn occurrences() {
+        let mut set = RBTreeMultiSet::new();
+        let mut oracle = Vec::new();
+        for i in 0..100 {
+            let value = (i * 7) % 13;
+            set.insert(value);
+            oracle.push(value);
+        }
+        for i in 0..30 {
+            let value = (i * 5) % 17;
+            if let Some(index) = oracle.iter().position(|v| *v == value) {
+                oracle.remove(index);
+                assert!(set.remove_one(&value));
+            } else {
+                assert!(!set.remove_one(&value));
+            }
+        }
+        let removed = oracle.iter().filter(|v| **v == 4).count();
+        assert_eq!(set.remove_all(&4), removed);
+        oracle.retain(|v| *v != 4);
+        oracle.sort_unstable();
+
+        assert_eq!(set.len(), oracle.len());
+        assert_eq!(set.values().collect::<Vec<_>>(), oracle);
+        for value in 0..13 {
+            let expected = oracle.iter().filter(|v| **v == value).count();
+            assert_eq!(set.count(&value), expected);
+        }
+        let node = set.get_node(&6).unwrap();
+        let start = oracle.iter().position(|v| *v == 6).unwrap();
+        assert_eq!(set.values_from(&node).collect::<Vec<_>>(), oracle[start..]);
+    }
+}
@@ -1,10 +1,14 @@
 use std::{
-    cell::{Ref, RefCell},
+    cell::{Ref, RefCell, RefMut},
     fmt,
+    marker::PhantomData,
     ops::Deref,
     rc::{Rc, Weak},
 };
 
+use crate::raw::{self, Storage};
+use crate::Augment;
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub(crate) enum Colour {
     Black,
@@ -21,18 +25,24 @@ impl fmt::Display for Colour {
     }
 }
 
-struct NodeData<T> {
+struct NodeData<T, A> {
     colour: Colour,
-    parent: Option<ParentNode<T>>,
-    left: Option<Node<T>>,
-    right: Option<Node<T>>,
+    detached: bool,
+    size: usize,
+    summary: A,
+    parent: Option<ParentNode<T, A>>,
+    left: Option<Node<T, A>>,
+    right: Option<Node<T, A>>,
     pub(crate) data: T,
 }
 
-impl<T> NodeData<T> {
-    fn new(data: T) -> NodeData<T> {
+impl<T, A> NodeData<T, A> {
+    fn new(data: T, summary: A) -> NodeData<T, A> {
         NodeData {
             colour: Colour::Red,
+            detached: false,
+            size: 1,
+            summary,
             parent: None,
             left: None,
             right: None,
@@ -41,65 +51,74 @@ impl<T> NodeData<T> {
     }
 }
 
-struct ParentNode<T>(Weak<RefCell<NodeData<T>>>);
+struct ParentNode<T, A>(Weak<RefCell<NodeData<T, A>>>);
 
 /// Type of the tree elements containing the actuel data.
-pub struct Node<T>(Rc<RefCell<NodeData<T>>>);
+pub struct Node<T, A = ()>(Rc<RefCell<NodeData<T, A>>>);
 
-impl<T> Node<T> {
+impl<T, A> Node<T, A> {
     pub(crate) fn id(&self) -> String {
         let address = format!("{:?}", self.0.as_ptr());
         address[2..].to_owned()
     }
 
-    pub(crate) fn duplicate(&self) -> Node<T> {
+    pub(crate) fn duplicate(&self) -> Node<T, A> {
         Node(Rc::clone(&self.0))
     }
 
-    pub(crate) fn set_data(&mut self, data: T) {
-        self.0.borrow_mut().data = data;
+    pub(crate) fn data_ref(&self) -> Ref<'_, T> {
+        Ref::map(self.0.borrow(), |nd| &nd.data)
     }
 
-    pub(crate) fn swap_data(&mut self, other: &mut Node<T>) {
-        std::mem::swap(
-            &mut self.0.borrow_mut().data,
-            &mut other.0.borrow_mut().data,
-        )
+    pub(crate) fn data_mut(&self) -> RefMut<'_, T> {
+        RefMut::map(self.0.borrow_mut(), |nd| &mut nd.data)
     }
 
-    pub(crate) fn parent(&self) -> Option<Node<T>> {
+    pub(crate) fn parent(&self) -> Option<Node<T, A>> {
         Some(Node(self.0.borrow().parent.as_ref()?.0.upgrade()?))
     }
 
     pub(crate) fn set_parent<I>(&mut self, node: I)
     where
-        I: Into<Option<Node<T>>>,
+        I: Into<Option<Node<T, A>>>,
     {
         self.0.borrow_mut().parent = node.into().map(|n| ParentNode(Rc::downgrade(&n.0)))
     }
 
-    pub(crate) fn left(&self) -> Option<Node<T>> {
+    pub(crate) fn left(&self) -> Option<Node<T, A>> {
         self.0.borrow().left.as_ref().map(Node::duplicate)
     }
 
     pub(crate) fn set_left<I>(&mut self, node: I)
     where
-        I: Into<Option<Node<T>>>,
+        I: Into<Option<Node<T, A>>>,
     {
         self.0.borrow_mut().left = node.into()
     }
 
-    pub(crate) fn right(&self) -> Option<Node<T>> {
+    pub(crate) fn right(&self) -> Option<Node<T, A>> {
         self.0.borrow().right.as_ref().map(Node::duplicate)
     }
 
     pub(crate) fn set_right<I>(&mut self, node: I)
     where
-        I: Into<Option<Node<T>>>,
+        I: Into<Option<Node<T, A>>>,
     {
         self.0.borrow_mut().right = node.into()
     }
 
+    pub(crate) fn size(&self) -> usize {
+        self.0.borrow().size
+    }
+
+    pub(crate) fn detach(&mut self) {
+        let mut nd = self.0.borrow_mut();
+        nd.detached = true;
+        nd.parent = None;
+        nd.left = None;
+        nd.right = None;
+    }
+
     pub(crate) fn colour(&self) -> Colour {
         self.0.borrow().colour
     }
@@ -108,14 +127,6 @@ impl<T> Node<T> {
         self.0.borrow_mut().colour = colour;
     }
 
-    /// Mutates the contained data in-place by applying the given closure.
-    pub fn apply<F>(&self, f: F)
-    where
-        F: Fn(&mut T),
-    {
-        f(&mut self.0.borrow_mut().data);
-    }
-
     /// Returns a reference to the contained data.
     pub fn data(&self) -> impl Deref<Target = T> + '_ {
         Ref::map(self.0.borrow(), |nd| &nd.data)
@@ -128,38 +139,139 @@ impl<T> Node<T> {
     {
         self.0.borrow().data.clone()
     }
+
+    /// Moves the contained data out of the node.
+    ///
+    /// The data stays in the node as long as other handles on the node are held, or the
+    /// node is still in its set, the node being given back in that case.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let mut set: RBTreeSet<_> = [1, 2].iter().cloned().collect();
+    /// let first = set.first().unwrap();
+    /// let node = set.pop_first().unwrap().unwrap_err();
+    /// assert_eq!(*first.data(), 1);
+    ///
+    /// let node = node.try_into_data().unwrap_err();
+    /// drop(first);
+    /// assert_eq!(node.try_into_data(), Ok(1));
+    /// ```
+    pub fn try_into_data(self) -> Result<T, Node<T, A>> {
+        Rc::try_unwrap(self.0)
+            .map(|cell| cell.into_inner().data)
+            .map_err(Node)
+    }
+
+    /// Returns true if the node still belongs to a live set.
+    ///
+    /// A node gets detached when its value is removed from its set or when the set is
+    /// cleared or dropped. Removing a value never affects the other nodes of the set,
+    /// each node keeping its value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let mut set = RBTreeSet::new();
+    /// let node = set.insert(1).unwrap();
+    /// assert!(node.is_attached());
+    /// set.clear();
+    /// assert!(!node.is_attached());
+    /// ```
+    pub fn is_attached(&self) -> bool {
+        !self.0.borrow().detached
+    }
+
+    /// Returns a reference to the summary of the subtree rooted at this node.
+    ///
+    /// See [Augment] for more informations.
+    ///
+    /// [Augment]: trait.Augment.html
+    pub fn summary(&self) -> impl Deref<Target = A> + '_ {
+        Ref::map(self.0.borrow(), |nd| &nd.summary)
+    }
 }
 
-impl<T: Ord> Node<T> {
-    pub(crate) fn is_left_child(&self) -> bool {
-        self.parent()
-            .as_ref()
-            .and_then(Node::left)
-            .as_ref()
-            .map(|n| n == self)
-            .unwrap_or(false)
-    }
-
-    pub(crate) fn sibling(&self) -> Option<Node<T>> {
-        if self.is_left_child() {
-            self.parent()?.right()
-        } else {
-            self.parent()?.left()
+impl<T, A: Augment<T>> Node<T, A> {
+    /// Mutates the contained data in-place by applying the given closure.
+    ///
+    /// The summaries of the node and its ancestors are updated accordingly.
+    pub fn apply<F>(&self, f: F)
+    where
+        F: Fn(&mut T),
+    {
+        f(&mut self.0.borrow_mut().data);
+        // zero-sized summaries hold no information, there is nothing to update
+        if std::mem::size_of::<A>() != 0 {
+            raw::refresh_upward(&mut Nodes::new(), Some(self.duplicate()));
         }
     }
+}
+
+impl<T, A> Node<T, A> {
+    /// Returns the position of the node in its set, in ascending order.
+    ///
+    /// This operation is `O(log n)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let set: RBTreeSet<_> = [10, 30, 20].iter().cloned().collect();
+    /// let node = set.get_node(&20).unwrap();
+    /// assert_eq!(node.index(), 1);
+    /// assert_eq!(set.iter_from(&node).count(), set.len() - node.index());
+    /// ```
+    pub fn index(&self) -> usize {
+        raw::position(&Nodes::new(), self)
+    }
+
+    /// Returns the next node of the set in ascending order, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let mut set: RBTreeSet<_> = [1, 3].iter().cloned().collect();
+    /// let node = set.insert(2).unwrap();
+    /// assert_eq!(*node.next().unwrap().data(), 3);
+    /// assert_eq!(node.next().unwrap().next(), None);
+    /// ```
+    pub fn next(&self) -> Option<Node<T, A>> {
+        raw::successor(&Nodes::new(), self)
+    }
 
-    pub(crate) fn uncle(&self) -> Option<Node<T>> {
-        self.parent()?.sibling()
+    /// Returns the previous node of the set in ascending order, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let mut set: RBTreeSet<_> = [1, 3].iter().cloned().collect();
+    /// let node = set.insert(2).unwrap();
+    /// assert_eq!(*node.prev().unwrap().data(), 1);
+    /// assert_eq!(node.prev().unwrap().prev(), None);
+    /// ```
+    pub fn prev(&self) -> Option<Node<T, A>> {
+        raw::predecessor(&Nodes::new(), self)
     }
 }
 
-impl<T> From<T> for Node<T> {
-    fn from(data: T) -> Node<T> {
-        Node(Rc::new(RefCell::new(NodeData::new(data))))
+impl<T, A: Augment<T>> From<T> for Node<T, A> {
+    fn from(data: T) -> Node<T, A> {
+        let summary = A::summarize(&data);
+        Node(Rc::new(RefCell::new(NodeData::new(data, summary))))
     }
 }
 
-impl<T: fmt::Debug> fmt::Debug for Node<T> {
+impl<T: fmt::Debug, A> fmt::Debug for Node<T, A> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
@@ -173,8 +285,105 @@ impl<T: fmt::Debug> fmt::Debug for Node<T> {
     }
 }
 
-impl<T> PartialEq for Node<T> {
-    fn eq(&self, other: &Node<T>) -> bool {
+impl<T, A> PartialEq for Node<T, A> {
+    fn eq(&self, other: &Node<T, A>) -> bool {
         Rc::ptr_eq(&self.0, &other.0)
     }
 }
+
+/// The storage of the nodes linked with `Rc`, see [Storage].
+///
+/// The nodes own each other, the storage itself holds nothing.
+///
+/// [Storage]: ../raw/trait.Storage.html
+pub(crate) struct Nodes<T, A>(PhantomData<Node<T, A>>);
+
+impl<T, A> Nodes<T, A> {
+    pub(crate) fn new() -> Nodes<T, A> {
+        Nodes(PhantomData)
+    }
+}
+
+impl<T, A> Storage for Nodes<T, A> {
+    type Value = T;
+    type Summary = A;
+    type Link = Node<T, A>;
+    /// The detached node, still holding its value.
+    type Released = Node<T, A>;
+    type Data<'a>
+        = Ref<'a, T>
+    where
+        Self: 'a;
+    type SummaryRef<'a>
+        = Ref<'a, A>
+    where
+        Self: 'a;
+
+    fn duplicate(link: &Node<T, A>) -> Node<T, A> {
+        link.duplicate()
+    }
+
+    fn alloc(&mut self, data: T, summary: A) -> Node<T, A> {
+        Node(Rc::new(RefCell::new(NodeData::new(data, summary))))
+    }
+
+    fn release(&mut self, node: Node<T, A>) -> Node<T, A> {
+        let mut node = node;
+        node.detach();
+        node
+    }
+
+    fn data<'a>(&'a self, node: &'a Node<T, A>) -> Ref<'a, T> {
+        node.data_ref()
+    }
+
+    fn set_data(&mut self, node: &Node<T, A>, data: T) {
+        *node.data_mut() = data;
+    }
+
+    fn summary<'a>(&'a self, node: &'a Node<T, A>) -> Ref<'a, A> {
+        Ref::map(node.0.borrow(), |nd| &nd.summary)
+    }
+
+    fn size(&self, node: &Node<T, A>) -> usize {
+        node.size()
+    }
+
+    fn set_subtree(&mut self, node: &Node<T, A>, size: usize, summary: A) {
+        let mut nd = node.0.borrow_mut();
+        nd.size = size;
+        nd.summary = summary;
+    }
+
+    fn parent(&self, node: &Node<T, A>) -> Option<Node<T, A>> {
+        node.parent()
+    }
+
+    fn set_parent(&mut self, node: &Node<T, A>, parent: Option<Node<T, A>>) {
+        node.duplicate().set_parent(parent);
+    }
+
+    fn left(&self, node: &Node<T, A>) -> Option<Node<T, A>> {
+        node.left()
+    }
+
+    fn set_left(&mut self, node: &Node<T, A>, left: Option<Node<T, A>>) {
+        node.duplicate().set_left(left);
+    }
+
+    fn right(&self, node: &Node<T, A>) -> Option<Node<T, A>> {
+        node.right()
+    }
+
+    fn set_right(&mut self, node: &Node<T, A>, right: Option<Node<T, A>>) {
+        node.duplicate().set_right(right);
+    }
+
+    fn colour(&self, node: &Node<T, A>) -> Colour {
+        node.colour()
+    }
+
+    fn set_colour(&mut self, node: &Node<T, A>, colour: Colour) {
+        node.duplicate().set_colour(colour);
+    }
+}
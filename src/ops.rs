@@ -0,0 +1,863 @@
+use std::cmp::Ordering;
+use std::ops::{BitAnd, BitOr, BitXor, Sub};
+
+use crate::arena::{ArenaIter, ArenaRBTreeSet};
+use crate::node::Node;
+use crate::tree::{Iter, RBTreeSet};
+use crate::{Augment, Compare, Natural};
+
+impl<T, A: Augment<T>, C: Compare<T>> RBTreeSet<T, A, C> {
+    /// Visits the nodes representing the union, i.e., all the values in `self` or
+    /// `other`, without duplicates, in ascending order.
+    ///
+    /// Values are matched with the comparator of `self`, when a value of `self` is
+    /// equivalent to a value of `other` the node of `self` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let a: RBTreeSet<_> = [1, 2].iter().cloned().collect();
+    /// let b: RBTreeSet<_> = [2, 3].iter().cloned().collect();
+    ///
+    /// let union: Vec<_> = a.union(&b).map(|n| n.clone_data()).collect();
+    /// assert_eq!(union, [1, 2, 3]);
+    /// ```
+    pub fn union<'a>(&'a self, other: &RBTreeSet<T, A, C>) -> Union<'a, T, A, C> {
+        Union(MergeIter::new(self.iter(), other.iter(), self.comparator()))
+    }
+
+    /// Visits the nodes representing the intersection, i.e., the values that are both
+    /// in `self` and `other`, in ascending order.
+    ///
+    /// The nodes returned are the ones of `self`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let a: RBTreeSet<_> = [1, 2].iter().cloned().collect();
+    /// let b: RBTreeSet<_> = [2, 3].iter().cloned().collect();
+    ///
+    /// let intersection: Vec<_> = a.intersection(&b).map(|n| n.clone_data()).collect();
+    /// assert_eq!(intersection, [2]);
+    /// ```
+    pub fn intersection<'a>(&'a self, other: &RBTreeSet<T, A, C>) -> Intersection<'a, T, A, C> {
+        Intersection(MergeIter::new(self.iter(), other.iter(), self.comparator()))
+    }
+
+    /// Visits the nodes representing the difference, i.e., the values that are in
+    /// `self` but not in `other`, in ascending order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let a: RBTreeSet<_> = [1, 2].iter().cloned().collect();
+    /// let b: RBTreeSet<_> = [2, 3].iter().cloned().collect();
+    ///
+    /// let difference: Vec<_> = a.difference(&b).map(|n| n.clone_data()).collect();
+    /// assert_eq!(difference, [1]);
+    /// ```
+    pub fn difference<'a>(&'a self, other: &RBTreeSet<T, A, C>) -> Difference<'a, T, A, C> {
+        Difference(MergeIter::new(self.iter(), other.iter(), self.comparator()))
+    }
+
+    /// Visits the nodes representing the symmetric difference, i.e., the values that
+    /// are in `self` or in `other` but not in both, in ascending order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let a: RBTreeSet<_> = [1, 2].iter().cloned().collect();
+    /// let b: RBTreeSet<_> = [2, 3].iter().cloned().collect();
+    ///
+    /// let sym_diff: Vec<_> = a.symmetric_difference(&b).map(|n| n.clone_data()).collect();
+    /// assert_eq!(sym_diff, [1, 3]);
+    /// ```
+    pub fn symmetric_difference<'a>(
+        &'a self,
+        other: &RBTreeSet<T, A, C>,
+    ) -> SymmetricDifference<'a, T, A, C> {
+        SymmetricDifference(MergeIter::new(self.iter(), other.iter(), self.comparator()))
+    }
+
+    /// Returns true if the set is a subset of another, i.e., `other` contains at least
+    /// all the values in `self`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let sup: RBTreeSet<_> = [1, 2, 3].iter().cloned().collect();
+    /// let mut set = RBTreeSet::new();
+    ///
+    /// assert_eq!(set.is_subset(&sup), true);
+    /// set.insert(2);
+    /// assert_eq!(set.is_subset(&sup), true);
+    /// set.insert(4);
+    /// assert_eq!(set.is_subset(&sup), false);
+    /// ```
+    pub fn is_subset(&self, other: &RBTreeSet<T, A, C>) -> bool {
+        self.len() <= other.len() && self.difference(other).next().is_none()
+    }
+
+    /// Returns true if the set is a superset of another, i.e., `self` contains at least
+    /// all the values in `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let sub: RBTreeSet<_> = [1, 2].iter().cloned().collect();
+    /// let mut set = RBTreeSet::new();
+    ///
+    /// assert_eq!(set.is_superset(&sub), false);
+    /// set.insert(0);
+    /// set.insert(1);
+    /// assert_eq!(set.is_superset(&sub), false);
+    /// set.insert(2);
+    /// assert_eq!(set.is_superset(&sub), true);
+    /// ```
+    pub fn is_superset(&self, other: &RBTreeSet<T, A, C>) -> bool {
+        other.is_subset(self)
+    }
+
+    /// Returns true if `self` has no values in common with `other`. This is equivalent
+    /// to checking for an empty intersection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let a: RBTreeSet<_> = [1, 2, 3].iter().cloned().collect();
+    /// let mut b = RBTreeSet::new();
+    ///
+    /// assert_eq!(a.is_disjoint(&b), true);
+    /// b.insert(4);
+    /// assert_eq!(a.is_disjoint(&b), true);
+    /// b.insert(1);
+    /// assert_eq!(a.is_disjoint(&b), false);
+    /// ```
+    pub fn is_disjoint(&self, other: &RBTreeSet<T, A, C>) -> bool {
+        self.intersection(other).next().is_none()
+    }
+}
+
+impl<T, A: Augment<T>, C: Compare<T>> ArenaRBTreeSet<T, A, C> {
+    /// Visits the values representing the union, i.e., all the values in `self` or
+    /// `other`, without duplicates, in ascending order.
+    ///
+    /// Values are matched with the comparator of `self`, when a value of `self` is
+    /// equivalent to a value of `other` the value of `self` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let a: ArenaRBTreeSet<_> = [1, 2].iter().cloned().collect();
+    /// let b: ArenaRBTreeSet<_> = [2, 3].iter().cloned().collect();
+    ///
+    /// let union: Vec<_> = a.union(&b).cloned().collect();
+    /// assert_eq!(union, [1, 2, 3]);
+    /// ```
+    pub fn union<'a>(&'a self, other: &'a ArenaRBTreeSet<T, A, C>) -> ArenaUnion<'a, T, A, C> {
+        ArenaUnion(MergeIter::new(self.iter(), other.iter(), self.comparator()))
+    }
+
+    /// Visits the values representing the intersection, i.e., the values that are both
+    /// in `self` and `other`, in ascending order.
+    ///
+    /// The values returned are the ones of `self`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let a: ArenaRBTreeSet<_> = [1, 2].iter().cloned().collect();
+    /// let b: ArenaRBTreeSet<_> = [2, 3].iter().cloned().collect();
+    ///
+    /// let intersection: Vec<_> = a.intersection(&b).cloned().collect();
+    /// assert_eq!(intersection, [2]);
+    /// ```
+    pub fn intersection<'a>(
+        &'a self,
+        other: &'a ArenaRBTreeSet<T, A, C>,
+    ) -> ArenaIntersection<'a, T, A, C> {
+        ArenaIntersection(MergeIter::new(self.iter(), other.iter(), self.comparator()))
+    }
+
+    /// Visits the values representing the difference, i.e., the values that are in
+    /// `self` but not in `other`, in ascending order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let a: ArenaRBTreeSet<_> = [1, 2].iter().cloned().collect();
+    /// let b: ArenaRBTreeSet<_> = [2, 3].iter().cloned().collect();
+    ///
+    /// let difference: Vec<_> = a.difference(&b).cloned().collect();
+    /// assert_eq!(difference, [1]);
+    /// ```
+    pub fn difference<'a>(
+        &'a self,
+        other: &'a ArenaRBTreeSet<T, A, C>,
+    ) -> ArenaDifference<'a, T, A, C> {
+        ArenaDifference(MergeIter::new(self.iter(), other.iter(), self.comparator()))
+    }
+
+    /// Visits the values representing the symmetric difference, i.e., the values that
+    /// are in `self` or in `other` but not in both, in ascending order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let a: ArenaRBTreeSet<_> = [1, 2].iter().cloned().collect();
+    /// let b: ArenaRBTreeSet<_> = [2, 3].iter().cloned().collect();
+    ///
+    /// let sym_diff: Vec<_> = a.symmetric_difference(&b).cloned().collect();
+    /// assert_eq!(sym_diff, [1, 3]);
+    /// ```
+    pub fn symmetric_difference<'a>(
+        &'a self,
+        other: &'a ArenaRBTreeSet<T, A, C>,
+    ) -> ArenaSymmetricDifference<'a, T, A, C> {
+        ArenaSymmetricDifference(MergeIter::new(self.iter(), other.iter(), self.comparator()))
+    }
+
+    /// Returns true if the set is a subset of another, i.e., `other` contains at least
+    /// all the values in `self`.
+    pub fn is_subset(&self, other: &ArenaRBTreeSet<T, A, C>) -> bool {
+        self.len() <= other.len() && self.difference(other).next().is_none()
+    }
+
+    /// Returns true if the set is a superset of another, i.e., `self` contains at least
+    /// all the values in `other`.
+    pub fn is_superset(&self, other: &ArenaRBTreeSet<T, A, C>) -> bool {
+        other.is_subset(self)
+    }
+
+    /// Returns true if `self` has no values in common with `other`. This is equivalent
+    /// to checking for an empty intersection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let a: ArenaRBTreeSet<_> = [1, 2, 3].iter().cloned().collect();
+    /// let b: ArenaRBTreeSet<_> = [3, 4].iter().cloned().collect();
+    /// let c: ArenaRBTreeSet<_> = [2, 3].iter().cloned().collect();
+    ///
+    /// assert_eq!(a.is_disjoint(&b), false);
+    /// assert_eq!(c.is_subset(&a), true);
+    /// assert_eq!(a.is_superset(&b), false);
+    /// ```
+    pub fn is_disjoint(&self, other: &ArenaRBTreeSet<T, A, C>) -> bool {
+        self.intersection(other).next().is_none()
+    }
+}
+
+/// A value visited by a [MergeIter], giving access to the data it holds.
+trait MergeItem {
+    type Data;
+
+    fn with_data<R>(&self, f: impl FnOnce(&Self::Data) -> R) -> R;
+}
+
+impl<T, A> MergeItem for Node<T, A> {
+    type Data = T;
+
+    fn with_data<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        f(&self.data())
+    }
+}
+
+impl<T> MergeItem for &T {
+    type Data = T;
+
+    fn with_data<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        f(self)
+    }
+}
+
+type Heads<I> = (Option<I>, Option<I>);
+
+/// Walks two sets in ascending order side by side.
+struct MergeIter<'a, I: Iterator, C> {
+    a: I,
+    b: I,
+    a_head: Option<I::Item>,
+    b_head: Option<I::Item>,
+    comparator: &'a C,
+}
+
+impl<'a, I, C> MergeIter<'a, I, C>
+where
+    I: Iterator,
+    I::Item: MergeItem,
+    C: Compare<<I::Item as MergeItem>::Data>,
+{
+    fn new(a: I, b: I, comparator: &'a C) -> MergeIter<'a, I, C> {
+        MergeIter {
+            a,
+            b,
+            a_head: None,
+            b_head: None,
+            comparator,
+        }
+    }
+
+    /// Returns the next smallest value of each side, both are returned when matching.
+    fn nexts(&mut self) -> Heads<I::Item> {
+        let a = self.a_head.take().or_else(|| self.a.next());
+        let b = self.b_head.take().or_else(|| self.b.next());
+        match (a, b) {
+            (Some(a), Some(b)) => {
+                let comparator = self.comparator;
+                let (equivalent, ordering) = a.with_data(|x| {
+                    b.with_data(|y| (comparator.equivalent(x, y), comparator.compare(x, y)))
+                });
+                if equivalent {
+                    return (Some(a), Some(b));
+                }
+                // values ordered alike without being equivalent are visited `a` first
+                if ordering == Ordering::Greater {
+                    self.a_head = Some(a);
+                    (None, Some(b))
+                } else {
+                    self.b_head = Some(b);
+                    (Some(a), None)
+                }
+            }
+            heads => heads,
+        }
+    }
+
+    fn next_union(&mut self) -> Option<I::Item> {
+        let (a, b) = self.nexts();
+        a.or(b)
+    }
+
+    fn next_intersection(&mut self) -> Option<I::Item> {
+        loop {
+            match self.nexts() {
+                (Some(a), Some(_)) => return Some(a),
+                (Some(_), None) if self.b_head.is_some() => {}
+                (None, Some(_)) if self.a_head.is_some() => {}
+                // one side ran out, nothing else can match
+                _ => return None,
+            }
+        }
+    }
+
+    fn next_difference(&mut self) -> Option<I::Item> {
+        loop {
+            match self.nexts() {
+                (Some(a), None) => return Some(a),
+                (Some(_), Some(_)) => {}
+                (None, Some(_)) if self.a_head.is_some() => {}
+                // `self` ran out, the rest of `other` does not matter
+                _ => return None,
+            }
+        }
+    }
+
+    fn next_symmetric_difference(&mut self) -> Option<I::Item> {
+        loop {
+            match self.nexts() {
+                (Some(a), None) => return Some(a),
+                (None, Some(b)) => return Some(b),
+                (None, None) => return None,
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Created with the method [union].
+///
+/// [union]: struct.RBTreeSet.html#method.union
+pub struct Union<'a, T, A = (), C = Natural>(MergeIter<'a, Iter<T, A>, C>);
+
+impl<T, A: Augment<T>, C: Compare<T>> Iterator for Union<'_, T, A, C> {
+    type Item = Node<T, A>;
+
+    fn next(&mut self) -> Option<Node<T, A>> {
+        self.0.next_union()
+    }
+}
+
+/// Created with the method [intersection].
+///
+/// [intersection]: struct.RBTreeSet.html#method.intersection
+pub struct Intersection<'a, T, A = (), C = Natural>(MergeIter<'a, Iter<T, A>, C>);
+
+impl<T, A: Augment<T>, C: Compare<T>> Iterator for Intersection<'_, T, A, C> {
+    type Item = Node<T, A>;
+
+    fn next(&mut self) -> Option<Node<T, A>> {
+        self.0.next_intersection()
+    }
+}
+
+/// Created with the method [difference].
+///
+/// [difference]: struct.RBTreeSet.html#method.difference
+pub struct Difference<'a, T, A = (), C = Natural>(MergeIter<'a, Iter<T, A>, C>);
+
+impl<T, A: Augment<T>, C: Compare<T>> Iterator for Difference<'_, T, A, C> {
+    type Item = Node<T, A>;
+
+    fn next(&mut self) -> Option<Node<T, A>> {
+        self.0.next_difference()
+    }
+}
+
+/// Created with the method [symmetric_difference].
+///
+/// [symmetric_difference]: struct.RBTreeSet.html#method.symmetric_difference
+pub struct SymmetricDifference<'a, T, A = (), C = Natural>(MergeIter<'a, Iter<T, A>, C>);
+
+impl<T, A: Augment<T>, C: Compare<T>> Iterator for SymmetricDifference<'_, T, A, C> {
+    type Item = Node<T, A>;
+
+    fn next(&mut self) -> Option<Node<T, A>> {
+        self.0.next_symmetric_difference()
+    }
+}
+
+impl<T, A, C> BitOr<&RBTreeSet<T, A, C>> for &RBTreeSet<T, A, C>
+where
+    T: Clone,
+    A: Augment<T>,
+    C: Compare<T> + Clone,
+{
+    type Output = RBTreeSet<T, A, C>;
+
+    /// Returns the union of `self` and `rhs` as a new `RBTreeSet<T, A, C>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let a: RBTreeSet<_> = vec![1, 2, 3].into_iter().collect();
+    /// let b: RBTreeSet<_> = vec![3, 4, 5].into_iter().collect();
+    ///
+    /// let result = &a | &b;
+    /// assert_eq!(result.values().collect::<Vec<_>>(), [1, 2, 3, 4, 5]);
+    /// ```
+    fn bitor(self, rhs: &RBTreeSet<T, A, C>) -> RBTreeSet<T, A, C> {
+        let mut set = self.new_like();
+        set.extend(self.union(rhs).map(|n| n.clone_data()));
+        set
+    }
+}
+
+impl<T, A, C> BitAnd<&RBTreeSet<T, A, C>> for &RBTreeSet<T, A, C>
+where
+    T: Clone,
+    A: Augment<T>,
+    C: Compare<T> + Clone,
+{
+    type Output = RBTreeSet<T, A, C>;
+
+    /// Returns the intersection of `self` and `rhs` as a new `RBTreeSet<T, A, C>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let a: RBTreeSet<_> = vec![1, 2, 3].into_iter().collect();
+    /// let b: RBTreeSet<_> = vec![2, 3, 4].into_iter().collect();
+    ///
+    /// let result = &a & &b;
+    /// assert_eq!(result.values().collect::<Vec<_>>(), [2, 3]);
+    /// ```
+    fn bitand(self, rhs: &RBTreeSet<T, A, C>) -> RBTreeSet<T, A, C> {
+        let mut set = self.new_like();
+        set.extend(self.intersection(rhs).map(|n| n.clone_data()));
+        set
+    }
+}
+
+impl<T, A, C> Sub<&RBTreeSet<T, A, C>> for &RBTreeSet<T, A, C>
+where
+    T: Clone,
+    A: Augment<T>,
+    C: Compare<T> + Clone,
+{
+    type Output = RBTreeSet<T, A, C>;
+
+    /// Returns the difference of `self` and `rhs` as a new `RBTreeSet<T, A, C>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let a: RBTreeSet<_> = vec![1, 2, 3].into_iter().collect();
+    /// let b: RBTreeSet<_> = vec![3, 4, 5].into_iter().collect();
+    ///
+    /// let result = &a - &b;
+    /// assert_eq!(result.values().collect::<Vec<_>>(), [1, 2]);
+    /// ```
+    fn sub(self, rhs: &RBTreeSet<T, A, C>) -> RBTreeSet<T, A, C> {
+        let mut set = self.new_like();
+        set.extend(self.difference(rhs).map(|n| n.clone_data()));
+        set
+    }
+}
+
+impl<T, A, C> BitXor<&RBTreeSet<T, A, C>> for &RBTreeSet<T, A, C>
+where
+    T: Clone,
+    A: Augment<T>,
+    C: Compare<T> + Clone,
+{
+    type Output = RBTreeSet<T, A, C>;
+
+    /// Returns the symmetric difference of `self` and `rhs` as a new `RBTreeSet<T, A, C>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let a: RBTreeSet<_> = vec![1, 2, 3].into_iter().collect();
+    /// let b: RBTreeSet<_> = vec![2, 3, 4].into_iter().collect();
+    ///
+    /// let result = &a ^ &b;
+    /// assert_eq!(result.values().collect::<Vec<_>>(), [1, 4]);
+    /// ```
+    fn bitxor(self, rhs: &RBTreeSet<T, A, C>) -> RBTreeSet<T, A, C> {
+        let mut set = self.new_like();
+        set.extend(self.symmetric_difference(rhs).map(|n| n.clone_data()));
+        set
+    }
+}
+
+/// Created with the method [union].
+///
+/// [union]: struct.ArenaRBTreeSet.html#method.union
+pub struct ArenaUnion<'a, T, A = (), C = Natural>(MergeIter<'a, ArenaIter<'a, T, A, C>, C>);
+
+impl<'a, T, A, C: Compare<T>> Iterator for ArenaUnion<'a, T, A, C> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.0.next_union()
+    }
+}
+
+/// Created with the method [intersection].
+///
+/// [intersection]: struct.ArenaRBTreeSet.html#method.intersection
+pub struct ArenaIntersection<'a, T, A = (), C = Natural>(MergeIter<'a, ArenaIter<'a, T, A, C>, C>);
+
+impl<'a, T, A, C: Compare<T>> Iterator for ArenaIntersection<'a, T, A, C> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.0.next_intersection()
+    }
+}
+
+/// Created with the method [difference].
+///
+/// [difference]: struct.ArenaRBTreeSet.html#method.difference
+pub struct ArenaDifference<'a, T, A = (), C = Natural>(MergeIter<'a, ArenaIter<'a, T, A, C>, C>);
+
+impl<'a, T, A, C: Compare<T>> Iterator for ArenaDifference<'a, T, A, C> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.0.next_difference()
+    }
+}
+
+/// Created with the method [symmetric_difference].
+///
+/// [symmetric_difference]: struct.ArenaRBTreeSet.html#method.symmetric_difference
+pub struct ArenaSymmetricDifference<'a, T, A = (), C = Natural>(
+    MergeIter<'a, ArenaIter<'a, T, A, C>, C>,
+);
+
+impl<'a, T, A, C: Compare<T>> Iterator for ArenaSymmetricDifference<'a, T, A, C> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.0.next_symmetric_difference()
+    }
+}
+
+impl<T, A, C> BitOr<&ArenaRBTreeSet<T, A, C>> for &ArenaRBTreeSet<T, A, C>
+where
+    T: Clone,
+    A: Augment<T>,
+    C: Compare<T> + Clone,
+{
+    type Output = ArenaRBTreeSet<T, A, C>;
+
+    /// Returns the union of `self` and `rhs` as a new `ArenaRBTreeSet<T, A, C>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let a: ArenaRBTreeSet<_> = vec![1, 2, 3].into_iter().collect();
+    /// let b: ArenaRBTreeSet<_> = vec![3, 4, 5].into_iter().collect();
+    ///
+    /// let result = &a | &b;
+    /// assert_eq!(result.iter().cloned().collect::<Vec<_>>(), [1, 2, 3, 4, 5]);
+    /// ```
+    fn bitor(self, rhs: &ArenaRBTreeSet<T, A, C>) -> ArenaRBTreeSet<T, A, C> {
+        let mut set = self.new_like();
+        set.extend(self.union(rhs).cloned());
+        set
+    }
+}
+
+impl<T, A, C> BitAnd<&ArenaRBTreeSet<T, A, C>> for &ArenaRBTreeSet<T, A, C>
+where
+    T: Clone,
+    A: Augment<T>,
+    C: Compare<T> + Clone,
+{
+    type Output = ArenaRBTreeSet<T, A, C>;
+
+    /// Returns the intersection of `self` and `rhs` as a new `ArenaRBTreeSet<T, A, C>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let a: ArenaRBTreeSet<_> = vec![1, 2, 3].into_iter().collect();
+    /// let b: ArenaRBTreeSet<_> = vec![2, 3, 4].into_iter().collect();
+    ///
+    /// let result = &a & &b;
+    /// assert_eq!(result.iter().cloned().collect::<Vec<_>>(), [2, 3]);
+    /// ```
+    fn bitand(self, rhs: &ArenaRBTreeSet<T, A, C>) -> ArenaRBTreeSet<T, A, C> {
+        let mut set = self.new_like();
+        set.extend(self.intersection(rhs).cloned());
+        set
+    }
+}
+
+impl<T, A, C> Sub<&ArenaRBTreeSet<T, A, C>> for &ArenaRBTreeSet<T, A, C>
+where
+    T: Clone,
+    A: Augment<T>,
+    C: Compare<T> + Clone,
+{
+    type Output = ArenaRBTreeSet<T, A, C>;
+
+    /// Returns the difference of `self` and `rhs` as a new `ArenaRBTreeSet<T, A, C>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let a: ArenaRBTreeSet<_> = vec![1, 2, 3].into_iter().collect();
+    /// let b: ArenaRBTreeSet<_> = vec![3, 4, 5].into_iter().collect();
+    ///
+    /// let result = &a - &b;
+    /// assert_eq!(result.iter().cloned().collect::<Vec<_>>(), [1, 2]);
+    /// ```
+    fn sub(self, rhs: &ArenaRBTreeSet<T, A, C>) -> ArenaRBTreeSet<T, A, C> {
+        let mut set = self.new_like();
+        set.extend(self.difference(rhs).cloned());
+        set
+    }
+}
+
+impl<T, A, C> BitXor<&ArenaRBTreeSet<T, A, C>> for &ArenaRBTreeSet<T, A, C>
+where
+    T: Clone,
+    A: Augment<T>,
+    C: Compare<T> + Clone,
+{
+    type Output = ArenaRBTreeSet<T, A, C>;
+
+    /// Returns the symmetric difference of `self` and `rhs` as a new `ArenaRBTreeSet<T, A, C>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::ArenaRBTreeSet;
+    ///
+    /// let a: ArenaRBTreeSet<_> = vec![1, 2, 3].into_iter().collect();
+    /// let b: ArenaRBTreeSet<_> = vec![2, 3, 4].into_iter().collect();
+    ///
+    /// let result = &a ^ &b;
+    /// assert_eq!(result.iter().cloned().collect::<Vec<_>>(), [1, 4]);
+    /// ```
+    fn bitxor(self, rhs: &ArenaRBTreeSet<T, A, C>) -> ArenaRBTreeSet<T, A, C> {
+        let mut set = self.new_like();
+        set.extend(self.symmetric_difference(rhs).cloned());
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn values<I: Iterator<Item = Node<i32>>>(iter: I) -> Vec<i32> {
+        iter.map(|n| n.clone_data()).collect()
+    }
+
+    #[test]
+    fn set_algebra() {
+        let samples: Vec<Vec<i32>> = vec![
+            vec![],
+            vec![1],
+            vec![1, 2, 3],
+            vec![2, 4, 6, 8, 10],
+            vec![1, 3, 5, 7, 9, 11],
+            (0..20).collect(),
+        ];
+        for a in &samples {
+            for b in &samples {
+                let set_a: RBTreeSet<_> = a.iter().cloned().collect();
+                let set_b: RBTreeSet<_> = b.iter().cloned().collect();
+                let oracle_a: BTreeSet<_> = a.iter().cloned().collect();
+                let oracle_b: BTreeSet<_> = b.iter().cloned().collect();
+
+                assert_eq!(
+                    values(set_a.union(&set_b)),
+                    oracle_a.union(&oracle_b).cloned().collect::<Vec<_>>()
+                );
+                assert_eq!(
+                    values(set_a.intersection(&set_b)),
+                    oracle_a
+                        .intersection(&oracle_b)
+                        .cloned()
+                        .collect::<Vec<_>>()
+                );
+                assert_eq!(
+                    values(set_a.difference(&set_b)),
+                    oracle_a.difference(&oracle_b).cloned().collect::<Vec<_>>()
+                );
+                assert_eq!(
+                    values(set_a.symmetric_difference(&set_b)),
+                    oracle_a
+                        .symmetric_difference(&oracle_b)
+                        .cloned()
+                        .collect::<Vec<_>>()
+                );
+                assert_eq!(set_a.is_subset(&set_b), oracle_a.is_subset(&oracle_b));
+                assert_eq!(set_a.is_superset(&set_b), oracle_a.is_superset(&oracle_b));
+                assert_eq!(set_a.is_disjoint(&set_b), oracle_a.is_disjoint(&oracle_b));
+                assert_eq!(
+                    (&set_a ^ &set_b).values().collect::<Vec<_>>(),
+                    values(set_a.symmetric_difference(&set_b))
+                );
+
+                let reverse = |x: &i32, y: &i32| y.cmp(x);
+                let mut rev_a = RBTreeSet::with_comparator(&reverse);
+                rev_a.extend(a.iter().cloned());
+                let mut rev_b = RBTreeSet::with_comparator(&reverse);
+                rev_b.extend(b.iter().cloned());
+                let rev = |v: Vec<i32>| v.into_iter().rev().collect::<Vec<_>>();
+
+                assert_eq!(
+                    values(rev_a.union(&rev_b)),
+                    rev(values(set_a.union(&set_b)))
+                );
+                assert_eq!(
+                    values(rev_a.intersection(&rev_b)),
+                    rev(values(set_a.intersection(&set_b)))
+                );
+                assert_eq!(
+                    values(rev_a.difference(&rev_b)),
+                    rev(values(set_a.difference(&set_b)))
+                );
+                assert_eq!(
+                    (&rev_a - &rev_b).values().collect::<Vec<_>>(),
+                    rev(values(set_a.difference(&set_b)))
+                );
+                assert_eq!(rev_a.is_subset(&rev_b), oracle_a.is_subset(&oracle_b));
+            }
+        }
+    }
+
+    #[test]
+    fn arena_set_algebra() {
+        let samples: Vec<Vec<i32>> = vec![vec![], vec![1], vec![2, 4, 6, 8], (0..10).collect()];
+        for a in &samples {
+            for b in &samples {
+                let set_a: ArenaRBTreeSet<_> = a.iter().cloned().collect();
+                let set_b: ArenaRBTreeSet<_> = b.iter().cloned().collect();
+                let oracle_a: BTreeSet<_> = a.iter().cloned().collect();
+                let oracle_b: BTreeSet<_> = b.iter().cloned().collect();
+
+                assert!(set_a.union(&set_b).eq(oracle_a.union(&oracle_b)));
+                assert!(set_a
+                    .intersection(&set_b)
+                    .eq(oracle_a.intersection(&oracle_b)));
+                assert!(set_a.difference(&set_b).eq(oracle_a.difference(&oracle_b)));
+                assert!(set_a
+                    .symmetric_difference(&set_b)
+                    .eq(oracle_a.symmetric_difference(&oracle_b)));
+                assert_eq!(set_a.is_subset(&set_b), oracle_a.is_subset(&oracle_b));
+                assert_eq!(set_a.is_superset(&set_b), oracle_a.is_superset(&oracle_b));
+                assert_eq!(set_a.is_disjoint(&set_b), oracle_a.is_disjoint(&oracle_b));
+                assert!((&set_a | &set_b).iter().eq((&oracle_a | &oracle_b).iter()));
+                assert!((&set_a & &set_b).iter().eq((&oracle_a & &oracle_b).iter()));
+                assert!((&set_a - &set_b).iter().eq((&oracle_a - &oracle_b).iter()));
+                assert!((&set_a ^ &set_b).iter().eq((&oracle_a ^ &oracle_b).iter()));
+            }
+        }
+    }
+
+    #[test]
+    fn equivalent_values() {
+        // values are matched by their tens
+        let tens = |x: &i32, y: &i32| (x / 10).cmp(&(y / 10));
+        let mut a = RBTreeSet::with_comparator(&tens);
+        a.extend(vec![1, 12, 25]);
+        let mut b = RBTreeSet::with_comparator(&tens);
+        b.extend(vec![15, 27, 33]);
+
+        assert_eq!(values(a.union(&b)), [1, 12, 25, 33]);
+        assert_eq!(values(a.intersection(&b)), [12, 25]);
+        assert_eq!(values(a.difference(&b)), [1]);
+        assert_eq!(values(a.symmetric_difference(&b)), [1, 33]);
+    }
+
+    #[test]
+    fn early_return() {
+        let small: RBTreeSet<_> = vec![1, 2].into_iter().collect();
+        let large: RBTreeSet<_> = (0..100).collect();
+
+        let mut intersection = small.intersection(&large);
+        assert_eq!(values(intersection.by_ref()), [1, 2]);
+        assert_eq!(intersection.0.b.next().map(|n| n.clone_data()), Some(4));
+
+        let mut difference = small.difference(&large);
+        assert_eq!(difference.next(), None);
+        assert_eq!(difference.0.b.next().map(|n| n.clone_data()), Some(4));
+    }
+}
@@ -0,0 +1,809 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::FromIterator;
+use std::sync::Arc;
+
+use crate::node::Colour::{self, Black, Red};
+use crate::{Compare, Natural};
+
+type Link<T> = Option<Arc<PNode<T>>>;
+
+struct PNode<T> {
+    colour: Colour,
+    left: Link<T>,
+    data: T,
+    right: Link<T>,
+}
+
+fn make<T>(colour: Colour, left: Link<T>, data: T, right: Link<T>) -> Link<T> {
+    Some(Arc::new(PNode {
+        colour,
+        left,
+        data,
+        right,
+    }))
+}
+
+fn recolour<T: Clone>(node: &PNode<T>, colour: Colour) -> Link<T> {
+    make(
+        colour,
+        node.left.clone(),
+        node.data.clone(),
+        node.right.clone(),
+    )
+}
+
+fn red_node<T>(link: &Link<T>) -> Option<&PNode<T>> {
+    link.as_deref().filter(|n| n.colour == Red)
+}
+
+fn black_node<T>(link: &Link<T>) -> Option<&PNode<T>> {
+    link.as_deref().filter(|n| n.colour == Black)
+}
+
+/// Turns a black node into a red one, lowering the black height of the subtree.
+fn redden<T: Clone>(link: &Link<T>) -> Link<T> {
+    let node = black_node(link).expect("black node expected");
+    recolour(node, Red)
+}
+
+/// Builds a black node, resolving a red-red conflict among its children or
+/// grandchildren.
+fn balance<T: Clone>(left: Link<T>, data: T, right: Link<T>) -> Link<T> {
+    if let (Some(l), Some(r)) = (red_node(&left), red_node(&right)) {
+        return make(Red, recolour(l, Black), data, recolour(r, Black));
+    }
+    if let Some(l) = red_node(&left) {
+        if let Some(ll) = red_node(&l.left) {
+            return make(
+                Red,
+                recolour(ll, Black),
+                l.data.clone(),
+                make(Black, l.right.clone(), data, right),
+            );
+        }
+        if let Some(lr) = red_node(&l.right) {
+            return make(
+                Red,
+                make(Black, l.left.clone(), l.data.clone(), lr.left.clone()),
+                lr.data.clone(),
+                make(Black, lr.right.clone(), data, right),
+            );
+        }
+    }
+    if let Some(r) = red_node(&right) {
+        if let Some(rr) = red_node(&r.right) {
+            return make(
+                Red,
+                make(Black, left, data, r.left.clone()),
+                r.data.clone(),
+                recolour(rr, Black),
+            );
+        }
+        if let Some(rl) = red_node(&r.left) {
+            return make(
+                Red,
+                make(Black, left, data, rl.left.clone()),
+                rl.data.clone(),
+                make(Black, rl.right.clone(), r.data.clone(), r.right.clone()),
+            );
+        }
+    }
+    make(Black, left, data, right)
+}
+
+/// Rebalances a node whose left subtree lost one black node.
+fn balance_left<T: Clone>(left: Link<T>, data: T, right: Link<T>) -> Link<T> {
+    if let Some(l) = red_node(&left) {
+        return make(Red, recolour(l, Black), data, right);
+    }
+    if let Some(r) = black_node(&right) {
+        return balance(left, data, recolour(r, Red));
+    }
+    let r = red_node(&right).expect("missing sibling");
+    let rl = black_node(&r.left).expect("black node expected");
+    make(
+        Red,
+        make(Black, left, data, rl.left.clone()),
+        rl.data.clone(),
+        balance(rl.right.clone(), r.data.clone(), redden(&r.right)),
+    )
+}
+
+/// Rebalances a node whose right subtree lost one black node.
+fn balance_right<T: Clone>(left: Link<T>, data: T, right: Link<T>) -> Link<T> {
+    if let Some(r) = red_node(&right) {
+        return make(Red, left, data, recolour(r, Black));
+    }
+    if let Some(l) = black_node(&left) {
+        return balance(recolour(l, Red), data, right);
+    }
+    let l = red_node(&left).expect("missing sibling");
+    let lr = black_node(&l.right).expect("black node expected");
+    make(
+        Red,
+        balance(redden(&l.left), l.data.clone(), lr.left.clone()),
+        lr.data.clone(),
+        make(Black, lr.right.clone(), data, right),
+    )
+}
+
+/// Builds a node with the colour of `node`, balancing it if black.
+fn rebuild<T: Clone>(node: &PNode<T>, left: Link<T>, right: Link<T>) -> Link<T> {
+    match node.colour {
+        Black => balance(left, node.data.clone(), right),
+        Red => make(Red, left, node.data.clone(), right),
+    }
+}
+
+/// Returns on which side of the value of the node the given value is, `Equal` when
+/// it matches the value of the node.
+fn direction<T, Q, C>(comparator: &C, data: &Q, node: &T) -> Ordering
+where
+    T: Borrow<Q>,
+    Q: ?Sized,
+    C: Compare<Q>,
+{
+    if comparator.equivalent(data, node.borrow()) {
+        Ordering::Equal
+    } else if comparator.compare(data, node.borrow()) == Ordering::Less {
+        Ordering::Less
+    } else {
+        Ordering::Greater
+    }
+}
+
+/// Returns the subtree with the value inserted, or `None` if it was already present.
+fn insert_in<T: Clone, C: Compare<T>>(link: &Link<T>, data: T, comparator: &C) -> Option<Link<T>> {
+    let node = match link.as_deref() {
+        Some(n) => n,
+        None => return Some(make(Red, None, data, None)),
+    };
+    match direction(comparator, &data, &node.data) {
+        Ordering::Less => {
+            let left = insert_in(&node.left, data, comparator)?;
+            Some(rebuild(node, left, node.right.clone()))
+        }
+        Ordering::Equal => None,
+        Ordering::Greater => {
+            let right = insert_in(&node.right, data, comparator)?;
+            Some(rebuild(node, node.left.clone(), right))
+        }
+    }
+}
+
+/// Returns the subtree with the value removed, the value must be present.
+fn remove_in<T, Q, C>(link: &Link<T>, data: &Q, comparator: &C) -> Link<T>
+where
+    T: Clone + Borrow<Q>,
+    Q: ?Sized,
+    C: Compare<Q>,
+{
+    let node = link.as_deref().expect("removed value must be present");
+    match direction(comparator, data, &node.data) {
+        Ordering::Less => {
+            let left = remove_in(&node.left, data, comparator);
+            if black_node(&node.left).is_some() {
+                balance_left(left, node.data.clone(), node.right.clone())
+            } else {
+                make(Red, left, node.data.clone(), node.right.clone())
+            }
+        }
+        Ordering::Equal => fuse(&node.left, &node.right),
+        Ordering::Greater => {
+            let right = remove_in(&node.right, data, comparator);
+            if black_node(&node.right).is_some() {
+                balance_right(node.left.clone(), node.data.clone(), right)
+            } else {
+                make(Red, node.left.clone(), node.data.clone(), right)
+            }
+        }
+    }
+}
+
+/// Joins two subtrees of the same black height, all the values of `left` being
+/// smaller than the values of `right`.
+fn fuse<T: Clone>(left: &Link<T>, right: &Link<T>) -> Link<T> {
+    let (l, r) = match (left.as_deref(), right.as_deref()) {
+        (None, _) => return right.clone(),
+        (_, None) => return left.clone(),
+        (Some(l), Some(r)) => (l, r),
+    };
+    match (l.colour, r.colour) {
+        (Red, Red) => {
+            let middle = fuse(&l.right, &r.left);
+            match red_node(&middle) {
+                Some(m) => make(
+                    Red,
+                    make(Red, l.left.clone(), l.data.clone(), m.left.clone()),
+                    m.data.clone(),
+                    make(Red, m.right.clone(), r.data.clone(), r.right.clone()),
+                ),
+                None => make(
+                    Red,
+                    l.left.clone(),
+                    l.data.clone(),
+                    make(Red, middle, r.data.clone(), r.right.clone()),
+                ),
+            }
+        }
+        (Black, Black) => {
+            let middle = fuse(&l.right, &r.left);
+            match red_node(&middle) {
+                Some(m) => make(
+                    Red,
+                    make(Black, l.left.clone(), l.data.clone(), m.left.clone()),
+                    m.data.clone(),
+                    make(Black, m.right.clone(), r.data.clone(), r.right.clone()),
+                ),
+                None => balance_left(
+                    l.left.clone(),
+                    l.data.clone(),
+                    make(Black, middle, r.data.clone(), r.right.clone()),
+                ),
+            }
+        }
+        (_, Red) => make(Red, fuse(left, &r.left), r.data.clone(), r.right.clone()),
+        (Red, _) => make(Red, l.left.clone(), l.data.clone(), fuse(&l.right, right)),
+    }
+}
+
+fn blacken<T: Clone>(link: Link<T>) -> Link<T> {
+    match red_node(&link) {
+        Some(n) => recolour(n, Black),
+        None => link,
+    }
+}
+
+/// A persistent set based on a RB-Tree.
+///
+/// Updating a persistent set returns a new version of the set and leaves the original
+/// untouched. Only the nodes on the path from the root to the updated value are
+/// copied, the rest of the tree is shared between both versions. This makes keeping
+/// the history of a set cheap: cloning is `O(1)` and each update allocates `O(log n)`
+/// nodes.
+///
+/// The nodes are shared with `Arc`, versions can be sent to other threads. See
+/// [SnapshotRBTreeSet] to update a set shared between threads.
+///
+/// The values are ordered by the comparator `C` like the values of an [RBTreeSet], the
+/// versions of a set sharing their comparator.
+///
+/// [SnapshotRBTreeSet]: struct.SnapshotRBTreeSet.html
+/// [RBTreeSet]: struct.RBTreeSet.html
+///
+/// # Examples
+///
+/// ```
+/// use rbtset::PersistentRBTreeSet;
+///
+/// let v1: PersistentRBTreeSet<_> = (1..4).collect();
+/// let v2 = v1.insert(4);
+/// let v3 = v2.remove(&1);
+///
+/// assert_eq!(v1.iter().collect::<Vec<_>>(), vec![&1, &2, &3]);
+/// assert_eq!(v2.iter().collect::<Vec<_>>(), vec![&1, &2, &3, &4]);
+/// assert_eq!(v3.iter().collect::<Vec<_>>(), vec![&2, &3, &4]);
+/// ```
+pub struct PersistentRBTreeSet<T, C = Natural> {
+    root: Link<T>,
+    length: usize,
+    comparator: Arc<C>,
+}
+
+impl<T, C> PersistentRBTreeSet<T, C> {
+    /// Returns the number of values in the set.
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    /// Returns true if the set contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Returns the smallest value of the set, if any.
+    pub fn first(&self) -> Option<&T> {
+        let mut node = self.root.as_deref()?;
+        while let Some(left) = node.left.as_deref() {
+            node = left;
+        }
+        Some(&node.data)
+    }
+
+    /// Returns the greatest value of the set, if any.
+    pub fn last(&self) -> Option<&T> {
+        let mut node = self.root.as_deref()?;
+        while let Some(right) = node.right.as_deref() {
+            node = right;
+        }
+        Some(&node.data)
+    }
+
+    /// Returns true if both sets are the same version or share their whole structure.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::PersistentRBTreeSet;
+    ///
+    /// let v1: PersistentRBTreeSet<_> = (1..4).collect();
+    /// assert!(v1.ptr_eq(&v1.clone()));
+    /// assert!(v1.ptr_eq(&v1.insert(2)));
+    /// assert!(!v1.ptr_eq(&v1.insert(4)));
+    /// ```
+    pub fn ptr_eq(&self, other: &PersistentRBTreeSet<T, C>) -> bool {
+        match (&self.root, &other.root) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns an empty version of the set, ordered by the same comparator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::PersistentRBTreeSet;
+    ///
+    /// let set: PersistentRBTreeSet<_> = (1..4).collect();
+    /// assert!(set.clear().is_empty());
+    /// assert_eq!(set.len(), 3);
+    /// ```
+    pub fn clear(&self) -> PersistentRBTreeSet<T, C> {
+        PersistentRBTreeSet {
+            root: None,
+            length: 0,
+            comparator: Arc::clone(&self.comparator),
+        }
+    }
+
+    /// Gets an iterator that visits the values in ascending order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::PersistentRBTreeSet;
+    ///
+    /// let set: PersistentRBTreeSet<_> = vec![3, 1, 2].into_iter().collect();
+    /// assert_eq!(set.iter().collect::<Vec<_>>(), vec![&1, &2, &3]);
+    /// assert_eq!(set.iter().rev().collect::<Vec<_>>(), vec![&3, &2, &1]);
+    /// ```
+    pub fn iter(&self) -> PersistentIter<'_, T> {
+        let mut iter = PersistentIter {
+            front: Vec::new(),
+            back: Vec::new(),
+            remaining: self.length,
+        };
+        iter.push_front(&self.root);
+        iter.push_back(&self.root);
+        iter
+    }
+}
+
+impl<T: Ord> PersistentRBTreeSet<T> {
+    /// Makes a new, empty `PersistentRBTreeSet`.
+    pub fn new() -> PersistentRBTreeSet<T> {
+        Self::default()
+    }
+}
+
+impl<T, C: Compare<T>> PersistentRBTreeSet<T, C> {
+    /// Makes a new, empty `PersistentRBTreeSet` ordering its values with the given
+    /// comparator.
+    ///
+    /// See [Compare] for more informations.
+    ///
+    /// [Compare]: trait.Compare.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::PersistentRBTreeSet;
+    ///
+    /// let set = PersistentRBTreeSet::with_comparator(|a: &i32, b: &i32| b.cmp(a));
+    /// let set = set.insert(1).insert(3).insert(2);
+    /// assert_eq!(set.iter().collect::<Vec<_>>(), vec![&3, &2, &1]);
+    /// ```
+    pub fn with_comparator(comparator: C) -> PersistentRBTreeSet<T, C> {
+        PersistentRBTreeSet {
+            root: None,
+            length: 0,
+            comparator: Arc::new(comparator),
+        }
+    }
+
+    /// Returns a reference to the value of the set matching the given value, if any.
+    ///
+    /// The value may be any borrowed form of the set's value type, but the ordering
+    /// on the borrowed form must match the ordering on the value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::PersistentRBTreeSet;
+    ///
+    /// let set: PersistentRBTreeSet<_> = (1..4).collect();
+    /// assert_eq!(set.get(&2), Some(&2));
+    /// assert_eq!(set.get(&4), None);
+    /// ```
+    pub fn get<Q>(&self, data: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        let mut tmp = self.root.as_deref();
+        while let Some(n) = tmp {
+            match direction(&*self.comparator, data, &n.data) {
+                Ordering::Less => tmp = n.left.as_deref(),
+                Ordering::Equal => return Some(&n.data),
+                Ordering::Greater => tmp = n.right.as_deref(),
+            }
+        }
+        None
+    }
+
+    /// Returns true if the set contains a value matching the given value.
+    ///
+    /// The value may be any borrowed form of the set's value type, but the ordering
+    /// on the borrowed form must match the ordering on the value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::PersistentRBTreeSet;
+    ///
+    /// let set: PersistentRBTreeSet<String> = ["a", "b"].iter().map(|s| s.to_string()).collect();
+    /// assert!(set.contains("a"));
+    /// assert!(!set.contains("c"));
+    /// ```
+    pub fn contains<Q>(&self, data: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.get(data).is_some()
+    }
+}
+
+impl<T: Clone, C: Compare<T>> PersistentRBTreeSet<T, C> {
+    /// Returns a new version of the set with the value added.
+    ///
+    /// If the value is already present the returned version shares the whole structure
+    /// of this one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::PersistentRBTreeSet;
+    ///
+    /// let empty = PersistentRBTreeSet::new();
+    /// let set = empty.insert(1);
+    /// assert_eq!(empty.len(), 0);
+    /// assert_eq!(set.len(), 1);
+    /// ```
+    pub fn insert(&self, data: T) -> PersistentRBTreeSet<T, C> {
+        match insert_in(&self.root, data, &*self.comparator) {
+            Some(root) => PersistentRBTreeSet {
+                root: blacken(root),
+                length: self.length + 1,
+                comparator: Arc::clone(&self.comparator),
+            },
+            None => self.clone(),
+        }
+    }
+
+    /// Returns a new version of the set with the value removed.
+    ///
+    /// If the value is not present the returned version shares the whole structure of
+    /// this one. The value may be any borrowed form of the set's value type, but the
+    /// ordering on the borrowed form must match the ordering on the value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::PersistentRBTreeSet;
+    ///
+    /// let set: PersistentRBTreeSet<_> = (1..4).collect();
+    /// let removed = set.remove(&2);
+    /// assert!(set.contains(&2));
+    /// assert!(!removed.contains(&2));
+    /// ```
+    pub fn remove<Q>(&self, data: &Q) -> PersistentRBTreeSet<T, C>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        if !self.contains(data) {
+            return self.clone();
+        }
+        PersistentRBTreeSet {
+            root: blacken(remove_in(&self.root, data, &*self.comparator)),
+            length: self.length - 1,
+            comparator: Arc::clone(&self.comparator),
+        }
+    }
+}
+
+impl<T, C> Clone for PersistentRBTreeSet<T, C> {
+    /// Returns the same version of the set, in `O(1)`.
+    fn clone(&self) -> Self {
+        PersistentRBTreeSet {
+            root: self.root.clone(),
+            length: self.length,
+            comparator: Arc::clone(&self.comparator),
+        }
+    }
+}
+
+impl<T, C: Default> Default for PersistentRBTreeSet<T, C> {
+    fn default() -> Self {
+        PersistentRBTreeSet {
+            root: None,
+            length: 0,
+            comparator: Arc::new(C::default()),
+        }
+    }
+}
+
+impl<T, C> fmt::Debug for PersistentRBTreeSet<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PersistentRBTreeSet {{ length: {} }}", self.length)
+    }
+}
+
+impl<T: Clone, C: Compare<T> + Default> FromIterator<T> for PersistentRBTreeSet<T, C> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut s = Self::default();
+        for i in iter {
+            s = s.insert(i);
+        }
+        s
+    }
+}
+
+/// Created with the method [iter].
+///
+/// [iter]: struct.PersistentRBTreeSet.html#method.iter
+pub struct PersistentIter<'a, T> {
+    front: Vec<&'a PNode<T>>,
+    back: Vec<&'a PNode<T>>,
+    remaining: usize,
+}
+
+impl<'a, T> PersistentIter<'a, T> {
+    fn push_front(&mut self, link: &'a Link<T>) {
+        let mut tmp = link.as_deref();
+        while let Some(n) = tmp {
+            self.front.push(n);
+            tmp = n.left.as_deref();
+        }
+    }
+
+    fn push_back(&mut self, link: &'a Link<T>) {
+        let mut tmp = link.as_deref();
+        while let Some(n) = tmp {
+            self.back.push(n);
+            tmp = n.right.as_deref();
+        }
+    }
+}
+
+impl<'a, T> Iterator for PersistentIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.front.pop()?;
+        self.push_front(&node.right);
+        self.remaining -= 1;
+        Some(&node.data)
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for PersistentIter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.back.pop()?;
+        self.push_back(&node.left);
+        self.remaining -= 1;
+        Some(&node.data)
+    }
+}
+
+impl<T: Clone, C> IntoIterator for PersistentRBTreeSet<T, C> {
+    type Item = T;
+    type IntoIter = PersistentIntoIter<T>;
+
+    /// Gets an iterator that visits clones of the values in ascending order, keeping
+    /// this version of the set alive.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::PersistentRBTreeSet;
+    ///
+    /// let set: PersistentRBTreeSet<_> = vec![3, 1, 2].into_iter().collect();
+    /// let values = set.clone().into_iter();
+    /// let set = set.remove(&2);
+    /// assert_eq!(values.collect::<Vec<_>>(), vec![1, 2, 3]);
+    /// assert_eq!(set.into_iter().rev().collect::<Vec<_>>(), vec![3, 1]);
+    /// ```
+    fn into_iter(self) -> PersistentIntoIter<T> {
+        let mut iter = PersistentIntoIter {
+            front: Vec::new(),
+            back: Vec::new(),
+            remaining: self.length,
+        };
+        iter.push_front(&self.root);
+        iter.push_back(&self.root);
+        iter
+    }
+}
+
+/// Created with the method [into_iter].
+///
+/// [into_iter]: struct.PersistentRBTreeSet.html#method.into_iter
+pub struct PersistentIntoIter<T> {
+    front: Vec<Arc<PNode<T>>>,
+    back: Vec<Arc<PNode<T>>>,
+    remaining: usize,
+}
+
+impl<T> PersistentIntoIter<T> {
+    fn push_front(&mut self, link: &Link<T>) {
+        let mut tmp = link.clone();
+        while let Some(n) = tmp {
+            tmp = n.left.clone();
+            self.front.push(n);
+        }
+    }
+
+    fn push_back(&mut self, link: &Link<T>) {
+        let mut tmp = link.clone();
+        while let Some(n) = tmp {
+            tmp = n.right.clone();
+            self.back.push(n);
+        }
+    }
+}
+
+impl<T: Clone> Iterator for PersistentIntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.front.pop()?;
+        self.push_front(&node.right);
+        self.remaining -= 1;
+        Some(node.data.clone())
+    }
+}
+
+impl<T: Clone> DoubleEndedIterator for PersistentIntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.back.pop()?;
+        self.push_back(&node.left);
+        self.remaining -= 1;
+        Some(node.data.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+
+    /// Returns the black height of the subtree, checking the RB-Tree invariants.
+    fn validate_subtree<T: Ord>(link: &Link<T>) -> usize {
+        let node = match link.as_deref() {
+            Some(n) => n,
+            None => return 1,
+        };
+        if node.colour == Red {
+            assert!(red_node(&node.left).is_none());
+            assert!(red_node(&node.right).is_none());
+        }
+        if let Some(left) = node.left.as_deref() {
+            assert!(left.data < node.data);
+        }
+        if let Some(right) = node.right.as_deref() {
+            assert!(right.data > node.data);
+        }
+        let left = validate_subtree(&node.left);
+        assert_eq!(left, validate_subtree(&node.right));
+        left + if node.colour == Black { 1 } else { 0 }
+    }
+
+    fn validate_tree<T: Ord, C>(set: &PersistentRBTreeSet<T, C>) {
+        assert!(red_node(&set.root).is_none());
+        validate_subtree(&set.root);
+        assert_eq!(set.iter().count(), set.len());
+    }
+
+    #[test]
+    fn versions() {
+        let mut versions = vec![PersistentRBTreeSet::new()];
+        let mut oracles = vec![BTreeSet::new()];
+        for i in 0..300 {
+            let value = (i * 37) % 101;
+            let (mut set, mut oracle) = (versions[i].clone(), oracles[i].clone());
+            if i % 3 == 2 {
+                set = set.remove(&value);
+                oracle.remove(&value);
+            } else {
+                set = set.insert(value);
+                oracle.insert(value);
+            }
+            validate_tree(&set);
+            versions.push(set);
+            oracles.push(oracle);
+        }
+        for (set, oracle) in versions.iter().zip(oracles.iter()) {
+            assert!(set.iter().eq(oracle.iter()));
+            assert!(set.iter().rev().eq(oracle.iter().rev()));
+        }
+    }
+
+    #[test]
+    fn structural_sharing() {
+        let set: PersistentRBTreeSet<_> = (0..64).collect();
+        let updated = set.insert(64);
+        let (old, new) = (set.root.as_ref().unwrap(), updated.root.as_ref().unwrap());
+        assert!(!Arc::ptr_eq(old, new));
+        // the left half of the tree is untouched by the insertion
+        assert!(Arc::ptr_eq(
+            old.left.as_ref().unwrap(),
+            new.left.as_ref().unwrap()
+        ));
+        let removed = updated.remove(&64);
+        assert!(set.iter().eq(removed.iter()));
+        assert!(set.ptr_eq(&set.remove(&100)));
+    }
+
+    #[test]
+    fn comparator() {
+        let reverse = |a: &i32, b: &i32| b.cmp(a);
+        let mut set = PersistentRBTreeSet::with_comparator(reverse);
+        for i in 0..100 {
+            set = set.insert((i * 37) % 101);
+        }
+        let older = set.clone();
+        set = set.remove(&36).clear().insert(5).insert(7);
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![&7, &5]);
+        assert!(older
+            .iter()
+            .rev()
+            .cloned()
+            .eq((0..101).filter(|v| *v != 64)));
+        assert_eq!(older.get(&36), Some(&36));
+
+        // values are matched case insensitively
+        let lowercase = |a: &str, b: &str| a.to_lowercase().cmp(&b.to_lowercase());
+        let words =
+            PersistentRBTreeSet::with_comparator(move |a: &String, b: &String| lowercase(a, b));
+        let words = words.insert("b".to_owned()).insert("A".to_owned());
+        assert_eq!(words.insert("a".to_owned()).len(), 2);
+        assert!(words.contains(&"B".to_owned()));
+        assert!(words.remove(&"a".to_owned()).iter().eq(vec!["b"]));
+
+        let words: PersistentRBTreeSet<String> = vec!["a".to_owned()].into_iter().collect();
+        assert_eq!(words.get("a").map(String::as_str), Some("a"));
+        assert!(words.remove("a").is_empty());
+    }
+}
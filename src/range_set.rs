@@ -0,0 +1,230 @@
+use std::fmt;
+use std::iter::FromIterator;
+
+use crate::node::Node;
+use crate::tree::{Iter, IterValues, RBTreeSet};
+use crate::{Consecutive, Containment, Splittable};
+
+/// A set of consecutive values kept permanently packed.
+///
+/// Every insertion merges the new value with the neighbouring values it is consecutive
+/// to or overlaps, see [insert_coalescing]. No two neighbouring values of a `RangeSet`
+/// are ever consecutive or overlapping, so there is no need to call [repack].
+///
+/// Values are looked up with the [Containment] comparator, matching a value with the
+/// value of the set covering it.
+///
+/// [insert_coalescing]: struct.RBTreeSet.html#method.insert_coalescing
+/// [repack]: struct.RBTreeSet.html#method.repack
+/// [Containment]: struct.Containment.html
+///
+/// # Examples
+///
+/// ```
+/// use rbtset::{RangeSet, Span};
+///
+/// let mut ranges = RangeSet::new();
+/// ranges.insert(Span(1..3));
+/// ranges.insert(Span(6..8));
+/// ranges.insert(Span(3..6));
+/// ranges.insert(Span(7..10));
+///
+/// assert_eq!(ranges.len(), 1);
+/// assert_eq!(ranges.first().unwrap().data().0, 1..10);
+/// assert!(ranges.contains(&Span(4..6)));
+/// ```
+pub struct RangeSet<T> {
+    set: RBTreeSet<T, (), Containment>,
+}
+
+impl<T: Ord + Clone + Consecutive + Splittable> RangeSet<T> {
+    /// Makes a new `RangeSet`.
+    pub fn new() -> RangeSet<T> {
+        RangeSet {
+            set: RBTreeSet::with_comparator(Containment),
+        }
+    }
+
+    /// Adds a value to the set, merging it with its consecutive and overlapping
+    /// neighbours.
+    ///
+    /// Returns the node holding the merged value, or `None` if the value was
+    /// already covered or is empty.
+    pub fn insert(&mut self, data: T) -> Option<Node<T>> {
+        self.set.insert_coalescing(data)
+    }
+
+    /// Returns the node in the set, if any, holding the value covering the given
+    /// value.
+    pub fn get_node(&self, data: &T) -> Option<Node<T>> {
+        self.set.get_node(data)
+    }
+
+    /// Returns true if the set contains a value covering the given value.
+    pub fn contains(&self, data: &T) -> bool {
+        self.set.contains(data)
+    }
+
+    /// Removes the value covering the given value from the set. Returns whether
+    /// such a value was present.
+    ///
+    /// The remaining values are not split, removing a whole merged value.
+    pub fn remove(&mut self, data: &T) -> bool {
+        self.set.remove(data)
+    }
+
+    /// Removes the parts of the values covered by the given range, splitting the
+    /// values straddling its bounds. Returns whether a value was overlapping the range.
+    ///
+    /// See [RBTreeSet::remove_range].
+    ///
+    /// [RBTreeSet::remove_range]: struct.RBTreeSet.html#method.remove_range
+    pub fn remove_range(&mut self, range: &T) -> bool {
+        self.set.remove_range(range)
+    }
+
+    /// Clears the set, removing all values.
+    pub fn clear(&mut self) {
+        self.set.clear();
+    }
+
+    /// Returns the node holding the smallest value, if not empty.
+    pub fn first(&self) -> Option<Node<T>> {
+        self.set.first()
+    }
+
+    /// Returns the node holding the greatest value, if not empty.
+    pub fn last(&self) -> Option<Node<T>> {
+        self.set.last()
+    }
+
+    /// Returns the number of merged values in the set.
+    pub fn len(&self) -> usize {
+        self.set.len()
+    }
+
+    /// Returns true if the set contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty()
+    }
+
+    /// Gets an iterator that visits the nodes in the set in ascending order.
+    pub fn iter(&self) -> Iter<T> {
+        self.set.iter()
+    }
+
+    /// Gets an iterator that visits the values in the set in ascending order.
+    pub fn values(&self) -> IterValues<T> {
+        self.set.values()
+    }
+}
+
+impl<T: Ord + Clone + Consecutive + Splittable> Default for RangeSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord + Splittable> fmt::Debug for RangeSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RangeSet {{ length: {} }}", self.set.len())
+    }
+}
+
+impl<T: Clone> Clone for RangeSet<T> {
+    fn clone(&self) -> Self {
+        RangeSet {
+            set: self.set.clone(),
+        }
+    }
+}
+
+impl<T: Ord + Clone + Consecutive + Splittable> From<RBTreeSet<T>> for RangeSet<T> {
+    /// Merges the consecutive and overlapping values of the given set into a
+    /// `RangeSet`.
+    fn from(mut set: RBTreeSet<T>) -> Self {
+        set.drain().values().collect()
+    }
+}
+
+impl<T: Ord + Clone + Consecutive + Splittable> FromIterator<T> for RangeSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut s = Self::new();
+        for i in iter {
+            s.insert(i);
+        }
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Span;
+
+    #[test]
+    fn packed() {
+        let ranges: Vec<_> = (0..20).rev().map(|i| Span(i * 2..i * 2 + 1)).collect();
+        let mut set: RangeSet<_> = ranges.into_iter().collect();
+        assert_eq!(set.len(), 20);
+        for i in 0..20 {
+            set.insert(Span(i * 2 + 1..i * 2 + 2));
+        }
+        assert_eq!(set.len(), 1);
+        assert_eq!(set.first().unwrap().data().0, 0..40);
+        assert!(set.contains(&Span(17..18)));
+
+        assert!(set.remove_range(&Span(10..20)));
+        assert!(!set.contains(&Span(17..18)));
+        set.insert(Span(10..20));
+        assert_eq!(set.len(), 1);
+
+        let tree: RBTreeSet<_> = (0..10).map(|i| Span(i..i + 1)).collect();
+        let set = RangeSet::from(tree);
+        assert_eq!(set.values().map(|s| s.0).collect::<Vec<_>>(), vec![0..10]);
+
+        let tree: RBTreeSet<_> = vec![Span(2..6), Span(0..4), Span(8..9)]
+            .into_iter()
+            .collect();
+        let set = RangeSet::from(tree);
+        assert_eq!(
+            set.values().map(|s| s.0).collect::<Vec<_>>(),
+            vec![0..6, 8..9]
+        );
+    }
+
+    #[test]
+    fn overlapping() {
+        let mut set = RangeSet::new();
+        set.insert(Span(1..5));
+        assert_eq!(set.insert(Span(0..3)).unwrap().data().0, 0..5);
+        assert_eq!(set.values().map(|s| s.0).collect::<Vec<_>>(), vec![0..5]);
+
+        let mut set = RangeSet::new();
+        set.insert(Span(1..5));
+        assert_eq!(set.insert(Span(3..8)).unwrap().data().0, 1..8);
+        assert_eq!(set.values().map(|s| s.0).collect::<Vec<_>>(), vec![1..8]);
+
+        assert!(set.insert(Span(2..4)).is_none());
+        set.insert(Span(10..12));
+        set.insert(Span(14..16));
+        assert_eq!(set.insert(Span(5..15)).unwrap().data().0, 1..16);
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn empty() {
+        let mut set = RangeSet::new();
+        assert!(set.insert(Span(3..3)).is_none());
+        assert!(set.is_empty());
+
+        set.insert(Span(1..3));
+        set.insert(Span(5..8));
+        assert!(set.insert(Span(3..3)).is_none());
+        assert!(set.insert(Span(10..10)).is_none());
+        assert_eq!(
+            set.values().map(|s| s.0).collect::<Vec<_>>(),
+            vec![1..3, 5..8]
+        );
+    }
+}
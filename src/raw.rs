@@ -0,0 +1,1050 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::ops::{Bound, Deref, RangeBounds};
+
+use crate::balance::{self, Links};
+use crate::node::Colour;
+use crate::{Augment, Compare, Consecutive, Splittable};
+
+/// The storage of the nodes of a red-black tree.
+///
+/// The algorithms of [RawTree] only reach the nodes through this interface, they are
+/// shared by the sets storing their nodes behind reference counted pointers, like
+/// [RBTreeSet], and in an arena, like [ArenaRBTreeSet].
+///
+/// [RawTree]: struct.RawTree.html
+/// [RBTreeSet]: ../struct.RBTreeSet.html
+/// [ArenaRBTreeSet]: ../struct.ArenaRBTreeSet.html
+pub(crate) trait Storage {
+    type Value;
+    type Summary;
+    /// A reference to a node.
+    type Link: PartialEq;
+    /// What is given back when a node leaves the tree, see `release`.
+    type Released;
+    type Data<'a>: Deref<Target = Self::Value>
+    where
+        Self: 'a;
+    type SummaryRef<'a>: Deref<Target = Self::Summary>
+    where
+        Self: 'a;
+
+    fn duplicate(link: &Self::Link) -> Self::Link;
+
+    /// Stores a new lone red node.
+    fn alloc(&mut self, data: Self::Value, summary: Self::Summary) -> Self::Link;
+    /// Gives up a node which is no longer linked in the tree.
+    fn release(&mut self, node: Self::Link) -> Self::Released;
+
+    fn data<'a>(&'a self, node: &'a Self::Link) -> Self::Data<'a>;
+    fn set_data(&mut self, node: &Self::Link, data: Self::Value);
+    fn summary<'a>(&'a self, node: &'a Self::Link) -> Self::SummaryRef<'a>;
+    fn size(&self, node: &Self::Link) -> usize;
+    /// Caches the informations computed from the subtree rooted at the node.
+    fn set_subtree(&mut self, node: &Self::Link, size: usize, summary: Self::Summary);
+
+    fn parent(&self, node: &Self::Link) -> Option<Self::Link>;
+    fn set_parent(&mut self, node: &Self::Link, parent: Option<Self::Link>);
+    fn left(&self, node: &Self::Link) -> Option<Self::Link>;
+    fn set_left(&mut self, node: &Self::Link, left: Option<Self::Link>);
+    fn right(&self, node: &Self::Link) -> Option<Self::Link>;
+    fn set_right(&mut self, node: &Self::Link, right: Option<Self::Link>);
+
+    fn colour(&self, node: &Self::Link) -> Colour;
+    fn set_colour(&mut self, node: &Self::Link, colour: Colour);
+}
+
+fn size_of<S: Storage>(nodes: &S, node: Option<&S::Link>) -> usize {
+    node.map_or(0, |n| nodes.size(n))
+}
+
+fn minimum<S: Storage>(nodes: &S, node: S::Link) -> S::Link {
+    let mut node = node;
+    while let Some(left) = nodes.left(&node) {
+        node = left;
+    }
+    node
+}
+
+fn maximum<S: Storage>(nodes: &S, node: S::Link) -> S::Link {
+    let mut node = node;
+    while let Some(right) = nodes.right(&node) {
+        node = right;
+    }
+    node
+}
+
+/// Returns the next node in ascending order, if any.
+pub(crate) fn successor<S: Storage>(nodes: &S, node: &S::Link) -> Option<S::Link> {
+    if let Some(right) = nodes.right(node) {
+        return Some(minimum(nodes, right));
+    }
+    let mut tmp = S::duplicate(node);
+    while let Some(parent) = nodes.parent(&tmp) {
+        if nodes.left(&parent).as_ref() == Some(&tmp) {
+            return Some(parent);
+        }
+        tmp = parent;
+    }
+    None
+}
+
+/// Returns the previous node in ascending order, if any.
+pub(crate) fn predecessor<S: Storage>(nodes: &S, node: &S::Link) -> Option<S::Link> {
+    if let Some(left) = nodes.left(node) {
+        return Some(maximum(nodes, left));
+    }
+    let mut tmp = S::duplicate(node);
+    while let Some(parent) = nodes.parent(&tmp) {
+        if nodes.right(&parent).as_ref() == Some(&tmp) {
+            return Some(parent);
+        }
+        tmp = parent;
+    }
+    None
+}
+
+/// Returns the position of the node in its tree, in ascending order.
+///
+/// This operation is `O(log n)`.
+pub(crate) fn position<S: Storage>(nodes: &S, node: &S::Link) -> usize {
+    let mut position = size_of(nodes, nodes.left(node).as_ref());
+    let mut tmp = S::duplicate(node);
+    while let Some(parent) = nodes.parent(&tmp) {
+        if nodes.right(&parent).as_ref() == Some(&tmp) {
+            position += size_of(nodes, nodes.left(&parent).as_ref()) + 1;
+        }
+        tmp = parent;
+    }
+    position
+}
+
+/// Returns the nodes of the subtree in ascending order.
+pub(crate) fn in_order<S: Storage>(nodes: &S, root: Option<S::Link>) -> Vec<S::Link> {
+    let mut links = Vec::new();
+    // the root is kept until the walk ends, the parents may be weak references
+    let mut tmp = root.as_ref().map(|root| minimum(nodes, S::duplicate(root)));
+    while let Some(node) = tmp {
+        tmp = successor(nodes, &node);
+        links.push(node);
+    }
+    links
+}
+
+/// Recomputes the informations cached by the node from its children.
+pub(crate) fn refresh<S: Storage>(nodes: &mut S, node: &S::Link)
+where
+    S::Summary: Augment<S::Value>,
+{
+    let mut size = 1;
+    let mut summary = S::Summary::summarize(&nodes.data(node));
+    if let Some(ref left) = nodes.left(node) {
+        size += nodes.size(left);
+        summary = nodes.summary(left).combine(&summary);
+    }
+    if let Some(ref right) = nodes.right(node) {
+        size += nodes.size(right);
+        summary = summary.combine(&nodes.summary(right));
+    }
+    nodes.set_subtree(node, size, summary);
+}
+
+/// Recomputes the informations cached by the node and all its ancestors.
+pub(crate) fn refresh_upward<S: Storage>(nodes: &mut S, node: Option<S::Link>)
+where
+    S::Summary: Augment<S::Value>,
+{
+    let mut tmp = node;
+    while let Some(n) = tmp {
+        refresh(nodes, &n);
+        tmp = nodes.parent(&n);
+    }
+}
+
+/// A red-black tree whatever the storage of its nodes, holding the algorithms of the
+/// sets.
+///
+/// The first and last nodes are cached along with the length, the methods taking the
+/// root out of the tree or relinking it as a whole leave them to `reset_bounds`.
+pub(crate) struct RawTree<S: Storage, C> {
+    pub(crate) nodes: S,
+    pub(crate) root: Option<S::Link>,
+    pub(crate) first: Option<S::Link>,
+    pub(crate) last: Option<S::Link>,
+    pub(crate) length: usize,
+    pub(crate) comparator: C,
+}
+
+impl<S: Storage, C> RawTree<S, C> {
+    pub(crate) fn new(nodes: S, comparator: C) -> RawTree<S, C> {
+        RawTree {
+            nodes,
+            root: None,
+            first: None,
+            last: None,
+            length: 0,
+            comparator,
+        }
+    }
+
+    pub(crate) fn next(&self, node: &S::Link) -> Option<S::Link> {
+        successor(&self.nodes, node)
+    }
+
+    pub(crate) fn prev(&self, node: &S::Link) -> Option<S::Link> {
+        predecessor(&self.nodes, node)
+    }
+
+    /// Returns the number of nodes from `first` to `last` included.
+    pub(crate) fn count_between(&self, first: &S::Link, last: &S::Link) -> usize {
+        position(&self.nodes, last) - position(&self.nodes, first) + 1
+    }
+
+    /// Recomputes the cached length, first and last nodes from the tree.
+    pub(crate) fn reset_bounds(&mut self) {
+        self.length = size_of(&self.nodes, self.root.as_ref());
+        self.first = self
+            .root
+            .as_ref()
+            .map(|root| minimum(&self.nodes, S::duplicate(root)));
+        self.last = self
+            .root
+            .as_ref()
+            .map(|root| maximum(&self.nodes, S::duplicate(root)));
+    }
+
+    /// Takes the root out of the tree, leaving it empty. The nodes stay linked to each
+    /// other.
+    pub(crate) fn take_root(&mut self) -> Option<S::Link> {
+        self.first = None;
+        self.last = None;
+        self.length = 0;
+        self.root.take()
+    }
+
+    /// Takes the nodes out of the tree in ascending order, leaving it empty.
+    pub(crate) fn take_links(&mut self) -> Vec<S::Link> {
+        let root = self.take_root();
+        in_order(&self.nodes, root)
+    }
+
+    /// Releases all the nodes, leaving the tree empty.
+    pub(crate) fn clear(&mut self) {
+        // walk the tree rather than the storage, an arena may be much larger
+        let mut stack: Vec<S::Link> = self.take_root().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            stack.extend(self.nodes.left(&node));
+            stack.extend(self.nodes.right(&node));
+            self.nodes.release(node);
+        }
+    }
+
+    /// Walks down the tree, `f` telling whether the searched node is before or after
+    /// the given value.
+    pub(crate) fn find<F>(&self, f: F) -> Option<S::Link>
+    where
+        F: Fn(&S::Value) -> Ordering,
+    {
+        let mut tmp = self.root.as_ref().map(S::duplicate);
+        while let Some(node) = tmp {
+            let order = f(&self.nodes.data(&node));
+            match order {
+                Ordering::Less => tmp = self.nodes.left(&node),
+                Ordering::Equal => return Some(node),
+                Ordering::Greater => tmp = self.nodes.right(&node),
+            }
+        }
+        None
+    }
+
+    pub(crate) fn nth(&self, index: usize) -> Option<S::Link> {
+        let mut index = index;
+        let mut tmp = self.root.as_ref().map(S::duplicate);
+        while let Some(node) = tmp {
+            let left_size = size_of(&self.nodes, self.nodes.left(&node).as_ref());
+            match index.cmp(&left_size) {
+                Ordering::Less => tmp = self.nodes.left(&node),
+                Ordering::Equal => return Some(node),
+                Ordering::Greater => {
+                    index -= left_size + 1;
+                    tmp = self.nodes.right(&node);
+                }
+            }
+        }
+        None
+    }
+
+    pub(crate) fn summary(&self) -> Option<S::Summary>
+    where
+        S::Summary: Clone,
+    {
+        self.root
+            .as_ref()
+            .map(|root| (*self.nodes.summary(root)).clone())
+    }
+
+    pub(crate) fn get<Q>(&self, data: &Q) -> Option<S::Link>
+    where
+        S::Value: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.find(|current| {
+            let current = current.borrow();
+            if self.comparator.equivalent(data, current) {
+                Ordering::Equal
+            } else if self.comparator.compare(data, current) == Ordering::Less {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            }
+        })
+    }
+
+    /// Returns the first node after the given bound, if any.
+    pub(crate) fn lower_bound<Q>(&self, bound: Bound<&Q>) -> Option<S::Link>
+    where
+        S::Value: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        let mut found = None;
+        let mut tmp = self.root.as_ref().map(S::duplicate);
+        while let Some(node) = tmp {
+            let matching = after_start(&self.comparator, (*self.nodes.data(&node)).borrow(), bound);
+            if matching {
+                tmp = self.nodes.left(&node);
+                found = Some(node);
+            } else {
+                tmp = self.nodes.right(&node);
+            }
+        }
+        found
+    }
+
+    /// Returns the last node before the given bound, if any.
+    pub(crate) fn upper_bound<Q>(&self, bound: Bound<&Q>) -> Option<S::Link>
+    where
+        S::Value: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        let mut found = None;
+        let mut tmp = self.root.as_ref().map(S::duplicate);
+        while let Some(node) = tmp {
+            let matching = before_end(&self.comparator, (*self.nodes.data(&node)).borrow(), bound);
+            if matching {
+                tmp = self.nodes.right(&node);
+                found = Some(node);
+            } else {
+                tmp = self.nodes.left(&node);
+            }
+        }
+        found
+    }
+
+    pub(crate) fn rank<Q>(&self, data: &Q) -> usize
+    where
+        S::Value: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        let mut rank = 0;
+        let mut tmp = self.root.as_ref().map(S::duplicate);
+        while let Some(node) = tmp {
+            let less = self
+                .comparator
+                .compare((*self.nodes.data(&node)).borrow(), data)
+                == Ordering::Less;
+            if less {
+                rank += size_of(&self.nodes, self.nodes.left(&node).as_ref()) + 1;
+                tmp = self.nodes.right(&node);
+            } else {
+                tmp = self.nodes.left(&node);
+            }
+        }
+        rank
+    }
+}
+
+impl<S: Storage, C> RawTree<S, C>
+where
+    S::Summary: Augment<S::Value>,
+{
+    pub(crate) fn alloc(&mut self, data: S::Value) -> S::Link {
+        let summary = S::Summary::summarize(&data);
+        self.nodes.alloc(data, summary)
+    }
+
+    pub(crate) fn set_data(&mut self, node: &S::Link, data: S::Value) {
+        self.nodes.set_data(node, data);
+        refresh_upward(&mut self.nodes, Some(S::duplicate(node)));
+    }
+
+    /// Turns a node out of the tree into a lone red node, ready to be linked again.
+    pub(crate) fn reset(&mut self, node: &S::Link) {
+        self.nodes.set_colour(node, Colour::Red);
+        self.nodes.set_parent(node, None);
+        self.nodes.set_left(node, None);
+        self.nodes.set_right(node, None);
+        refresh(&mut self.nodes, node);
+    }
+
+    /// Links the lone red node as the given child of `parent`, the root when there is
+    /// no parent, and rebalances the tree.
+    pub(crate) fn link_at(&mut self, node: S::Link, parent: Option<S::Link>, is_left: bool) {
+        let first = parent.is_none() || (is_left && parent == self.first);
+        let last = parent.is_none() || (!is_left && parent == self.last);
+        self.nodes
+            .set_parent(&node, parent.as_ref().map(S::duplicate));
+        match parent {
+            None => self.root = Some(S::duplicate(&node)),
+            Some(ref p) if is_left => self.nodes.set_left(p, Some(S::duplicate(&node))),
+            Some(ref p) => self.nodes.set_right(p, Some(S::duplicate(&node))),
+        }
+        if first {
+            self.first = Some(S::duplicate(&node));
+        }
+        if last {
+            self.last = Some(S::duplicate(&node));
+        }
+        self.length += 1;
+        refresh_upward(&mut self.nodes, parent);
+        balance::insert_fixup(self, node);
+    }
+
+    /// Removes the node from the tree without releasing it.
+    pub(crate) fn unlink(&mut self, node: &S::Link) {
+        if self.first.as_ref() == Some(node) {
+            self.first = self.next(node);
+        }
+        if self.last.as_ref() == Some(node) {
+            self.last = self.prev(node);
+        }
+        balance::unlink(self, node);
+        self.length -= 1;
+    }
+
+    pub(crate) fn remove(&mut self, node: S::Link) -> S::Released {
+        self.unlink(&node);
+        self.nodes.release(node)
+    }
+
+    pub(crate) fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&S::Value) -> bool,
+    {
+        let mut tmp = self.first.as_ref().map(S::duplicate);
+        while let Some(node) = tmp {
+            tmp = self.next(&node);
+            if !f(&self.nodes.data(&node)) {
+                self.remove(node);
+            }
+        }
+    }
+
+    /// Removes the `next` node if any of the `remaining` ones, moving on to the
+    /// following one.
+    pub(crate) fn drain_next(
+        &mut self,
+        next: &mut Option<S::Link>,
+        remaining: &mut usize,
+    ) -> Option<S::Released> {
+        if *remaining == 0 {
+            return None;
+        }
+        let node = next.take()?;
+        *next = self.next(&node);
+        *remaining -= 1;
+        Some(self.remove(node))
+    }
+
+    /// Removes the first node from `next` onwards matching the predicate, moving on to
+    /// the following one.
+    pub(crate) fn extract_next<F>(
+        &mut self,
+        next: &mut Option<S::Link>,
+        pred: &mut F,
+    ) -> Option<S::Released>
+    where
+        F: FnMut(&S::Value) -> bool,
+    {
+        while let Some(node) = next.take() {
+            *next = self.next(&node);
+            if pred(&self.nodes.data(&node)) {
+                return Some(self.remove(node));
+            }
+        }
+        None
+    }
+
+    /// Builds a balanced subtree from values strictly in ascending order.
+    pub(crate) fn build(&mut self, values: Vec<S::Value>) -> Option<S::Link> {
+        let length = values.len();
+        // Nodes below the last complete level are red, every path has the same number
+        // of black nodes.
+        let red_depth = (usize::BITS - 1 - (length + 1).leading_zeros()) as usize;
+        self.build_subtree(&mut values.into_iter(), length, 0, red_depth)
+    }
+
+    fn build_subtree(
+        &mut self,
+        values: &mut impl Iterator<Item = S::Value>,
+        length: usize,
+        depth: usize,
+        red_depth: usize,
+    ) -> Option<S::Link> {
+        if length == 0 {
+            return None;
+        }
+        let left_length = (length - 1) / 2;
+        let left = self.build_subtree(values, left_length, depth + 1, red_depth);
+        let node = self.alloc(values.next().expect("enough values"));
+        let right = self.build_subtree(values, length - 1 - left_length, depth + 1, red_depth);
+
+        if depth != red_depth {
+            self.nodes.set_colour(&node, Colour::Black);
+        }
+        for child in left.iter().chain(right.iter()) {
+            self.nodes.set_parent(child, Some(S::duplicate(&node)));
+        }
+        self.nodes.set_left(&node, left);
+        self.nodes.set_right(&node, right);
+        refresh(&mut self.nodes, &node);
+        Some(node)
+    }
+
+    /// Fills the empty tree with values strictly in ascending order.
+    pub(crate) fn assign(&mut self, values: Vec<S::Value>) {
+        self.root = self.build(values);
+        self.reset_bounds();
+    }
+
+    /// Makes the join of two standalone subtrees with the lone pivot in between the
+    /// root of the tree, see `balance::join`. The cached length and bounds are left to
+    /// `reset_bounds`.
+    ///
+    /// This operation is `O(log n)`.
+    pub(crate) fn join_root(
+        &mut self,
+        left: Option<S::Link>,
+        pivot: S::Link,
+        right: Option<S::Link>,
+    ) {
+        self.root = None;
+        balance::join(self, left, pivot, right);
+    }
+
+    /// Joins two standalone subtrees with the lone pivot in between, the result
+    /// replacing the tree.
+    pub(crate) fn join(&mut self, left: Option<S::Link>, pivot: S::Link, right: Option<S::Link>) {
+        self.join_root(left, pivot, right);
+        self.reset_bounds();
+    }
+}
+
+impl<S: Storage, C: Compare<S::Value>> RawTree<S, C>
+where
+    S::Summary: Augment<S::Value>,
+{
+    /// Collects values in ascending order, consecutive equivalent values being kept
+    /// once. Returns `None` if the values are not sorted.
+    pub(crate) fn collect_sorted<I>(&self, iter: I) -> Option<Vec<S::Value>>
+    where
+        I: IntoIterator<Item = S::Value>,
+    {
+        let mut values: Vec<S::Value> = Vec::new();
+        for data in iter {
+            if let Some(last) = values.last() {
+                if self.comparator.compare(last, &data) == Ordering::Greater {
+                    return None;
+                }
+                if self.comparator.equivalent(&data, last) {
+                    continue;
+                }
+            }
+            values.push(data);
+        }
+        Some(values)
+    }
+
+    /// Splits the values in runs of ascending values, each run being built at once
+    /// like `assign` and then appended to the tree.
+    pub(crate) fn extend_from_iter<I: Iterator<Item = S::Value>>(&mut self, iter: I) {
+        let mut run: Vec<S::Value> = Vec::new();
+        for data in iter {
+            if let Some(last) = run.last() {
+                if self.comparator.equivalent(&data, last) {
+                    continue;
+                }
+                if self.comparator.compare(last, &data) != Ordering::Less {
+                    self.append_run(std::mem::take(&mut run));
+                }
+            }
+            run.push(data);
+        }
+        self.append_run(run);
+    }
+
+    /// Appends a run of ascending values. The run is built as a tree and joined to
+    /// the tree when all its values are less or greater than the values of the tree,
+    /// its values are inserted one by one otherwise.
+    pub(crate) fn append_run(&mut self, mut run: Vec<S::Value>) {
+        let (first, last) = match (run.first(), run.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return,
+        };
+        // a comparator may find values equivalent without them being equal, like
+        // `Containment`, the run is only joined when none of them matches the tree
+        let after = match self.last {
+            Some(ref n) => {
+                let tree_last = self.nodes.data(n);
+                self.comparator.compare(&tree_last, first) == Ordering::Less
+                    && !self.comparator.equivalent(first, &tree_last)
+            }
+            None => {
+                self.assign(run);
+                return;
+            }
+        };
+        let before = !after
+            && match self.first {
+                Some(ref n) => {
+                    let tree_first = self.nodes.data(n);
+                    self.comparator.compare(last, &tree_first) == Ordering::Less
+                        && !self.comparator.equivalent(last, &tree_first)
+                }
+                None => false,
+            };
+        if after {
+            let pivot = self.alloc(run.remove(0));
+            let right = self.build(run);
+            let left = self.take_root();
+            self.join(left, pivot, right);
+        } else if before {
+            let pivot = self.alloc(run.pop().unwrap());
+            let left = self.build(run);
+            let right = self.take_root();
+            self.join(left, pivot, right);
+        } else {
+            for data in run {
+                self.insert(data);
+            }
+        }
+    }
+
+    /// Returns where a node of the value is to be linked, see `link_at`, or `None` if
+    /// the tree already has a matching value.
+    pub(crate) fn insert_position(&self, data: &S::Value) -> Option<(Option<S::Link>, bool)> {
+        let mut parent = None;
+        let mut is_left = false;
+        let mut tmp = self.root.as_ref().map(S::duplicate);
+        while let Some(node) = tmp {
+            {
+                let current = self.nodes.data(&node);
+                if self.comparator.equivalent(data, &current) {
+                    return None;
+                }
+                is_left = self.comparator.compare(data, &current) != Ordering::Greater;
+            }
+            tmp = if is_left {
+                self.nodes.left(&node)
+            } else {
+                self.nodes.right(&node)
+            };
+            parent = Some(node);
+        }
+        Some((parent, is_left))
+    }
+
+    pub(crate) fn insert(&mut self, data: S::Value) -> Option<S::Link> {
+        let (parent, is_left) = self.insert_position(&data)?;
+        let node = self.alloc(data);
+        self.link_at(S::duplicate(&node), parent, is_left);
+        Some(node)
+    }
+
+    /// Returns true if the values of the tree are less than the pivot and the pivot
+    /// less than the values of `right`.
+    pub(crate) fn ordered_around(&self, pivot: &S::Value, right: &RawTree<S, C>) -> bool {
+        let after_left = match self.last {
+            Some(ref n) => self.comparator.compare(&self.nodes.data(n), pivot) == Ordering::Less,
+            None => true,
+        };
+        let before_right = match right.first {
+            Some(ref n) => self.comparator.compare(pivot, &right.nodes.data(n)) == Ordering::Less,
+            None => true,
+        };
+        after_left && before_right
+    }
+
+    /// Keeps the values less than the given one in the tree and returns the other
+    /// values as standalone pieces in ascending order, each piece being a lone pivot
+    /// and the subtree of the values following it.
+    ///
+    /// This operation is `O(log n)`.
+    pub(crate) fn split_off(&mut self, data: &S::Value) -> Vec<(S::Link, Option<S::Link>)> {
+        // Walk down to the split point, detaching the nodes of the path along with the
+        // subtree on their side, then join the pieces of the left side bottom-up.
+        let mut lefts = Vec::new();
+        let mut rights = Vec::new();
+        let mut tmp = self.take_root();
+        while let Some(node) = tmp {
+            let (left, right) = (self.nodes.left(&node), self.nodes.right(&node));
+            for child in left.iter().chain(right.iter()) {
+                self.nodes.set_parent(child, None);
+            }
+            self.reset(&node);
+            if self.comparator.compare(&self.nodes.data(&node), data) == Ordering::Less {
+                lefts.push((left, node));
+                tmp = right;
+            } else {
+                rights.push((node, right));
+                tmp = left;
+            }
+        }
+        for (subtree, pivot) in lefts.into_iter().rev() {
+            let right = self.root.take();
+            self.join_root(subtree, pivot, right);
+        }
+        self.reset_bounds();
+        rights.reverse();
+        rights
+    }
+
+    /// Returns the first and last nodes within the range, if any.
+    pub(crate) fn range_bounds<R>(&self, range: &R) -> Option<(S::Link, S::Link)>
+    where
+        R: RangeBounds<S::Value>,
+    {
+        assert_range(&self.comparator, range);
+        let first = self.lower_bound(range.start_bound())?;
+        let last = self.upper_bound(range.end_bound())?;
+        let ordered = self
+            .comparator
+            .compare(&self.nodes.data(&first), &self.nodes.data(&last))
+            != Ordering::Greater;
+        if ordered {
+            Some((first, last))
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn fold_range<R>(&self, range: R) -> Option<S::Summary>
+    where
+        R: RangeBounds<S::Value>,
+    {
+        assert_range(&self.comparator, &range);
+        let after_start = |node: &S::Link| {
+            after_start(
+                &self.comparator,
+                &*self.nodes.data(node),
+                range.start_bound(),
+            )
+        };
+        let before_end = |node: &S::Link| {
+            before_end(&self.comparator, &*self.nodes.data(node), range.end_bound())
+        };
+
+        // the highest node within the range, splitting it in two paths
+        let mut tmp = self.root.as_ref().map(S::duplicate);
+        let split = loop {
+            let node = tmp?;
+            if !after_start(&node) {
+                tmp = self.nodes.right(&node);
+            } else if !before_end(&node) {
+                tmp = self.nodes.left(&node);
+            } else {
+                break node;
+            }
+        };
+
+        let mut acc = S::Summary::summarize(&self.nodes.data(&split));
+        let mut tmp = self.nodes.left(&split);
+        while let Some(node) = tmp {
+            if after_start(&node) {
+                if let Some(ref right) = self.nodes.right(&node) {
+                    acc = self.nodes.summary(right).combine(&acc);
+                }
+                acc = S::Summary::summarize(&self.nodes.data(&node)).combine(&acc);
+                tmp = self.nodes.left(&node);
+            } else {
+                tmp = self.nodes.right(&node);
+            }
+        }
+        let mut tmp = self.nodes.right(&split);
+        while let Some(node) = tmp {
+            if before_end(&node) {
+                if let Some(ref left) = self.nodes.left(&node) {
+                    acc = acc.combine(&self.nodes.summary(left));
+                }
+                acc = acc.combine(&S::Summary::summarize(&self.nodes.data(&node)));
+                tmp = self.nodes.right(&node);
+            } else {
+                tmp = self.nodes.left(&node);
+            }
+        }
+        Some(acc)
+    }
+
+    pub(crate) fn repack_counted(&mut self) -> usize
+    where
+        S::Value: Consecutive,
+    {
+        match self.first.as_ref().map(S::duplicate) {
+            Some(first) => self.repack_from(first, Bound::Unbounded),
+            None => 0,
+        }
+    }
+
+    pub(crate) fn repack_range<R>(&mut self, range: R) -> usize
+    where
+        R: RangeBounds<S::Value>,
+        S::Value: Consecutive,
+    {
+        assert_range(&self.comparator, &range);
+        match self.lower_bound(range.start_bound()) {
+            Some(first) => self.repack_from(first, range.end_bound()),
+            None => 0,
+        }
+    }
+
+    /// Merges the run of consecutive values the node belongs to.
+    pub(crate) fn repack_around(&mut self, node: S::Link) -> usize
+    where
+        S::Value: Consecutive,
+    {
+        let mut first = node;
+        while let Some(prev) = self.prev(&first) {
+            if !self.nodes.data(&prev).consecutive(&self.nodes.data(&first)) {
+                break;
+            }
+            first = prev;
+        }
+        self.merge_run(&first, Bound::Unbounded)
+    }
+
+    fn repack_from(&mut self, node: S::Link, end: Bound<&S::Value>) -> usize
+    where
+        S::Value: Consecutive,
+    {
+        let mut merged = 0;
+        let mut tmp = Some(node);
+        while let Some(node) = tmp {
+            if !before_end(&self.comparator, &*self.nodes.data(&node), end) {
+                break;
+            }
+            merged += self.merge_run(&node, end);
+            tmp = self.next(&node);
+        }
+        merged
+    }
+
+    /// Merges the values following the node into it as long as they are consecutive,
+    /// returning the number of merged values.
+    fn merge_run(&mut self, node: &S::Link, end: Bound<&S::Value>) -> usize
+    where
+        S::Value: Consecutive,
+    {
+        let mut merged = 0;
+        while let Some(next) = self.next(node) {
+            let data = {
+                let (data, following) = (self.nodes.data(node), self.nodes.data(&next));
+                if !before_end(&self.comparator, &*following, end) || !data.consecutive(&following)
+                {
+                    break;
+                }
+                data.merged(&following)
+            };
+            self.remove(next);
+            self.set_data(node, data);
+            merged += 1;
+        }
+        merged
+    }
+
+    pub(crate) fn insert_coalescing(&mut self, data: S::Value) -> Option<S::Link>
+    where
+        S::Value: Consecutive + Splittable,
+    {
+        // an empty value does not overlap itself
+        data.split(&data)?;
+        let floor = self.upper_bound(Bound::Included(&data));
+        let mut node = match floor.or_else(|| self.first.as_ref().map(S::duplicate)) {
+            Some(node) if touching(&*self.nodes.data(&node), &data) => node,
+            Some(node) => match self.next(&node) {
+                Some(next) if touching(&*self.nodes.data(&next), &data) => next,
+                _ => return self.insert(data),
+            },
+            None => return self.insert(data),
+        };
+        while let Some(prev) = self.prev(&node) {
+            if !touching(&*self.nodes.data(&prev), &data) {
+                break;
+            }
+            node = prev;
+        }
+        if matches!(data.split(&self.nodes.data(&node)), Some((None, None))) {
+            return None;
+        }
+
+        let mut acc = coalesced(&*self.nodes.data(&node), data);
+        while let Some(next) = self.next(&node) {
+            if !touching(&*self.nodes.data(&next), &acc) {
+                break;
+            }
+            acc = coalesced(&*self.nodes.data(&next), acc);
+            self.remove(next);
+        }
+        self.set_data(&node, acc);
+        Some(node)
+    }
+
+    pub(crate) fn remove_range(&mut self, range: &S::Value) -> bool
+    where
+        S::Value: Splittable,
+    {
+        let mut overlapping = false;
+        let floor = self.upper_bound(Bound::Included(range));
+        let mut tmp = floor.or_else(|| self.first.as_ref().map(S::duplicate));
+        while let Some(node) = tmp {
+            tmp = self.next(&node);
+            let parts = self.nodes.data(&node).split(range);
+            match parts {
+                Some((Some(before), Some(after))) => {
+                    self.set_data(&node, before);
+                    self.insert(after);
+                }
+                Some((Some(part), None)) | Some((None, Some(part))) => self.set_data(&node, part),
+                Some((None, None)) => {
+                    self.remove(node);
+                }
+                None if self.comparator.compare(&self.nodes.data(&node), range)
+                    == Ordering::Greater =>
+                {
+                    break
+                }
+                None => continue,
+            }
+            overlapping = true;
+        }
+        overlapping
+    }
+}
+
+impl<S: Storage, C> Links for RawTree<S, C>
+where
+    S::Summary: Augment<S::Value>,
+{
+    type Link = S::Link;
+
+    fn duplicate(link: &S::Link) -> S::Link {
+        S::duplicate(link)
+    }
+
+    fn root(&self) -> Option<S::Link> {
+        self.root.as_ref().map(S::duplicate)
+    }
+
+    fn set_root(&mut self, root: Option<S::Link>) {
+        self.root = root;
+    }
+
+    fn parent(&self, node: &S::Link) -> Option<S::Link> {
+        self.nodes.parent(node)
+    }
+
+    fn set_parent(&mut self, node: &S::Link, parent: Option<S::Link>) {
+        self.nodes.set_parent(node, parent);
+    }
+
+    fn left(&self, node: &S::Link) -> Option<S::Link> {
+        self.nodes.left(node)
+    }
+
+    fn set_left(&mut self, node: &S::Link, left: Option<S::Link>) {
+        self.nodes.set_left(node, left);
+    }
+
+    fn right(&self, node: &S::Link) -> Option<S::Link> {
+        self.nodes.right(node)
+    }
+
+    fn set_right(&mut self, node: &S::Link, right: Option<S::Link>) {
+        self.nodes.set_right(node, right);
+    }
+
+    fn colour(&self, node: &S::Link) -> Colour {
+        self.nodes.colour(node)
+    }
+
+    fn set_colour(&mut self, node: &S::Link, colour: Colour) {
+        self.nodes.set_colour(node, colour);
+    }
+
+    fn refresh(&mut self, node: &S::Link) {
+        refresh(&mut self.nodes, node);
+    }
+
+    fn refresh_upward(&mut self, node: Option<S::Link>) {
+        refresh_upward(&mut self.nodes, node);
+    }
+}
+
+fn assert_range<T, C: Compare<T>, R: RangeBounds<T>>(comparator: &C, range: &R) {
+    match (range.start_bound(), range.end_bound()) {
+        (Bound::Excluded(s), Bound::Excluded(e)) if comparator.compare(s, e) == Ordering::Equal => {
+            panic!("range start and end are equal and excluded in RBTreeSet")
+        }
+        (Bound::Included(s), Bound::Included(e))
+        | (Bound::Included(s), Bound::Excluded(e))
+        | (Bound::Excluded(s), Bound::Included(e))
+        | (Bound::Excluded(s), Bound::Excluded(e))
+            if comparator.compare(s, e) == Ordering::Greater =>
+        {
+            panic!("range start is greater than range end in RBTreeSet")
+        }
+        _ => {}
+    }
+}
+
+/// Returns true if `a` and `b` overlap or are consecutive in either order.
+fn touching<T: Consecutive + Splittable>(a: &T, b: &T) -> bool {
+    a.consecutive(b) || b.consecutive(a) || a.split(b).is_some()
+}
+
+/// Returns the union of two touching values, see `touching`.
+fn coalesced<T: Consecutive + Splittable>(a: &T, b: T) -> T {
+    if a.consecutive(&b) {
+        return a.merged(&b);
+    }
+    if b.consecutive(a) {
+        return b.merged(a);
+    }
+    let (before, after) = a.split(&b).expect("overlapping values");
+    let merged = match before {
+        Some(before) => before.merged(&b),
+        None => b,
+    };
+    match after {
+        Some(after) => merged.merged(&after),
+        None => merged,
+    }
+}
+
+fn after_start<T: ?Sized, C: Compare<T>>(comparator: &C, data: &T, start: Bound<&T>) -> bool {
+    match start {
+        Bound::Included(s) => comparator.compare(data, s) != Ordering::Less,
+        Bound::Excluded(s) => comparator.compare(data, s) == Ordering::Greater,
+        Bound::Unbounded => true,
+    }
+}
+
+fn before_end<T: ?Sized, C: Compare<T>>(comparator: &C, data: &T, end: Bound<&T>) -> bool {
+    match end {
+        Bound::Included(e) => comparator.compare(data, e) != Ordering::Greater,
+        Bound::Excluded(e) => comparator.compare(data, e) == Ordering::Less,
+        Bound::Unbounded => true,
+    }
+}
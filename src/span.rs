@@ -0,0 +1,332 @@
+use std::cmp::Ordering;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::ops::{Range, RangeInclusive};
+
+use crate::{Consecutive, Splittable};
+
+/// An interface for types whose values have a direct successor and predecessor.
+///
+/// Required by [SpanInclusive] to know whether two inclusive ranges are consecutive.
+/// It is implemented for all the integer types, `char`, `Ipv4Addr` and `Ipv6Addr`.
+///
+/// [SpanInclusive]: struct.SpanInclusive.html
+///
+/// # Examples
+///
+/// ```
+/// use rbtset::Discrete;
+///
+/// assert_eq!(3u8.successor(), Some(4));
+/// assert_eq!(255u8.successor(), None);
+/// assert_eq!('\u{E000}'.predecessor(), Some('\u{D7FF}'));
+/// ```
+pub trait Discrete: Sized {
+    /// Returns the smallest value greater than `self`, if any.
+    fn successor(&self) -> Option<Self>;
+    /// Returns the greatest value less than `self`, if any.
+    fn predecessor(&self) -> Option<Self>;
+}
+
+macro_rules! discrete_integer {
+    ($($t:ty)*) => {$(
+        impl Discrete for $t {
+            fn successor(&self) -> Option<$t> {
+                self.checked_add(1)
+            }
+
+            fn predecessor(&self) -> Option<$t> {
+                self.checked_sub(1)
+            }
+        }
+    )*};
+}
+
+discrete_integer!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize);
+
+impl Discrete for char {
+    fn successor(&self) -> Option<char> {
+        match *self {
+            '\u{D7FF}' => Some('\u{E000}'),
+            c => char::from_u32(c as u32 + 1),
+        }
+    }
+
+    fn predecessor(&self) -> Option<char> {
+        match *self {
+            '\u{E000}' => Some('\u{D7FF}'),
+            c => (c as u32).checked_sub(1).and_then(char::from_u32),
+        }
+    }
+}
+
+impl Discrete for Ipv4Addr {
+    fn successor(&self) -> Option<Ipv4Addr> {
+        u32::from(*self).checked_add(1).map(Ipv4Addr::from)
+    }
+
+    fn predecessor(&self) -> Option<Ipv4Addr> {
+        u32::from(*self).checked_sub(1).map(Ipv4Addr::from)
+    }
+}
+
+impl Discrete for Ipv6Addr {
+    fn successor(&self) -> Option<Ipv6Addr> {
+        u128::from(*self).checked_add(1).map(Ipv6Addr::from)
+    }
+
+    fn predecessor(&self) -> Option<Ipv6Addr> {
+        u128::from(*self).checked_sub(1).map(Ipv6Addr::from)
+    }
+}
+
+/// Implements the ordering of a span type, by start then end, and the matching
+/// equality from its `bounds`.
+macro_rules! span_ordering {
+    ($span:ident) => {
+        impl<T: Ord> Ord for $span<T> {
+            fn cmp(&self, other: &$span<T>) -> Ordering {
+                self.bounds().cmp(&other.bounds())
+            }
+        }
+
+        impl<T: Ord> PartialOrd for $span<T> {
+            fn partial_cmp(&self, other: &$span<T>) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl<T: Ord> PartialEq for $span<T> {
+            fn eq(&self, other: &$span<T>) -> bool {
+                self.bounds() == other.bounds()
+            }
+        }
+
+        impl<T: Ord> Eq for $span<T> {}
+    };
+}
+
+/// A ready-made [Consecutive] value for a half-open range `start..end`.
+///
+/// Spans are ordered by their start then their end. Two spans are consecutive when the
+/// first one ends where the second one starts. A [RangeSet] matches a span with the
+/// span containing it, see [Containment], allowing to look a value up with a
+/// single-element span.
+///
+/// [Consecutive]: trait.Consecutive.html
+/// [RangeSet]: struct.RangeSet.html
+/// [Containment]: struct.Containment.html
+///
+/// # Examples
+///
+/// ```
+/// use rbtset::{RangeSet, Span};
+///
+/// let mut set = RangeSet::new();
+/// set.insert(Span(1..3));
+/// set.insert(Span(5..8));
+/// set.insert(Span(3..5));
+///
+/// assert_eq!(set.values().collect::<Vec<_>>(), vec![Span(1..8)]);
+/// assert!(set.contains(&Span(6..7)));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Span<T>(pub Range<T>);
+
+impl<T> Span<T> {
+    fn bounds(&self) -> (&T, &T) {
+        (&self.0.start, &self.0.end)
+    }
+}
+
+span_ordering!(Span);
+
+impl<T: Ord + Clone> Consecutive for Span<T> {
+    fn consecutive(&self, other: &Span<T>) -> bool {
+        self.0.end == other.0.start
+    }
+
+    fn merged(&self, other: &Span<T>) -> Span<T> {
+        Span(self.0.start.clone()..other.0.end.clone())
+    }
+}
+
+impl<T: Ord + Clone> Splittable for Span<T> {
+    fn split(&self, other: &Span<T>) -> Option<(Option<Span<T>>, Option<Span<T>>)> {
+        if other.0.start >= other.0.end {
+            return None;
+        }
+        if self.0.end <= other.0.start || other.0.end <= self.0.start {
+            return None;
+        }
+        let before =
+            Some(Span(self.0.start.clone()..other.0.start.clone())).filter(|s| s.0.start < s.0.end);
+        let after =
+            Some(Span(other.0.end.clone()..self.0.end.clone())).filter(|s| s.0.start < s.0.end);
+        Some((before, after))
+    }
+}
+
+impl<T> From<Range<T>> for Span<T> {
+    fn from(range: Range<T>) -> Span<T> {
+        Span(range)
+    }
+}
+
+/// A ready-made [Consecutive] value for an inclusive range `start..=end`.
+///
+/// Behaves like [Span], two inclusive spans being consecutive when the second one
+/// starts right after the end of the first one, see [Discrete]. Unlike [Span], it can
+/// hold the maximum value of a type, like the address `255.255.255.255`.
+///
+/// [Consecutive]: trait.Consecutive.html
+/// [Span]: struct.Span.html
+/// [Discrete]: trait.Discrete.html
+///
+/// # Examples
+///
+/// ```
+/// use std::net::Ipv4Addr;
+/// use rbtset::{RangeSet, SpanInclusive};
+///
+/// let mut set = RangeSet::new();
+/// set.insert(SpanInclusive(Ipv4Addr::new(10, 0, 0, 0)..=Ipv4Addr::new(10, 0, 0, 255)));
+/// set.insert(SpanInclusive(Ipv4Addr::new(10, 0, 1, 0)..=Ipv4Addr::new(10, 0, 1, 255)));
+///
+/// assert_eq!(set.len(), 1);
+/// assert!(set.contains(&SpanInclusive::from(Ipv4Addr::new(10, 0, 1, 7))));
+/// ```
+#[derive(Clone, Debug)]
+pub struct SpanInclusive<T>(pub RangeInclusive<T>);
+
+impl<T> SpanInclusive<T> {
+    fn bounds(&self) -> (&T, &T) {
+        (self.0.start(), self.0.end())
+    }
+}
+
+span_ordering!(SpanInclusive);
+
+impl<T: Ord + Clone + Discrete> Consecutive for SpanInclusive<T> {
+    fn consecutive(&self, other: &SpanInclusive<T>) -> bool {
+        self.0.end().successor().as_ref() == Some(other.0.start())
+    }
+
+    fn merged(&self, other: &SpanInclusive<T>) -> SpanInclusive<T> {
+        SpanInclusive(self.0.start().clone()..=other.0.end().clone())
+    }
+}
+
+impl<T: Ord + Clone + Discrete> Splittable for SpanInclusive<T> {
+    fn split(
+        &self,
+        other: &SpanInclusive<T>,
+    ) -> Option<(Option<SpanInclusive<T>>, Option<SpanInclusive<T>>)> {
+        if other.0.is_empty() {
+            return None;
+        }
+        if self.0.end() < other.0.start() || other.0.end() < self.0.start() {
+            return None;
+        }
+        let before = if self.0.start() < other.0.start() {
+            let end = other.0.start().predecessor().expect("greater than start");
+            Some(SpanInclusive(self.0.start().clone()..=end))
+        } else {
+            None
+        };
+        let after = if other.0.end() < self.0.end() {
+            let start = other.0.end().successor().expect("less than end");
+            Some(SpanInclusive(start..=self.0.end().clone()))
+        } else {
+            None
+        };
+        Some((before, after))
+    }
+}
+
+impl<T> From<RangeInclusive<T>> for SpanInclusive<T> {
+    fn from(range: RangeInclusive<T>) -> SpanInclusive<T> {
+        SpanInclusive(range)
+    }
+}
+
+impl<T: Clone> From<T> for SpanInclusive<T> {
+    /// Makes a span holding a single value.
+    fn from(value: T) -> SpanInclusive<T> {
+        SpanInclusive(value.clone()..=value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RangeSet;
+
+    #[test]
+    fn discrete() {
+        assert_eq!(i8::MIN.predecessor(), None);
+        assert_eq!(u64::MAX.successor(), None);
+        assert_eq!('\u{D7FF}'.successor(), Some('\u{E000}'));
+        assert_eq!(char::MAX.successor(), None);
+        assert_eq!('\0'.predecessor(), None);
+        assert_eq!(
+            Ipv4Addr::new(10, 0, 0, 255).successor(),
+            Some(Ipv4Addr::new(10, 0, 1, 0))
+        );
+        assert_eq!(Ipv6Addr::UNSPECIFIED.predecessor(), None);
+    }
+
+    #[test]
+    fn ordering() {
+        assert!(Span(1..3) < Span(1..5));
+        assert!(Span(1..5) < Span(2..3));
+        assert_ne!(Span(2..3), Span(1..5));
+        assert_eq!(Span(1..5), Span(1..5));
+        assert!(SpanInclusive(1..=3) < SpanInclusive(1..=4));
+        assert_ne!(SpanInclusive(2..=2), SpanInclusive(1..=4));
+    }
+
+    #[test]
+    fn full_coverage() {
+        let mut set = RangeSet::new();
+        for i in (0..=255u8).rev().step_by(2) {
+            set.insert(SpanInclusive::from(i));
+        }
+        assert_eq!(set.len(), 128);
+        for i in (0..=255u8).step_by(2) {
+            set.insert(SpanInclusive::from(i));
+        }
+        assert_eq!(set.len(), 1);
+        assert_eq!(set.first().unwrap().data().0, 0..=255);
+
+        assert!(set.remove_range(&SpanInclusive(10..=19)));
+        assert!(!set.contains(&SpanInclusive::from(15)));
+        assert_eq!(
+            set.values().map(|s| s.0).collect::<Vec<_>>(),
+            vec![0..=9, 20..=255]
+        );
+
+        let mut set: RangeSet<_> = vec![Span('a'..'f'), Span('k'..'p')].into_iter().collect();
+        set.insert(Span('f'..'k'));
+        assert_eq!(
+            set.values().map(|s| s.0).collect::<Vec<_>>(),
+            vec!['a'..'p']
+        );
+        assert!(set.remove_range(&Span('c'..'d')));
+        assert_eq!(
+            set.values().map(|s| s.0).collect::<Vec<_>>(),
+            vec!['a'..'c', 'd'..'p']
+        );
+    }
+
+    #[test]
+    #[allow(clippy::reversed_empty_ranges)]
+    fn split_empty() {
+        assert_eq!(Span(1..5).split(&Span(3..3)), None);
+        assert_eq!(Span(1..5).split(&Span(4..2)), None);
+        assert_eq!(SpanInclusive(1..=5).split(&SpanInclusive(4..=2)), None);
+
+        let mut set: RangeSet<_> = vec![Span(1..5)].into_iter().collect();
+        assert!(!set.remove_range(&Span(3..3)));
+        assert_eq!(set.values().map(|s| s.0).collect::<Vec<_>>(), vec![1..5]);
+    }
+}
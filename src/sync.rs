@@ -0,0 +1,1216 @@
+use std::borrow::Borrow;
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::{Bound, Deref, RangeBounds};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::arena::{ArenaRBTreeSet, Cursor, Handle};
+use crate::persistent::{PersistentIntoIter, PersistentRBTreeSet};
+use crate::{Augment, Compare, Consecutive, Natural, Splittable};
+
+/// A `Send + Sync` set based on a RB-Tree.
+///
+/// The default [RBTreeSet] links its nodes with `Rc` and cannot cross thread
+/// boundaries. This set guards an [ArenaRBTreeSet] with a `RwLock`, values being
+/// designated by [Handle]s which are plain `Copy` values and can be freely sent to
+/// other threads.
+///
+/// Readers block the writers for as long as they hold the lock, see
+/// [SnapshotRBTreeSet] to iterate while the set is updated.
+///
+/// Every method takes the lock for the duration of the call and returns clones of the
+/// values. Use [read] or [write] to perform several operations atomically or to get
+/// the iterators of [ArenaRBTreeSet], like ranges, set operations and drains.
+///
+/// [RBTreeSet]: struct.RBTreeSet.html
+/// [ArenaRBTreeSet]: struct.ArenaRBTreeSet.html
+/// [Handle]: struct.Handle.html
+/// [SnapshotRBTreeSet]: struct.SnapshotRBTreeSet.html
+/// [read]: #method.read
+/// [write]: #method.write
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::thread;
+/// use rbtset::SyncRBTreeSet;
+///
+/// let set = Arc::new(SyncRBTreeSet::new());
+/// let workers: Vec<_> = (0..4)
+///     .map(|i| {
+///         let set = Arc::clone(&set);
+///         thread::spawn(move || set.insert(i))
+///     })
+///     .collect();
+/// for worker in workers {
+///     worker.join().unwrap();
+/// }
+///
+/// assert_eq!(set.read().iter().collect::<Vec<_>>(), vec![&0, &1, &2, &3]);
+/// ```
+pub struct SyncRBTreeSet<T, A = (), C = Natural> {
+    inner: RwLock<ArenaRBTreeSet<T, A, C>>,
+}
+
+impl<T, A, C> SyncRBTreeSet<T, A, C> {
+    /// Locks the set for reading, blocking the writers until the guard is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a writer panicked while holding the lock.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::SyncRBTreeSet;
+    ///
+    /// let set: SyncRBTreeSet<_> = (1..4).collect();
+    /// let sum: i32 = set.read().iter().sum();
+    /// assert_eq!(sum, 6);
+    /// ```
+    pub fn read(&self) -> RwLockReadGuard<'_, ArenaRBTreeSet<T, A, C>> {
+        self.inner.read().expect("SyncRBTreeSet lock poisoned")
+    }
+
+    /// Locks the set for writing, blocking the other users until the guard is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a writer panicked while holding the lock.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::SyncRBTreeSet;
+    ///
+    /// let set: SyncRBTreeSet<_> = (1..4).collect();
+    /// {
+    ///     let mut guard = set.write();
+    ///     if !guard.contains(&4) {
+    ///         guard.insert(4);
+    ///     }
+    /// }
+    /// assert_eq!(set.len(), 4);
+    /// ```
+    pub fn write(&self) -> RwLockWriteGuard<'_, ArenaRBTreeSet<T, A, C>> {
+        self.inner.write().expect("SyncRBTreeSet lock poisoned")
+    }
+
+    /// Consumes the lock and returns the underlying arena set.
+    pub fn into_inner(self) -> ArenaRBTreeSet<T, A, C> {
+        self.inner
+            .into_inner()
+            .expect("SyncRBTreeSet lock poisoned")
+    }
+
+    /// Returns a clone of the value designated by the handle, if it is still in the
+    /// set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::SyncRBTreeSet;
+    ///
+    /// let set = SyncRBTreeSet::new();
+    /// let handle = set.insert(1).unwrap();
+    /// assert_eq!(set.get(handle), Some(1));
+    /// set.remove(&1);
+    /// assert_eq!(set.get(handle), None);
+    /// ```
+    pub fn get(&self, handle: Handle) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.read().get(handle).cloned()
+    }
+
+    /// Returns the handle of the next value in ascending order, if any.
+    pub fn next(&self, handle: Handle) -> Option<Handle> {
+        self.read().next(handle)
+    }
+
+    /// Returns the handle of the previous value in ascending order, if any.
+    pub fn prev(&self, handle: Handle) -> Option<Handle> {
+        self.read().prev(handle)
+    }
+
+    /// Returns the handle of the first value of the set, if any.
+    pub fn first(&self) -> Option<Handle> {
+        self.read().first()
+    }
+
+    /// Returns the handle of the last value of the set, if any.
+    pub fn last(&self) -> Option<Handle> {
+        self.read().last()
+    }
+
+    /// Returns a clone of the smallest value of the set, if not empty.
+    pub fn peek_first(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.read().peek_first().cloned()
+    }
+
+    /// Returns a clone of the greatest value of the set, if not empty.
+    pub fn peek_last(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.read().peek_last().cloned()
+    }
+
+    /// Returns the handle of the value at the given position in the set, in ascending
+    /// order, if any.
+    ///
+    /// See [ArenaRBTreeSet::nth].
+    ///
+    /// [ArenaRBTreeSet::nth]: struct.ArenaRBTreeSet.html#method.nth
+    pub fn nth(&self, index: usize) -> Option<Handle> {
+        self.read().nth(index)
+    }
+
+    /// Returns the summary of all the values of the set, if not empty.
+    ///
+    /// See [Augment] for more informations.
+    ///
+    /// [Augment]: trait.Augment.html
+    pub fn summary(&self) -> Option<A>
+    where
+        A: Clone,
+    {
+        self.read().summary()
+    }
+
+    /// Clears the set, removing all values.
+    pub fn clear(&self) {
+        self.write().clear()
+    }
+
+    /// Returns the number of values in the set.
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    /// Returns true if the set contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /// Gets an iterator that visits clones of the values in ascending order.
+    ///
+    /// The iterator holds the read lock, blocking the writers until it is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a writer panicked while holding the lock.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::SyncRBTreeSet;
+    ///
+    /// let set: SyncRBTreeSet<_> = vec![3, 1, 2].into_iter().collect();
+    /// assert_eq!(set.values().collect::<Vec<_>>(), vec![1, 2, 3]);
+    /// assert_eq!(set.values().rev().next(), Some(3));
+    /// ```
+    pub fn values(&self) -> SyncValues<'_, T, A, C> {
+        let guard = self.read();
+        SyncValues {
+            cursor: guard.cursor(),
+            guard,
+        }
+    }
+}
+
+impl<T, A: Augment<T>, C> SyncRBTreeSet<T, A, C> {
+    /// Removes the value designated by the handle from the set and returns it, if it
+    /// is still in the set.
+    pub fn remove_node(&self, handle: Handle) -> Option<T> {
+        self.write().remove_node(handle)
+    }
+
+    /// Removes the smallest value from the set and returns it, if not empty.
+    pub fn pop_first(&self) -> Option<T> {
+        self.write().pop_first()
+    }
+
+    /// Removes the greatest value from the set and returns it, if not empty.
+    pub fn pop_last(&self) -> Option<T> {
+        self.write().pop_last()
+    }
+
+    /// Retains only the values specified by the predicate, while holding the write
+    /// lock.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::SyncRBTreeSet;
+    ///
+    /// let set: SyncRBTreeSet<_> = (0..10).collect();
+    /// set.retain(|v| v % 3 == 0);
+    /// assert_eq!(set.values().collect::<Vec<_>>(), vec![0, 3, 6, 9]);
+    /// ```
+    pub fn retain<F>(&self, f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.write().retain(f)
+    }
+}
+
+impl<T: Ord> SyncRBTreeSet<T> {
+    /// Makes a new `SyncRBTreeSet`.
+    pub fn new() -> SyncRBTreeSet<T> {
+        Self::default()
+    }
+}
+
+impl<T: Ord, A: Augment<T>> SyncRBTreeSet<T, A> {
+    /// Makes a new `SyncRBTreeSet` maintaining a summary of its values.
+    ///
+    /// See [Augment] for more informations.
+    ///
+    /// [Augment]: trait.Augment.html
+    pub fn new_augmented() -> SyncRBTreeSet<T, A> {
+        Self::default()
+    }
+}
+
+impl<T, C: Compare<T>> SyncRBTreeSet<T, (), C> {
+    /// Makes a new `SyncRBTreeSet` ordering its values with the given comparator.
+    ///
+    /// See [Compare] for more informations.
+    ///
+    /// [Compare]: trait.Compare.html
+    pub fn with_comparator(comparator: C) -> SyncRBTreeSet<T, (), C> {
+        SyncRBTreeSet::from(ArenaRBTreeSet::with_comparator(comparator))
+    }
+}
+
+impl<T, A: Augment<T>, C: Compare<T>> SyncRBTreeSet<T, A, C> {
+    /// Adds a value to the set.
+    ///
+    /// Returns the handle of the new value, or `None` if the value was already present.
+    pub fn insert(&self, data: T) -> Option<Handle> {
+        self.write().insert(data)
+    }
+
+    /// Returns the handle of the value matching the given value, if present.
+    ///
+    /// The value may be any borrowed form of the set's value type, but the ordering
+    /// on the borrowed form must match the ordering on the value type.
+    pub fn get_node<Q>(&self, data: &Q) -> Option<Handle>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.read().get_node(data)
+    }
+
+    /// Returns true if the set contains a value matching the given value.
+    ///
+    /// The value may be any borrowed form of the set's value type, but the ordering
+    /// on the borrowed form must match the ordering on the value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::SyncRBTreeSet;
+    ///
+    /// let set: SyncRBTreeSet<String> = ["a", "b"].iter().map(|s| s.to_string()).collect();
+    /// assert!(set.contains("a"));
+    /// assert!(!set.contains("c"));
+    /// ```
+    pub fn contains<Q>(&self, data: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.read().contains(data)
+    }
+
+    /// Removes a value from the set. Returns whether the value was present.
+    ///
+    /// The value may be any borrowed form of the set's value type, but the ordering
+    /// on the borrowed form must match the ordering on the value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::SyncRBTreeSet;
+    ///
+    /// let set: SyncRBTreeSet<_> = (1..4).collect();
+    /// assert_eq!(set.remove(&2), true);
+    /// assert_eq!(set.remove(&2), false);
+    /// ```
+    pub fn remove<Q>(&self, data: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.write().remove(data)
+    }
+
+    /// Returns the handle of the first value of the set matching the given lower
+    /// bound, if any.
+    ///
+    /// See [ArenaRBTreeSet::lower_bound].
+    ///
+    /// [ArenaRBTreeSet::lower_bound]: struct.ArenaRBTreeSet.html#method.lower_bound
+    pub fn lower_bound<Q>(&self, bound: Bound<&Q>) -> Option<Handle>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.read().lower_bound(bound)
+    }
+
+    /// Returns the handle of the last value of the set matching the given upper bound,
+    /// if any.
+    ///
+    /// See [ArenaRBTreeSet::upper_bound].
+    ///
+    /// [ArenaRBTreeSet::upper_bound]: struct.ArenaRBTreeSet.html#method.upper_bound
+    pub fn upper_bound<Q>(&self, bound: Bound<&Q>) -> Option<Handle>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.read().upper_bound(bound)
+    }
+
+    /// Returns the handle of the smallest value greater than or equal to the given
+    /// value, if any.
+    pub fn ceiling<Q>(&self, data: &Q) -> Option<Handle>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.read().ceiling(data)
+    }
+
+    /// Returns the handle of the greatest value less than or equal to the given value,
+    /// if any.
+    pub fn floor<Q>(&self, data: &Q) -> Option<Handle>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.read().floor(data)
+    }
+
+    /// Returns the handle of the smallest value strictly greater than the given value,
+    /// if any.
+    pub fn successor_of<Q>(&self, data: &Q) -> Option<Handle>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.read().successor_of(data)
+    }
+
+    /// Returns the handle of the greatest value strictly less than the given value, if
+    /// any.
+    pub fn predecessor_of<Q>(&self, data: &Q) -> Option<Handle>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.read().predecessor_of(data)
+    }
+
+    /// Returns the number of values in the set that are strictly less than the given
+    /// value.
+    ///
+    /// See [ArenaRBTreeSet::rank].
+    ///
+    /// [ArenaRBTreeSet::rank]: struct.ArenaRBTreeSet.html#method.rank
+    pub fn rank<Q>(&self, data: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.read().rank(data)
+    }
+
+    /// Returns the combined summary of the values within the given range, if any.
+    ///
+    /// See [ArenaRBTreeSet::fold_range].
+    ///
+    /// [ArenaRBTreeSet::fold_range]: struct.ArenaRBTreeSet.html#method.fold_range
+    pub fn fold_range<R>(&self, range: R) -> Option<A>
+    where
+        R: RangeBounds<T>,
+    {
+        self.read().fold_range(range)
+    }
+
+    /// Optimize the set by merging values where applicable while keeping the ordering.
+    ///
+    /// See [ArenaRBTreeSet::repack].
+    ///
+    /// [ArenaRBTreeSet::repack]: struct.ArenaRBTreeSet.html#method.repack
+    pub fn repack(&self)
+    where
+        T: Consecutive,
+    {
+        self.write().repack()
+    }
+
+    /// Optimize the set like [repack](#method.repack), returning the number of merged
+    /// values.
+    pub fn repack_counted(&self) -> usize
+    where
+        T: Consecutive,
+    {
+        self.write().repack_counted()
+    }
+
+    /// Merges the consecutive values within the given range, returning the number of
+    /// merged values.
+    ///
+    /// See [ArenaRBTreeSet::repack_range].
+    ///
+    /// [ArenaRBTreeSet::repack_range]: struct.ArenaRBTreeSet.html#method.repack_range
+    pub fn repack_range<R>(&self, range: R) -> usize
+    where
+        R: RangeBounds<T>,
+        T: Consecutive,
+    {
+        self.write().repack_range(range)
+    }
+
+    /// Merges the value designated by the handle with its consecutive neighbours,
+    /// returning the number of merged values.
+    ///
+    /// See [ArenaRBTreeSet::repack_around].
+    ///
+    /// [ArenaRBTreeSet::repack_around]: struct.ArenaRBTreeSet.html#method.repack_around
+    pub fn repack_around(&self, handle: Handle) -> usize
+    where
+        T: Consecutive,
+    {
+        self.write().repack_around(handle)
+    }
+
+    /// Adds a value to the set, merging it with its consecutive and overlapping
+    /// neighbours.
+    ///
+    /// See [ArenaRBTreeSet::insert_coalescing].
+    ///
+    /// [ArenaRBTreeSet::insert_coalescing]: struct.ArenaRBTreeSet.html#method.insert_coalescing
+    pub fn insert_coalescing(&self, data: T) -> Option<Handle>
+    where
+        T: Consecutive + Splittable,
+    {
+        self.write().insert_coalescing(data)
+    }
+
+    /// Removes the parts of the values of the set covered by the given range.
+    ///
+    /// See [ArenaRBTreeSet::remove_range].
+    ///
+    /// [ArenaRBTreeSet::remove_range]: struct.ArenaRBTreeSet.html#method.remove_range
+    pub fn remove_range(&self, range: &T) -> bool
+    where
+        T: Splittable,
+    {
+        self.write().remove_range(range)
+    }
+
+    /// Makes a new `SyncRBTreeSet` from the values of `left`, the pivot and the values
+    /// of `right`.
+    ///
+    /// See [ArenaRBTreeSet::join].
+    ///
+    /// [ArenaRBTreeSet::join]: struct.ArenaRBTreeSet.html#method.join
+    pub fn join(left: Self, pivot: T, right: Self) -> Self {
+        SyncRBTreeSet::from(ArenaRBTreeSet::join(
+            left.into_inner(),
+            pivot,
+            right.into_inner(),
+        ))
+    }
+
+    /// Splits the set in two at the given value, while holding the write lock.
+    /// Returns everything after the given value, including the value.
+    ///
+    /// See [ArenaRBTreeSet::split_off].
+    ///
+    /// [ArenaRBTreeSet::split_off]: struct.ArenaRBTreeSet.html#method.split_off
+    pub fn split_off(&self, data: &T) -> Self
+    where
+        C: Clone,
+    {
+        SyncRBTreeSet::from(self.write().split_off(data))
+    }
+
+    /// Moves all the values of `other` into the set, while holding the write lock.
+    ///
+    /// `other` is borrowed mutably and needs no locking.
+    ///
+    /// See [ArenaRBTreeSet::append].
+    ///
+    /// [ArenaRBTreeSet::append]: struct.ArenaRBTreeSet.html#method.append
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::SyncRBTreeSet;
+    ///
+    /// let set: SyncRBTreeSet<_> = (0..5).collect();
+    /// let mut other: SyncRBTreeSet<_> = (5..10).collect();
+    /// set.append(&mut other);
+    /// assert_eq!(set.len(), 10);
+    /// assert!(other.is_empty());
+    /// ```
+    pub fn append(&self, other: &mut Self) {
+        let other = other.inner.get_mut().expect("SyncRBTreeSet lock poisoned");
+        self.write().append(other)
+    }
+}
+
+impl<T, A, C: Default> Default for SyncRBTreeSet<T, A, C> {
+    fn default() -> Self {
+        SyncRBTreeSet::from(ArenaRBTreeSet::default())
+    }
+}
+
+impl<T, A, C> fmt::Debug for SyncRBTreeSet<T, A, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SyncRBTreeSet {{ length: {} }}", self.len())
+    }
+}
+
+impl<T, A, C> From<ArenaRBTreeSet<T, A, C>> for SyncRBTreeSet<T, A, C> {
+    fn from(set: ArenaRBTreeSet<T, A, C>) -> Self {
+        SyncRBTreeSet {
+            inner: RwLock::new(set),
+        }
+    }
+}
+
+impl<T, A: Augment<T>, C: Compare<T> + Default> FromIterator<T> for SyncRBTreeSet<T, A, C> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        SyncRBTreeSet::from(iter.into_iter().collect::<ArenaRBTreeSet<T, A, C>>())
+    }
+}
+
+impl<T, A: Augment<T>, C: Compare<T>> Extend<T> for SyncRBTreeSet<T, A, C> {
+    /// Extends the set while holding the write lock.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.write().extend(iter);
+    }
+}
+
+/// Created with the method [values].
+///
+/// [values]: struct.SyncRBTreeSet.html#method.values
+pub struct SyncValues<'a, T, A = (), C = Natural> {
+    guard: RwLockReadGuard<'a, ArenaRBTreeSet<T, A, C>>,
+    cursor: Cursor,
+}
+
+impl<T: Clone, A, C> Iterator for SyncValues<'_, T, A, C> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.cursor.next(&self.guard).cloned()
+    }
+}
+
+impl<T: Clone, A, C> DoubleEndedIterator for SyncValues<'_, T, A, C> {
+    fn next_back(&mut self) -> Option<T> {
+        self.cursor.next_back(&self.guard).cloned()
+    }
+}
+
+/// A `Send + Sync` set based on a RB-Tree offering snapshot iterations.
+///
+/// The set holds the current version of a [PersistentRBTreeSet]: a [snapshot] is a
+/// cheap reference to this version which is never modified afterwards, so a
+/// long-running scan never observes a write in progress. A write creates a new version
+/// copying the `O(log n)` nodes on the path to the updated value, whether snapshots
+/// are alive or not, the other nodes being shared.
+///
+/// In exchange, the nodes are reference counted with `Arc` and the values are cloned
+/// along the copied paths, and by the iterators of the snapshots.
+///
+/// The values are ordered by the comparator `C`, see [with_comparator].
+///
+/// [PersistentRBTreeSet]: struct.PersistentRBTreeSet.html
+/// [snapshot]: #method.snapshot
+/// [with_comparator]: #method.with_comparator
+///
+/// # Examples
+///
+/// ```
+/// use rbtset::SnapshotRBTreeSet;
+///
+/// let set: SnapshotRBTreeSet<_> = (1..4).collect();
+/// let mut values = set.values();
+/// assert_eq!(values.next(), Some(1));
+///
+/// // the iteration is not affected by the writes
+/// set.insert(0);
+/// set.remove(&2);
+/// assert_eq!(values.collect::<Vec<_>>(), vec![2, 3]);
+/// assert_eq!(set.values().collect::<Vec<_>>(), vec![0, 1, 3]);
+/// ```
+pub struct SnapshotRBTreeSet<T, C = Natural> {
+    current: RwLock<PersistentRBTreeSet<T, C>>,
+}
+
+impl<T, C> SnapshotRBTreeSet<T, C> {
+    /// Returns a snapshot of the current state of the set, in `O(1)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a writer panicked while holding the lock.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::SnapshotRBTreeSet;
+    ///
+    /// let set: SnapshotRBTreeSet<_> = (1..4).collect();
+    /// let snapshot = set.snapshot();
+    /// set.clear();
+    /// assert_eq!(snapshot.len(), 3);
+    /// assert!(set.is_empty());
+    /// ```
+    pub fn snapshot(&self) -> Snapshot<T, C> {
+        let current = self
+            .current
+            .read()
+            .expect("SnapshotRBTreeSet lock poisoned");
+        Snapshot(current.clone())
+    }
+
+    /// Gets an iterator over a snapshot of the set, visiting the values in ascending
+    /// order.
+    pub fn values(&self) -> SnapshotValues<T>
+    where
+        T: Clone,
+    {
+        self.snapshot().values()
+    }
+
+    /// Returns the number of values in the set.
+    pub fn len(&self) -> usize {
+        self.snapshot().len()
+    }
+
+    /// Returns true if the set contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.snapshot().is_empty()
+    }
+
+    /// Applies a batch of modifications to the set atomically, by replacing the
+    /// current version of the set.
+    ///
+    /// The existing snapshots are not affected, the new ones see either none or all
+    /// the modifications.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a writer panicked while holding the lock.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::SnapshotRBTreeSet;
+    ///
+    /// let set: SnapshotRBTreeSet<_> = (1..4).collect();
+    /// let removed = set.update(|s| {
+    ///     let removed = s.contains(&1);
+    ///     *s = s.insert(4).remove(&1);
+    ///     removed
+    /// });
+    /// assert!(removed);
+    /// assert_eq!(set.values().collect::<Vec<_>>(), vec![2, 3, 4]);
+    /// ```
+    pub fn update<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut PersistentRBTreeSet<T, C>) -> R,
+    {
+        let mut current = self
+            .current
+            .write()
+            .expect("SnapshotRBTreeSet lock poisoned");
+        f(&mut current)
+    }
+
+    /// Clears the set, removing all values.
+    pub fn clear(&self) {
+        self.update(|s| *s = s.clear())
+    }
+}
+
+impl<T: Ord> SnapshotRBTreeSet<T> {
+    /// Makes a new `SnapshotRBTreeSet`.
+    pub fn new() -> SnapshotRBTreeSet<T> {
+        Self::default()
+    }
+}
+
+impl<T, C: Compare<T>> SnapshotRBTreeSet<T, C> {
+    /// Makes a new `SnapshotRBTreeSet` ordering its values with the given comparator.
+    ///
+    /// See [Compare] for more informations.
+    ///
+    /// [Compare]: trait.Compare.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::SnapshotRBTreeSet;
+    ///
+    /// let set = SnapshotRBTreeSet::with_comparator(|a: &i32, b: &i32| b.cmp(a));
+    /// set.insert(1);
+    /// set.insert(2);
+    /// assert_eq!(set.values().collect::<Vec<_>>(), vec![2, 1]);
+    /// ```
+    pub fn with_comparator(comparator: C) -> SnapshotRBTreeSet<T, C> {
+        SnapshotRBTreeSet::from(PersistentRBTreeSet::with_comparator(comparator))
+    }
+
+    /// Returns true if the set contains a value matching the given value.
+    ///
+    /// The value may be any borrowed form of the set's value type, but the ordering
+    /// on the borrowed form must match the ordering on the value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::SnapshotRBTreeSet;
+    ///
+    /// let set: SnapshotRBTreeSet<String> = ["a", "b"].iter().map(|s| s.to_string()).collect();
+    /// assert!(set.contains("a"));
+    /// assert!(!set.contains("c"));
+    /// ```
+    pub fn contains<Q>(&self, data: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.snapshot().contains(data)
+    }
+}
+
+impl<T: Clone, C: Compare<T>> SnapshotRBTreeSet<T, C> {
+    /// Adds a value to the set. Returns whether the value was newly inserted.
+    pub fn insert(&self, data: T) -> bool {
+        self.update(|s| {
+            let length = s.len();
+            *s = s.insert(data);
+            s.len() > length
+        })
+    }
+
+    /// Removes a value from the set. Returns whether the value was present.
+    ///
+    /// The value may be any borrowed form of the set's value type, but the ordering
+    /// on the borrowed form must match the ordering on the value type.
+    pub fn remove<Q>(&self, data: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.update(|s| {
+            let length = s.len();
+            *s = s.remove(data);
+            s.len() < length
+        })
+    }
+}
+
+impl<T, C: Default> Default for SnapshotRBTreeSet<T, C> {
+    fn default() -> Self {
+        SnapshotRBTreeSet::from(PersistentRBTreeSet::default())
+    }
+}
+
+impl<T, C> fmt::Debug for SnapshotRBTreeSet<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SnapshotRBTreeSet {{ length: {} }}", self.len())
+    }
+}
+
+impl<T, C> From<PersistentRBTreeSet<T, C>> for SnapshotRBTreeSet<T, C> {
+    fn from(set: PersistentRBTreeSet<T, C>) -> Self {
+        SnapshotRBTreeSet {
+            current: RwLock::new(set),
+        }
+    }
+}
+
+impl<T: Clone, C: Compare<T> + Default> FromIterator<T> for SnapshotRBTreeSet<T, C> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        SnapshotRBTreeSet::from(iter.into_iter().collect::<PersistentRBTreeSet<T, C>>())
+    }
+}
+
+/// An immutable state of a [SnapshotRBTreeSet].
+///
+/// It dereferences to a version of a [PersistentRBTreeSet] for lookups and iterations.
+///
+/// [SnapshotRBTreeSet]: struct.SnapshotRBTreeSet.html
+/// [PersistentRBTreeSet]: struct.PersistentRBTreeSet.html
+pub struct Snapshot<T, C = Natural>(PersistentRBTreeSet<T, C>);
+
+impl<T, C> Snapshot<T, C> {
+    /// Gets an iterator that visits the values in ascending order.
+    ///
+    /// The iterator keeps the snapshot alive and can be sent to another thread.
+    pub fn values(&self) -> SnapshotValues<T>
+    where
+        T: Clone,
+    {
+        SnapshotValues(self.0.clone().into_iter())
+    }
+}
+
+impl<T, C> Clone for Snapshot<T, C> {
+    fn clone(&self) -> Self {
+        Snapshot(self.0.clone())
+    }
+}
+
+impl<T, C> Deref for Snapshot<T, C> {
+    type Target = PersistentRBTreeSet<T, C>;
+
+    fn deref(&self) -> &PersistentRBTreeSet<T, C> {
+        &self.0
+    }
+}
+
+impl<T, C> fmt::Debug for Snapshot<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Snapshot {{ length: {} }}", self.0.len())
+    }
+}
+
+/// Created with the method [values] of a set or of a snapshot.
+///
+/// [values]: struct.SnapshotRBTreeSet.html#method.values
+pub struct SnapshotValues<T>(PersistentIntoIter<T>);
+
+impl<T: Clone> Iterator for SnapshotValues<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.next()
+    }
+}
+
+impl<T: Clone> DoubleEndedIterator for SnapshotValues<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.0.next_back()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::*;
+    use crate::{Node, RBTreeSet, Span};
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Sum(i64);
+
+    impl Augment<i64> for Sum {
+        fn summarize(data: &i64) -> Sum {
+            Sum(*data)
+        }
+
+        fn combine(&self, other: &Sum) -> Sum {
+            Sum(self.0 + other.0)
+        }
+    }
+
+    fn assert_send_sync<S: Send + Sync>() {}
+
+    #[test]
+    fn send_sync() {
+        assert_send_sync::<SyncRBTreeSet<i32>>();
+        assert_send_sync::<Handle>();
+        assert_send_sync::<SnapshotRBTreeSet<i32>>();
+        assert_send_sync::<SnapshotValues<i32>>();
+        assert_send_sync::<PersistentRBTreeSet<i32>>();
+    }
+
+    /// The value popped from a set, given back in a node by `RBTreeSet` when handles
+    /// are held.
+    trait Popped<T> {
+        fn value(self) -> T;
+    }
+
+    impl<T, A> Popped<T> for Result<T, Node<T, A>> {
+        fn value(self) -> T {
+            self.ok().expect("no handle is held")
+        }
+    }
+
+    impl Popped<i64> for i64 {
+        fn value(self) -> i64 {
+            self
+        }
+    }
+
+    impl Popped<Span<i64>> for Span<i64> {
+        fn value(self) -> Span<i64> {
+            self
+        }
+    }
+
+    /// Runs the same calls on a set, returning what they observed.
+    macro_rules! exercise {
+        ($set:expr) => {{
+            let set = $set;
+            let mut log = Vec::new();
+            set.extend(0..40);
+            set.extend(vec![45, 41, 60]);
+            log.push(set.insert(100).is_some() as i64);
+            log.push(set.remove(&3) as i64);
+            log.push(set.contains(&4) as i64);
+            log.push(set.get_node(&3).is_some() as i64);
+            log.push(set.rank(&20) as i64);
+            log.push(set.nth(5).is_some() as i64);
+            log.push(set.ceiling(&42).is_some() as i64);
+            log.push(set.successor_of(&100).is_some() as i64);
+            log.push(set.summary().map_or(0, |s| s.0));
+            log.push(set.fold_range(10..20).map_or(0, |s| s.0));
+            log.push(*set.peek_first().unwrap());
+            set.retain(|v| v % 7 != 0);
+            log.push(set.pop_first().unwrap().value());
+            log.push(set.pop_last().unwrap().value());
+            log.push(set.drain_range(30..35).count() as i64);
+            let mut other = set.split_off(&25);
+            log.push(other.len() as i64);
+            log.push(set.union(&other).count() as i64);
+            log.push(set.is_disjoint(&other) as i64);
+            log.push(set.is_subset(&other) as i64);
+            set.append(&mut other);
+            log.push(set.range(10..30).count() as i64);
+            log.push(set.extract_if(|v| v % 2 == 0).count() as i64);
+            log.push(set.drain().count() as i64);
+            log.push(set.len() as i64);
+            log
+        }};
+    }
+
+    /// Runs the same calls on a set of spans, returning the spans left.
+    macro_rules! exercise_spans {
+        ($set:expr) => {{
+            let set = $set;
+            let mut log = Vec::new();
+            for i in 0..20 {
+                set.insert(Span(i * 2..i * 2 + 2));
+            }
+            log.push(set.repack_range(Span(4..6)..Span(10..12)) as i64);
+            log.push(set.repack_counted() as i64);
+            log.push(set.remove_range(&Span(5..8)) as i64);
+            log.push(set.insert_coalescing(Span(4..6)).is_some() as i64);
+            log.push(set.insert_coalescing(Span(50..60)).is_some() as i64);
+            while let Some(span) = set.pop_first().map(Popped::value) {
+                log.extend(vec![span.0.start, span.0.end]);
+            }
+            log
+        }};
+    }
+
+    #[test]
+    fn shared_api() {
+        let mut tree: RBTreeSet<i64, Sum> = RBTreeSet::new_augmented();
+        let sync: SyncRBTreeSet<i64, Sum> = SyncRBTreeSet::new_augmented();
+        assert_eq!(exercise!(&mut tree), exercise!(&mut *sync.write()));
+
+        let mut tree: RBTreeSet<Span<i64>> = RBTreeSet::new();
+        let sync: SyncRBTreeSet<Span<i64>> = SyncRBTreeSet::new();
+        assert_eq!(
+            exercise_spans!(&mut tree),
+            exercise_spans!(&mut *sync.write())
+        );
+
+        // the calls forwarded by the lock
+        let set: SyncRBTreeSet<i64, Sum> = (0..10).collect();
+        assert_eq!(set.summary(), Some(Sum(45)));
+        assert_eq!(set.fold_range(2..4), Some(Sum(5)));
+        assert_eq!(set.rank(&5), 5);
+        assert_eq!(set.nth(3).and_then(|h| set.get(h)), Some(3));
+        assert_eq!(set.floor(&-1), None);
+        assert_eq!(set.pop_last(), Some(9));
+        let right = set.split_off(&5);
+        assert_eq!(right.values().collect::<Vec<_>>(), vec![5, 6, 7, 8]);
+        let set = SyncRBTreeSet::join(set, 10, SyncRBTreeSet::new_augmented());
+        assert_eq!(set.peek_last(), Some(10));
+    }
+
+    #[test]
+    fn snapshot_versions() {
+        let set: SnapshotRBTreeSet<_> = (0..64).collect();
+        let before = set.snapshot();
+        assert!(!set.insert(10));
+        assert!(!set.remove(&100));
+        assert!(set.snapshot().ptr_eq(&before));
+
+        assert!(set.insert(64));
+        assert!(!set.snapshot().ptr_eq(&before));
+        assert!(before.iter().eq((0..64).collect::<Vec<_>>().iter()));
+        assert_eq!(set.values().next_back(), Some(64));
+    }
+
+    #[test]
+    fn lookups() {
+        let mut set = SyncRBTreeSet::with_comparator(|a: &String, b: &String| {
+            a.to_lowercase().cmp(&b.to_lowercase())
+        });
+        set.extend(vec!["b".to_owned(), "D".to_owned(), "f".to_owned()]);
+        assert!(set.insert("B".to_owned()).is_none());
+        assert!(set.contains(&"d".to_owned()));
+
+        let handle = set.lower_bound(Bound::Excluded(&"c".to_owned())).unwrap();
+        assert_eq!(set.get(handle), Some("D".to_owned()));
+        let handle = set.upper_bound(Bound::Excluded(&"b".to_owned()));
+        assert_eq!(handle, None);
+
+        let values = set.values();
+        assert_eq!(values.rev().collect::<Vec<_>>(), vec!["f", "D", "b"]);
+        assert!(set.remove(&"F".to_owned()));
+        assert_eq!(set.read().range(.."c".to_owned()).count(), 1);
+
+        let words: SyncRBTreeSet<String> = vec!["a".to_owned()].into_iter().collect();
+        assert!(words.contains("a"));
+        assert!(words.get_node("b").is_none());
+    }
+
+    #[test]
+    fn snapshot_comparator() {
+        let set = SnapshotRBTreeSet::with_comparator(|a: &String, b: &String| {
+            a.to_lowercase().cmp(&b.to_lowercase())
+        });
+        assert!(set.insert("b".to_owned()));
+        assert!(set.insert("A".to_owned()));
+        assert!(!set.insert("a".to_owned()));
+        let snapshot = set.snapshot();
+        assert!(set.remove(&"B".to_owned()));
+        set.clear();
+        assert!(set.insert("c".to_owned()));
+        assert!(set.values().eq(vec!["c"]));
+        assert!(snapshot.values().eq(vec!["A", "b"]));
+
+        let words: SnapshotRBTreeSet<String> = vec!["a".to_owned()].into_iter().collect();
+        assert!(words.contains("a"));
+        assert!(!words.remove("b"));
+        assert!(words.remove("a"));
+    }
+
+    #[test]
+    fn readers_and_writer() {
+        let set: Arc<SyncRBTreeSet<u32>> = Arc::new((0..100).map(|i| i * 2).collect());
+        let writer = {
+            let set = Arc::clone(&set);
+            thread::spawn(move || {
+                for i in 0..2000 {
+                    set.insert(i * 2 + 1);
+                    set.remove(&(i * 2 + 1));
+                    if i % 10 == 0 {
+                        set.insert(1000 + i);
+                    }
+                }
+            })
+        };
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let set = Arc::clone(&set);
+                thread::spawn(move || {
+                    for _ in 0..200 {
+                        let guard = set.read();
+                        let values: Vec<_> = guard.iter().cloned().collect();
+                        assert_eq!(values.len(), guard.len());
+                        assert!(values.windows(2).all(|w| w[0] < w[1]));
+                        // values never removed by the writer are always visible
+                        assert!((0..100).all(|i| guard.contains(&(i * 2))));
+                        drop(guard);
+
+                        // handles are resolved against the current state of the set
+                        if let Some(handle) = set.get_node(&1000) {
+                            assert_eq!(set.get(handle), Some(1000));
+                        }
+                        let mut tmp = set.first();
+                        while let Some(handle) = tmp {
+                            tmp = set.next(handle);
+                        }
+                    }
+                })
+            })
+            .collect();
+        writer.join().unwrap();
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        assert_eq!(set.len(), 100 + 200);
+    }
+
+    #[test]
+    fn snapshot_readers_and_writer() {
+        let set: Arc<SnapshotRBTreeSet<u32>> = Arc::new((0..100).map(|i| i * 2).collect());
+        let writer = {
+            let set = Arc::clone(&set);
+            thread::spawn(move || {
+                for i in 0..500 {
+                    set.insert(i * 2 + 1);
+                    set.update(|s| *s = s.remove(&(i * 2 + 1)).insert(1000 + i * 2));
+                }
+            })
+        };
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let set = Arc::clone(&set);
+                thread::spawn(move || {
+                    for _ in 0..100 {
+                        let snapshot = set.snapshot();
+                        let mut count = 0;
+                        let mut previous = None;
+                        for value in snapshot.values() {
+                            assert!(previous < Some(value));
+                            previous = Some(value);
+                            count += 1;
+                            thread::yield_now();
+                        }
+                        assert_eq!(count, snapshot.len());
+                        assert!((0..100).all(|i| snapshot.contains(&(i * 2))));
+                        // the batched updates are never seen half applied
+                        let odd = snapshot.iter().filter(|v| *v % 2 == 1).count();
+                        let batched = snapshot.iter().filter(|v| **v >= 1000).count();
+                        assert!(odd <= 1);
+                        assert_eq!(snapshot.len(), 100 + odd + batched);
+                    }
+                })
+            })
+            .collect();
+        writer.join().unwrap();
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        assert_eq!(set.len(), 100 + 500);
+    }
+}
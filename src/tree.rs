@@ -1,8 +1,12 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
 use std::fmt;
-use std::iter::FromIterator;
+use std::iter::{FromIterator, Rev};
+use std::ops::{Bound, Deref, RangeBounds};
 
-use crate::node::{Colour, Node};
-use crate::Consecutive;
+use crate::node::{Node, Nodes};
+use crate::raw::{self, RawTree, Storage};
+use crate::{Augment, Compare, Consecutive, Natural, Splittable};
 
 /// A set based on a RB-Tree for efficient operations.
 ///
@@ -60,23 +64,93 @@ use crate::Consecutive;
 ///     println!("{}", number);
 /// }
 /// ```
-#[derive(Default)]
-pub struct RBTreeSet<T> {
-    root: Option<Node<T>>,
-    length: usize,
+pub struct RBTreeSet<T, A = (), C = Natural> {
+    raw: RawTree<Nodes<T, A>, C>,
 }
 
 impl<T: Ord> RBTreeSet<T> {
     /// Makes a new `RBTreeSet`.
     pub fn new() -> RBTreeSet<T> {
+        RBTreeSet::new_augmented()
+    }
+}
+
+impl<T: Ord, A: Augment<T>> RBTreeSet<T, A> {
+    /// Makes a new `RBTreeSet` caching the summary `A` of each subtree.
+    ///
+    /// See [Augment] for more informations.
+    ///
+    /// [Augment]: trait.Augment.html
+    pub fn new_augmented() -> RBTreeSet<T, A> {
+        RBTreeSet::default()
+    }
+}
+
+impl<T, C: Compare<T>> RBTreeSet<T, (), C> {
+    /// Makes a new `RBTreeSet` ordering its values with the given comparator.
+    ///
+    /// See [Compare] for more informations.
+    ///
+    /// [Compare]: trait.Compare.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let mut set = RBTreeSet::with_comparator(|a: &String, b: &String| {
+    ///     a.to_lowercase().cmp(&b.to_lowercase())
+    /// });
+    /// set.insert("b".to_owned());
+    /// set.insert("A".to_owned());
+    /// assert!(set.insert("a".to_owned()).is_none());
+    /// assert_eq!(set.values().collect::<Vec<_>>(), vec!["A", "b"]);
+    /// ```
+    pub fn with_comparator(comparator: C) -> RBTreeSet<T, (), C> {
         RBTreeSet {
-            root: None,
-            length: 0,
+            raw: RawTree::new(Nodes::new(), comparator),
         }
     }
+}
+
+impl<T, A: Augment<T>, C: Compare<T>> RBTreeSet<T, A, C> {
+    /// Makes a new `RBTreeSet` from values in ascending order.
+    ///
+    /// The tree is built balanced at once, making this operation `O(n)` instead of the
+    /// `O(n log n)` of successive insertions. Consecutive equivalent values are kept
+    /// once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the values are not in ascending order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let set: RBTreeSet<_> = RBTreeSet::from_sorted_iter(0..1000);
+    /// assert_eq!(set.len(), 1000);
+    /// assert_eq!(*set.nth(500).unwrap().data(), 500);
+    /// ```
+    pub fn from_sorted_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        C: Default,
+    {
+        let mut set = Self::default();
+        match set.raw.collect_sorted(iter) {
+            Some(values) => set.raw.assign(values),
+            None => panic!("values are not sorted in RBTreeSet::from_sorted_iter"),
+        }
+        set
+    }
 
     /// Returns the value in the set, if any, that is matching the given value.
     ///
+    /// The value may be any borrowed form of the set's value type, but the ordering
+    /// on the borrowed form must match the ordering on the value type.
+    ///
     /// Use [get_node] in pair with [Node::data] if you want to avoid value cloning.
     ///
     /// [get_node]: #method.get_node
@@ -91,131 +165,40 @@ impl<T: Ord> RBTreeSet<T> {
     /// assert_eq!(set.get(&2), Some(2));
     /// assert_eq!(set.get(&4), None);
     /// ```
-    pub fn get(&self, data: &T) -> Option<T>
+    pub fn get<Q>(&self, data: &Q) -> Option<T>
     where
-        T: Clone,
+        T: Borrow<Q> + Clone,
+        Q: ?Sized,
+        C: Compare<Q>,
     {
         self.get_node(data).as_ref().map(|n| n.clone_data())
     }
 
-    fn insert_from(&mut self, mut root: Node<T>, data: T) -> Option<Node<T>> {
-        if data == *root.data() {
-            None
-        } else if data <= *root.data() {
-            if root.left().is_none() {
-                let mut node = Node::from(data);
-                node.set_parent(root.duplicate());
-                root.set_left(node.duplicate());
-                Some(node)
-            } else {
-                self.insert_from(root.left().as_ref().unwrap().duplicate(), data)
-            }
-        } else if root.right().is_none() {
-            let mut node = Node::from(data);
-            node.set_parent(root.duplicate());
-            root.set_right(node.duplicate());
-            Some(node)
-        } else {
-            self.insert_from(root.right().as_ref().unwrap().duplicate(), data)
-        }
-    }
-
-    fn rotate_right(&mut self, mut node: Node<T>) {
-        let mut parent = node.left().expect("get parent node");
-        node.set_left(parent.right());
-        if let Some(ref mut right) = parent.right() {
-            right.set_parent(node.duplicate());
-        }
-        parent.set_right(node.duplicate());
-        parent.set_parent(node.parent());
-        if let Some(ref mut gparent) = parent.parent() {
-            if node.is_left_child() {
-                gparent.set_left(parent.duplicate());
-            } else {
-                gparent.set_right(parent.duplicate());
-            }
-        } else {
-            self.root = Some(parent.duplicate());
-        }
-        node.set_parent(parent);
-    }
-
-    fn rotate_left(&mut self, mut node: Node<T>) {
-        let mut parent = node.right().expect("get parent node");
-        node.set_right(parent.left());
-        if let Some(ref mut left) = parent.left() {
-            left.set_parent(node.duplicate());
-        }
-        parent.set_left(node.duplicate());
-        parent.set_parent(node.parent());
-        if let Some(ref mut gparent) = parent.parent() {
-            if node.is_left_child() {
-                gparent.set_left(parent.duplicate());
-            } else {
-                gparent.set_right(parent.duplicate());
-            }
-        } else {
-            self.root = Some(parent.duplicate());
-        }
-        node.set_parent(parent);
-    }
-
-    fn balance(&mut self, mut node: Node<T>) {
-        if node.parent().is_none() {
-            node.set_colour(Colour::Black);
-        } else if node.parent().as_ref().map(Node::colour) == Some(Colour::Black) {
-            // we're good here
-        } else if node.uncle().as_ref().map(Node::colour) == Some(Colour::Red) {
-            // parent colour <- black
-            node.parent().as_mut().unwrap().set_colour(Colour::Black);
-            // uncle colour <- black
-            node.uncle().as_mut().unwrap().set_colour(Colour::Black);
-            // grand parent colour <- red
-            let mut grand_parent = node.parent().as_ref().and_then(Node::parent).unwrap();
-            grand_parent.set_colour(Colour::Red);
-            // balance from grand parent
-            self.balance(grand_parent.duplicate());
-        } else {
-            let parent = node.parent().as_ref().map(Node::duplicate).unwrap();
-            let mut new_node = node.duplicate();
-
-            // rotate as needed
-            let parent_is_left = parent.is_left_child();
-            let node_is_left = node.is_left_child();
-            if parent_is_left && !node_is_left {
-                self.rotate_left(node.parent().as_ref().unwrap().duplicate());
-                new_node = node.left().as_ref().unwrap().duplicate();
-            } else if !parent_is_left && node_is_left {
-                self.rotate_right(node.parent().as_ref().unwrap().duplicate());
-                new_node = node.right().as_ref().unwrap().duplicate();
-            }
-
-            let mut new_gparent = new_node
-                .parent()
-                .as_ref()
-                .unwrap()
-                .parent()
-                .as_ref()
-                .map(Node::duplicate)
-                .unwrap();
-
-            // swap parent and grand parent colours
-            new_node
-                .parent()
-                .as_ref()
-                .map(Node::duplicate)
-                .unwrap()
-                .set_colour(Colour::Black);
-            new_gparent.set_colour(Colour::Red);
-
-            if new_node.is_left_child() {
-                self.rotate_right(new_gparent.duplicate());
-            } else {
-                self.rotate_left(new_gparent.duplicate());
-            }
-        }
+    /// Returns true if the set contains a value matching the given value.
+    ///
+    /// The value may be any borrowed form of the set's value type, but the ordering
+    /// on the borrowed form must match the ordering on the value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let set: RBTreeSet<String> = ["a", "b"].iter().map(|s| s.to_string()).collect();
+    /// assert!(set.contains("a"));
+    /// assert!(!set.contains("c"));
+    /// ```
+    pub fn contains<Q>(&self, data: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.get_node(data).is_some()
     }
+}
 
+impl<T, A: Augment<T>, C: Compare<T>> RBTreeSet<T, A, C> {
     /// Adds a value to the set.
     ///
     /// If the set did not have a matching value present, the new node is returned.
@@ -232,23 +215,15 @@ impl<T: Ord> RBTreeSet<T> {
     /// assert!(set.insert(2).is_none());
     /// assert_eq!(set.len(), 1);
     /// ```
-    pub fn insert(&mut self, data: T) -> Option<Node<T>> {
-        let node = if let Some(ref root) = self.root {
-            let dup = root.duplicate();
-            self.insert_from(dup, data)
-        } else {
-            self.root = Some(Node::from(data));
-            Some(self.root.as_ref().unwrap().duplicate())
-        };
-        if let Some(ref n) = node {
-            self.balance(n.duplicate());
-            self.length += 1;
-        }
-        node
+    pub fn insert(&mut self, data: T) -> Option<Node<T, A>> {
+        self.raw.insert(data)
     }
 
     /// Removes a matching value from the set. Returns whether a matching value was present in the set.
     ///
+    /// The value may be any borrowed form of the set's value type, but the ordering
+    /// on the borrowed form must match the ordering on the value type.
+    ///
     /// # Examples
     ///
     /// ```
@@ -258,11 +233,20 @@ impl<T: Ord> RBTreeSet<T> {
     /// set.insert(2);
     /// assert_eq!(set.remove(&2), true);
     /// assert_eq!(set.remove(&2), false);
+    ///
+    /// let mut set = RBTreeSet::new();
+    /// set.insert(String::from("a"));
+    /// assert_eq!(set.remove("a"), true);
     /// ```
-    pub fn remove(&mut self, data: &T) -> bool {
+    pub fn remove<Q>(&mut self, data: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
         match self.get_node(data) {
-            Some(ref mut node) => {
-                self.remove_node(node);
+            Some(ref node) => {
+                self.unlink(node);
                 true
             }
             None => false,
@@ -282,12 +266,127 @@ impl<T: Ord> RBTreeSet<T> {
     /// assert!(v.is_empty());
     /// ```
     pub fn clear(&mut self) {
-        self.root = None;
-        self.length = 0;
+        self.raw.clear();
+    }
+
+    /// Retains only the values specified by the predicate, removing the values for
+    /// which `f` returns false. The values are visited in ascending order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let mut set: RBTreeSet<_> = (0..10).collect();
+    /// set.retain(|v| v % 3 == 0);
+    /// assert_eq!(set.values().collect::<Vec<_>>(), vec![0, 3, 6, 9]);
+    /// ```
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.raw.retain(f)
+    }
+
+    /// Removes all the values of the set, returning them in ascending order.
+    ///
+    /// The set is left empty even if the iterator is dropped before being consumed.
+    /// The values are moved out of their nodes, the nodes on which handles are still
+    /// held being returned instead, see [Drain::values] to get their values cloned.
+    ///
+    /// [Drain::values]: struct.Drain.html#method.values
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let mut set: RBTreeSet<_> = (0..5).collect();
+    /// let held = set.get_node(&3).unwrap();
+    /// let drained: Vec<_> = set.drain().collect();
+    /// assert_eq!(drained[..3], [Ok(0), Ok(1), Ok(2)]);
+    /// assert_eq!(drained[3], Err(held));
+    /// assert!(set.is_empty());
+    /// ```
+    pub fn drain(&mut self) -> Drain<'_, T, A, C> {
+        Drain {
+            next: self.first(),
+            remaining: self.raw.length,
+            set: self,
+        }
+    }
+
+    /// Removes the values of the given range from the set, returning them in ascending
+    /// order.
+    ///
+    /// The values of the range are removed even if the iterator is dropped before
+    /// being consumed.
+    /// The values are moved out of their nodes, see [drain](#method.drain).
+    ///
+    /// # Panics
+    ///
+    /// Panics if range `start > end`.
+    /// Panics if range `start == end` and both bounds are `Excluded`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let mut set: RBTreeSet<_> = (0..10).collect();
+    /// assert_eq!(set.drain_range(3..6).values().collect::<Vec<_>>(), vec![3, 4, 5]);
+    /// set.drain_range(8..);
+    /// assert_eq!(set.values().collect::<Vec<_>>(), vec![0, 1, 2, 6, 7]);
+    /// ```
+    pub fn drain_range<R>(&mut self, range: R) -> Drain<'_, T, A, C>
+    where
+        R: RangeBounds<T>,
+    {
+        let bounds = self.raw.range_bounds(&range);
+        Drain {
+            remaining: bounds
+                .as_ref()
+                .map_or(0, |(first, last)| self.raw.count_between(first, last)),
+            next: bounds.map(|(first, _)| first),
+            set: self,
+        }
+    }
+
+    /// Creates an iterator removing and returning the values for which the predicate
+    /// returns true, in ascending order.
+    ///
+    /// The values not visited when the iterator is dropped are kept in the set.
+    /// The values are moved out of their nodes, the nodes on which handles are still
+    /// held being returned instead, see [ExtractIf::values] to get their values cloned.
+    ///
+    /// [ExtractIf::values]: struct.ExtractIf.html#method.values
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let mut set: RBTreeSet<_> = (0..10).collect();
+    /// let evens: Vec<_> = set.extract_if(|v| v % 2 == 0).values().collect();
+    /// assert_eq!(evens, vec![0, 2, 4, 6, 8]);
+    /// assert_eq!(set.values().collect::<Vec<_>>(), vec![1, 3, 5, 7, 9]);
+    /// ```
+    pub fn extract_if<F>(&mut self, pred: F) -> ExtractIf<'_, T, A, C, F>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        ExtractIf {
+            next: self.first(),
+            set: self,
+            pred,
+        }
     }
 
     /// Returns the node in the set, if any, that is matching the given value.
     ///
+    /// The value may be any borrowed form of the set's value type, but the ordering
+    /// on the borrowed form must match the ordering on the value type.
+    ///
     /// If the value is cheap to clone [get] should be more convenient to use.
     ///
     /// [get]: #method.get
@@ -301,95 +400,301 @@ impl<T: Ord> RBTreeSet<T> {
     /// assert_eq!(*set.get_node(&2).unwrap().data(), 2);
     /// assert_eq!(set.get_node(&4), None);
     /// ```
-    pub fn get_node(&self, data: &T) -> Option<Node<T>> {
-        let mut tmp = self.root.as_ref().map(Node::duplicate);
-        while let Some(ref n) = tmp {
-            if *data == *n.data() {
-                return Some(n.duplicate());
-            } else if *data < *n.data() {
-                tmp = n.left();
-            } else {
-                tmp = n.right();
-            }
-        }
-        None
+    pub fn get_node<Q>(&self, data: &Q) -> Option<Node<T, A>>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.raw.get(data)
     }
 
-    fn successor(node: Node<T>) -> Option<Node<T>> {
-        if let Some(right) = node.right() {
-            let mut tmp = right;
-            while let Some(n) = tmp.left() {
-                tmp = n;
-            }
-            Some(tmp)
-        } else if node.is_left_child() {
-            node.parent()
-        } else {
-            let mut tmp = node.duplicate();
-            while tmp.parent().as_ref().and_then(Node::right).as_ref() == Some(&tmp) {
-                tmp = tmp.parent().as_ref().unwrap().duplicate();
-            }
-            tmp.parent()
-        }
+    /// Returns the first node of the set matching the given lower bound, if any.
+    ///
+    /// The value may be any borrowed form of the set's value type, but the ordering
+    /// on the borrowed form must match the ordering on the value type.
+    ///
+    /// `Included(x)` behaves like [ceiling], `Excluded(x)` like [successor_of] and
+    /// `Unbounded` like [first].
+    ///
+    /// [ceiling]: #method.ceiling
+    /// [successor_of]: #method.successor_of
+    /// [first]: #method.first
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::ops::Bound;
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let set: RBTreeSet<_> = [1, 3, 5].iter().cloned().collect();
+    /// assert_eq!(*set.lower_bound(Bound::Included(&3)).unwrap().data(), 3);
+    /// assert_eq!(*set.lower_bound(Bound::Excluded(&3)).unwrap().data(), 5);
+    /// assert_eq!(*set.lower_bound(Bound::<&i32>::Unbounded).unwrap().data(), 1);
+    /// assert_eq!(set.lower_bound(Bound::Excluded(&5)), None);
+    /// ```
+    pub fn lower_bound<Q>(&self, bound: Bound<&Q>) -> Option<Node<T, A>>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.raw.lower_bound(bound)
     }
 
-    fn double_black_fixup(&mut self, node: &Node<T>) {
-        if self.root.as_ref() == Some(node) {
-            return;
-        }
+    /// Returns the last node of the set matching the given upper bound, if any.
+    ///
+    /// The value may be any borrowed form of the set's value type, but the ordering
+    /// on the borrowed form must match the ordering on the value type.
+    ///
+    /// `Included(x)` behaves like [floor], `Excluded(x)` like [predecessor_of] and
+    /// `Unbounded` like [last].
+    ///
+    /// [floor]: #method.floor
+    /// [predecessor_of]: #method.predecessor_of
+    /// [last]: #method.last
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::ops::Bound;
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let set: RBTreeSet<_> = [1, 3, 5].iter().cloned().collect();
+    /// assert_eq!(*set.upper_bound(Bound::Included(&3)).unwrap().data(), 3);
+    /// assert_eq!(*set.upper_bound(Bound::Excluded(&3)).unwrap().data(), 1);
+    /// assert_eq!(*set.upper_bound(Bound::<&i32>::Unbounded).unwrap().data(), 5);
+    /// assert_eq!(set.upper_bound(Bound::Excluded(&1)), None);
+    /// ```
+    pub fn upper_bound<Q>(&self, bound: Bound<&Q>) -> Option<Node<T, A>>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.raw.upper_bound(bound)
+    }
 
-        let mut parent = node.parent().unwrap();
-        if let Some(ref mut sibling) = node.sibling() {
-            if sibling.colour() == Colour::Red {
-                parent.set_colour(Colour::Red);
-                sibling.set_colour(Colour::Black);
-                if sibling.is_left_child() {
-                    self.rotate_right(parent);
-                } else {
-                    self.rotate_left(parent);
-                }
-                self.double_black_fixup(node);
-            } else if sibling.left().as_ref().map(Node::colour) == Some(Colour::Red)
-                || sibling.right().as_ref().map(Node::colour) == Some(Colour::Red)
-            {
-                if sibling.left().as_ref().map(Node::colour) == Some(Colour::Red) {
-                    let mut left = sibling.left().unwrap();
-                    if sibling.is_left_child() {
-                        left.set_colour(sibling.colour());
-                        sibling.set_colour(parent.colour());
-                        self.rotate_right(parent.duplicate());
-                    } else {
-                        left.set_colour(parent.colour());
-                        self.rotate_right(sibling.duplicate());
-                        self.rotate_left(parent.duplicate());
-                    }
-                } else {
-                    let mut right = sibling.right().unwrap();
-                    if sibling.is_left_child() {
-                        right.set_colour(parent.colour());
-                        self.rotate_left(sibling.duplicate());
-                        self.rotate_right(parent.duplicate());
-                    } else {
-                        right.set_colour(sibling.colour());
-                        sibling.set_colour(parent.colour());
-                        self.rotate_left(parent.duplicate());
-                    }
-                }
-                parent.set_colour(Colour::Black);
-            } else {
-                sibling.set_colour(Colour::Red);
-                if parent.colour() == Colour::Black {
-                    self.double_black_fixup(&parent);
-                } else {
-                    parent.set_colour(Colour::Black);
-                }
-            }
-        } else {
-            self.double_black_fixup(&parent)
+    pub(crate) fn root(&self) -> Option<Node<T, A>> {
+        self.raw.root.as_ref().map(Node::duplicate)
+    }
+
+    pub(crate) fn comparator(&self) -> &C {
+        &self.raw.comparator
+    }
+
+    /// Makes a new empty set ordered by a clone of the comparator of `self`.
+    pub(crate) fn new_like(&self) -> RBTreeSet<T, A, C>
+    where
+        C: Clone,
+    {
+        RBTreeSet {
+            raw: RawTree::new(Nodes::new(), self.raw.comparator.clone()),
         }
     }
 
-    /// Removes a node from the set. This method expects a matching node to be present in the set.
+    /// Returns the node for which `f` returns `Ordering::Equal`, `f` giving the
+    /// ordering of the searched value compared to the data of the visited node.
+    pub(crate) fn find_node<F>(&self, f: F) -> Option<Node<T, A>>
+    where
+        F: Fn(&T) -> Ordering,
+    {
+        self.raw.find(f)
+    }
+
+    /// Returns the node holding the smallest value greater than or equal to the given
+    /// value, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let set: RBTreeSet<_> = [1, 3, 5].iter().cloned().collect();
+    /// assert_eq!(*set.ceiling(&2).unwrap().data(), 3);
+    /// assert_eq!(*set.ceiling(&3).unwrap().data(), 3);
+    /// assert_eq!(set.ceiling(&6), None);
+    ///
+    /// let words: RBTreeSet<String> = ["b", "d"].iter().map(|s| s.to_string()).collect();
+    /// assert_eq!(*words.ceiling("c").unwrap().data(), "d");
+    /// ```
+    pub fn ceiling<Q>(&self, data: &Q) -> Option<Node<T, A>>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.lower_bound(Bound::Included(data))
+    }
+
+    /// Returns the node holding the greatest value less than or equal to the given
+    /// value, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let set: RBTreeSet<_> = [1, 3, 5].iter().cloned().collect();
+    /// assert_eq!(*set.floor(&4).unwrap().data(), 3);
+    /// assert_eq!(*set.floor(&3).unwrap().data(), 3);
+    /// assert_eq!(set.floor(&0), None);
+    /// ```
+    pub fn floor<Q>(&self, data: &Q) -> Option<Node<T, A>>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.upper_bound(Bound::Included(data))
+    }
+
+    /// Returns the node holding the smallest value strictly greater than the given
+    /// value, if any. The value does not need to be present in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let set: RBTreeSet<_> = [1, 3, 5].iter().cloned().collect();
+    /// assert_eq!(*set.successor_of(&3).unwrap().data(), 5);
+    /// assert_eq!(*set.successor_of(&4).unwrap().data(), 5);
+    /// assert_eq!(set.successor_of(&5), None);
+    /// ```
+    pub fn successor_of<Q>(&self, data: &Q) -> Option<Node<T, A>>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.lower_bound(Bound::Excluded(data))
+    }
+
+    /// Returns the node holding the greatest value strictly less than the given
+    /// value, if any. The value does not need to be present in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let set: RBTreeSet<_> = [1, 3, 5].iter().cloned().collect();
+    /// assert_eq!(*set.predecessor_of(&3).unwrap().data(), 1);
+    /// assert_eq!(*set.predecessor_of(&2).unwrap().data(), 1);
+    /// assert_eq!(set.predecessor_of(&1), None);
+    /// ```
+    pub fn predecessor_of<Q>(&self, data: &Q) -> Option<Node<T, A>>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.upper_bound(Bound::Excluded(data))
+    }
+
+    /// Returns the node at the given position in the set, in ascending order, if any.
+    ///
+    /// This operation is `O(log n)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let set: RBTreeSet<_> = [10, 30, 20].iter().cloned().collect();
+    /// assert_eq!(*set.nth(0).unwrap().data(), 10);
+    /// assert_eq!(*set.nth(2).unwrap().data(), 30);
+    /// assert_eq!(set.nth(3), None);
+    /// ```
+    pub fn nth(&self, index: usize) -> Option<Node<T, A>> {
+        self.raw.nth(index)
+    }
+
+    /// Returns the number of values in the set that are strictly less than the given
+    /// value. The value does not need to be present in the set.
+    ///
+    /// The value may be any borrowed form of the set's value type, but the ordering
+    /// on the borrowed form must match the ordering on the value type.
+    ///
+    /// This operation is `O(log n)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let set: RBTreeSet<_> = [10, 30, 20].iter().cloned().collect();
+    /// assert_eq!(set.rank(&5), 0);
+    /// assert_eq!(set.rank(&20), 1);
+    /// assert_eq!(set.rank(&25), 2);
+    /// assert_eq!(set.rank(&40), 3);
+    ///
+    /// let names: RBTreeSet<String> = ["ada", "bob"].iter().map(|s| s.to_string()).collect();
+    /// assert_eq!(names.rank("bob"), 1);
+    /// ```
+    pub fn rank<Q>(&self, data: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.raw.rank(data)
+    }
+
+    /// Returns the summary of all the values of the set, if not empty.
+    ///
+    /// See [Augment] for more informations.
+    ///
+    /// [Augment]: trait.Augment.html
+    pub fn summary(&self) -> Option<A> {
+        self.raw.summary()
+    }
+
+    /// Returns the combined summary of the values within the given range, if any.
+    ///
+    /// This operation is `O(log n)`, see [Augment] for more informations.
+    ///
+    /// [Augment]: trait.Augment.html
+    ///
+    /// # Panics
+    ///
+    /// Panics if range `start > end`.
+    /// Panics if range `start == end` and both bounds are `Excluded`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::{Augment, RBTreeSet};
+    ///
+    /// #[derive(Debug, Clone, PartialEq)]
+    /// struct Max(u32);
+    ///
+    /// impl Augment<u32> for Max {
+    ///     fn summarize(data: &u32) -> Max {
+    ///         Max(*data)
+    ///     }
+    ///
+    ///     fn combine(&self, other: &Max) -> Max {
+    ///         Max(self.0.max(other.0))
+    ///     }
+    /// }
+    ///
+    /// let set: RBTreeSet<u32, Max> = [4, 8, 15, 16, 23, 42].iter().cloned().collect();
+    /// assert_eq!(set.fold_range(..20), Some(Max(16)));
+    /// assert_eq!(set.fold_range(50..), None);
+    /// ```
+    pub fn fold_range<R>(&self, range: R) -> Option<A>
+    where
+        R: RangeBounds<T>,
+    {
+        self.raw.fold_range(range)
+    }
+
+    /// Removes a node from the set.
+    ///
+    /// Nothing is done if the node was already removed or belongs to another set, the
+    /// node is checked to be in this set in `O(log n)`.
     ///
     /// # Examples
     ///
@@ -402,65 +707,29 @@ impl<T: Ord> RBTreeSet<T> {
     /// set.remove_node(&mut node);
     /// assert!(set.is_empty());
     /// ```
-    pub fn remove_node(&mut self, node: &mut Node<T>) {
-        let new_node = if node.left().is_some() && node.right().is_some() {
-            Self::successor(node.duplicate())
-        } else if node.left().is_some() {
-            node.left()
-        } else {
-            node.right()
-        };
-        let double_black = node.colour() == Colour::Black
-            && new_node.as_ref().map(Node::colour) != Some(Colour::Red);
-
-        if new_node.is_none() {
-            if self.root.as_ref() == Some(node) {
-                self.root = None;
-            } else {
-                if double_black {
-                    self.double_black_fixup(&node)
-                } else if let Some(ref mut sibling) = node.sibling() {
-                    sibling.set_colour(Colour::Red);
-                }
-
-                if let Some(ref mut parent) = node.parent() {
-                    if node.is_left_child() {
-                        parent.set_left(None);
-                    } else {
-                        parent.set_right(None);
-                    }
-                }
-            }
-            self.length -= 1;
-            return;
+    pub fn remove_node(&mut self, node: &mut Node<T, A>) {
+        if self.holds(node) {
+            self.unlink(node);
         }
+    }
 
-        let mut substitute = new_node.unwrap();
-
-        if node.left().is_none() || node.right().is_none() {
-            if self.root.as_ref() == Some(node) {
-                node.swap_data(&mut substitute);
-                node.set_left(None);
-                node.set_right(None);
-            } else if let Some(ref mut parent) = node.parent() {
-                if node.is_left_child() {
-                    parent.set_left(substitute.duplicate());
-                } else {
-                    parent.set_right(substitute.duplicate());
-                }
-                substitute.set_parent(node.parent());
-                if double_black {
-                    self.double_black_fixup(&substitute);
-                } else {
-                    substitute.set_colour(Colour::Black)
-                }
-            }
-            self.length -= 1;
-            return;
+    /// Returns true if the node is attached to the tree of this set.
+    fn holds(&self, node: &Node<T, A>) -> bool {
+        if !node.is_attached() {
+            return false;
         }
+        let mut top = node.duplicate();
+        while let Some(parent) = top.parent() {
+            top = parent;
+        }
+        self.raw.root.as_ref() == Some(&top)
+    }
 
-        substitute.swap_data(node);
-        self.remove_node(&mut substitute);
+    /// Removes a node from the tree, the node being detached along with its data.
+    ///
+    /// The other nodes of the tree are relinked, keeping their data.
+    pub(crate) fn unlink(&mut self, node: &Node<T, A>) {
+        self.raw.remove(node.duplicate());
     }
 
     /// Returns the first node of the set if not empty.
@@ -475,12 +744,8 @@ impl<T: Ord> RBTreeSet<T> {
     /// set.clear();
     /// assert_eq!(set.first(), None);
     /// ```
-    pub fn first(&self) -> Option<Node<T>> {
-        let mut n = self.root.as_ref().map(Node::duplicate)?;
-        while let Some(left) = n.left() {
-            n = left;
-        }
-        Some(n)
+    pub fn first(&self) -> Option<Node<T, A>> {
+        self.raw.first.as_ref().map(Node::duplicate)
     }
 
     /// Returns the last node of the set if not empty.
@@ -495,12 +760,95 @@ impl<T: Ord> RBTreeSet<T> {
     /// set.clear();
     /// assert_eq!(set.last(), None);
     /// ```
-    pub fn last(&self) -> Option<Node<T>> {
-        let mut n = self.root.as_ref().map(Node::duplicate)?;
-        while let Some(right) = n.right() {
-            n = right;
-        }
-        Some(n)
+    pub fn last(&self) -> Option<Node<T, A>> {
+        self.raw.last.as_ref().map(Node::duplicate)
+    }
+
+    /// Removes the smallest value from the set and returns it, if not empty.
+    ///
+    /// This operation is `O(log n)`, the first node being cached.
+    /// The value is moved out of its node. When handles on the node are still held, the
+    /// detached node is returned instead and keeps the value, see [Node::try_into_data].
+    ///
+    /// [Node::try_into_data]: struct.Node.html#method.try_into_data
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let mut set: RBTreeSet<_> = [3, 1, 2].iter().cloned().collect();
+    /// assert_eq!(set.pop_first(), Some(Ok(1)));
+    /// assert_eq!(set.pop_first(), Some(Ok(2)));
+    ///
+    /// let last = set.first().unwrap();
+    /// let node = set.pop_first().unwrap().unwrap_err();
+    /// assert_eq!(node, last);
+    /// assert_eq!(set.pop_first(), None);
+    /// ```
+    pub fn pop_first(&mut self) -> Option<Result<T, Node<T, A>>> {
+        let node = self.first()?;
+        Some(self.raw.remove(node).try_into_data())
+    }
+
+    /// Removes the greatest value from the set and returns it, if not empty.
+    ///
+    /// This operation is `O(log n)`, the last node being cached.
+    /// The value is moved out of its node. When handles on the node are still held, the
+    /// detached node is returned instead and keeps the value, see [Node::try_into_data].
+    ///
+    /// [Node::try_into_data]: struct.Node.html#method.try_into_data
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let mut set: RBTreeSet<_> = [3, 1, 2].iter().cloned().collect();
+    /// assert_eq!(set.pop_last(), Some(Ok(3)));
+    /// assert_eq!(set.pop_last(), Some(Ok(2)));
+    /// assert_eq!(set.len(), 1);
+    /// ```
+    pub fn pop_last(&mut self) -> Option<Result<T, Node<T, A>>> {
+        let node = self.last()?;
+        Some(self.raw.remove(node).try_into_data())
+    }
+
+    /// Returns a reference to the smallest value of the set, if not empty.
+    ///
+    /// The value stays borrowed until the returned guard is dropped, mutating it
+    /// meanwhile with [Node::apply] panics.
+    ///
+    /// [Node::apply]: struct.Node.html#method.apply
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let set: RBTreeSet<_> = [3, 1, 2].iter().cloned().collect();
+    /// assert_eq!(set.peek_first().as_deref(), Some(&1));
+    /// assert_eq!(set.len(), 3);
+    /// ```
+    pub fn peek_first(&self) -> Option<impl Deref<Target = T> + '_> {
+        self.raw.first.as_ref().map(Node::data)
+    }
+
+    /// Returns a reference to the greatest value of the set, if not empty.
+    ///
+    /// The value stays borrowed until the returned guard is dropped, see
+    /// [peek_first](#method.peek_first).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let set: RBTreeSet<_> = [3, 1, 2].iter().cloned().collect();
+    /// assert_eq!(set.peek_last().as_deref(), Some(&3));
+    /// ```
+    pub fn peek_last(&self) -> Option<impl Deref<Target = T> + '_> {
+        self.raw.last.as_ref().map(Node::data)
     }
 
     /// Returns the number of elements in the set.
@@ -516,7 +864,7 @@ impl<T: Ord> RBTreeSet<T> {
     /// assert_eq!(v.len(), 1);
     /// ```
     pub fn len(&self) -> usize {
-        self.length
+        self.raw.length
     }
 
     /// Returns true if the set contains no elements.
@@ -532,7 +880,7 @@ impl<T: Ord> RBTreeSet<T> {
     /// assert!(!v.is_empty());
     /// ```
     pub fn is_empty(&self) -> bool {
-        self.length == 0
+        self.raw.length == 0
     }
 
     /// Gets an iterator that visits the nodes in the RBTreeSet in ascending order.
@@ -550,9 +898,10 @@ impl<T: Ord> RBTreeSet<T> {
     /// assert_eq!(*set_iter.next().unwrap().data(), 3);
     /// assert_eq!(set_iter.next(), None);
     /// ```
-    pub fn iter(&self) -> Iter<T> {
+    pub fn iter(&self) -> Iter<T, A> {
         Iter {
-            cursor: self.first(),
+            front: self.first(),
+            back: self.last(),
         }
     }
 
@@ -572,9 +921,69 @@ impl<T: Ord> RBTreeSet<T> {
     /// assert_eq!(*set_iter.next().unwrap().data(), 3);
     /// assert_eq!(set_iter.next(), None);
     /// ```
-    pub fn iter_from(&self, node: &Node<T>) -> Iter<T> {
+    pub fn iter_from(&self, node: &Node<T, A>) -> Iter<T, A> {
+        Iter {
+            front: Some(node.duplicate()),
+            back: self.last(),
+        }
+    }
+
+    /// Gets an iterator that visits the nodes in the RBTreeSet in descending order,
+    /// starting at the given node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let set: RBTreeSet<_> = [3, 1, 2].iter().cloned().collect();
+    /// let node = set.get_node(&2).unwrap();
+    /// let mut set_iter = set.iter_rev_from(&node);
+    ///
+    /// assert_eq!(*set_iter.next().unwrap().data(), 2);
+    /// assert_eq!(*set_iter.next().unwrap().data(), 1);
+    /// assert_eq!(set_iter.next(), None);
+    /// ```
+    pub fn iter_rev_from(&self, node: &Node<T, A>) -> Rev<Iter<T, A>> {
+        Iter {
+            front: self.first(),
+            back: Some(node.duplicate()),
+        }
+        .rev()
+    }
+
+    /// Gets an iterator that visits the nodes in the RBTreeSet in ascending order,
+    /// restricted to the values within the given range.
+    ///
+    /// The bounds do not need to be present in the set, the iteration starts at the
+    /// first value matching the start bound and stops after the last value matching
+    /// the end bound.
+    ///
+    /// # Panics
+    ///
+    /// Panics if range `start > end`.
+    /// Panics if range `start == end` and both bounds are `Excluded`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let set: RBTreeSet<_> = [1, 3, 5, 7].iter().cloned().collect();
+    /// let mut set_iter = set.range(2..7);
+    ///
+    /// assert_eq!(*set_iter.next().unwrap().data(), 3);
+    /// assert_eq!(*set_iter.next().unwrap().data(), 5);
+    /// assert_eq!(set_iter.next(), None);
+    /// ```
+    pub fn range<R>(&self, range: R) -> Iter<T, A>
+    where
+        R: RangeBounds<T>,
+    {
+        let bounds = self.raw.range_bounds(&range);
         Iter {
-            cursor: Some(node.duplicate()),
+            front: bounds.as_ref().map(|(first, _)| first.duplicate()),
+            back: bounds.map(|(_, last)| last),
         }
     }
 
@@ -599,7 +1008,7 @@ impl<T: Ord> RBTreeSet<T> {
     /// assert_eq!(set_values.next(), Some(3));
     /// assert_eq!(set_values.next(), None);
     /// ```
-    pub fn values(&self) -> IterValues<T>
+    pub fn values(&self) -> IterValues<T, A>
     where
         T: Clone,
     {
@@ -628,7 +1037,7 @@ impl<T: Ord> RBTreeSet<T> {
     /// assert_eq!(set_values.next(), Some(3));
     /// assert_eq!(set_values.next(), None);
     /// ```
-    pub fn values_from(&self, node: &Node<T>) -> IterValues<T>
+    pub fn values_from(&self, node: &Node<T, A>) -> IterValues<T, A>
     where
         T: Clone,
     {
@@ -637,6 +1046,76 @@ impl<T: Ord> RBTreeSet<T> {
         }
     }
 
+    /// Gets an iterator that visit the nodes values in the RBTreeSet in descending order,
+    /// starting at the given node.
+    ///
+    /// This iterator clones the values. Use [iter_rev_from] in pair with [Node::data] if
+    /// you want to avoid the cloning.
+    ///
+    /// [iter_rev_from]: #method.iter_rev_from
+    /// [Node::data]: struct.Node.html#method.data
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let set: RBTreeSet<_> = [3, 1, 2].iter().cloned().collect();
+    /// let node = set.get_node(&2).unwrap();
+    /// let mut set_values = set.values_rev_from(&node);
+    ///
+    /// assert_eq!(set_values.next(), Some(2));
+    /// assert_eq!(set_values.next(), Some(1));
+    /// assert_eq!(set_values.next(), None);
+    /// ```
+    pub fn values_rev_from(&self, node: &Node<T, A>) -> Rev<IterValues<T, A>>
+    where
+        T: Clone,
+    {
+        IterValues {
+            inner: Iter {
+                front: self.first(),
+                back: Some(node.duplicate()),
+            },
+        }
+        .rev()
+    }
+
+    /// Gets an iterator that visit the nodes values in the RBTreeSet in ascending order,
+    /// restricted to the values within the given range.
+    ///
+    /// This iterator clones the values. Use [range] in pair with [Node::data] if you want
+    /// to avoid the cloning.
+    ///
+    /// [range]: #method.range
+    /// [Node::data]: struct.Node.html#method.data
+    ///
+    /// # Panics
+    ///
+    /// Panics if range `start > end`.
+    /// Panics if range `start == end` and both bounds are `Excluded`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let set: RBTreeSet<_> = [1, 3, 5, 7].iter().cloned().collect();
+    ///
+    /// assert_eq!(set.values_range(2..7).collect::<Vec<_>>(), vec![3, 5]);
+    /// assert_eq!(set.values_range(..=3).collect::<Vec<_>>(), vec![1, 3]);
+    /// assert_eq!(set.values_range(8..).next(), None);
+    /// ```
+    pub fn values_range<R>(&self, range: R) -> IterValues<T, A>
+    where
+        R: RangeBounds<T>,
+        T: Clone,
+    {
+        IterValues {
+            inner: self.range(range),
+        }
+    }
+
     /// Optimize the set by merging nodes where applicable while keeping the ordering.
     ///
     /// Two nodes can be merged together when [consecutive].
@@ -699,35 +1178,325 @@ impl<T: Ord> RBTreeSet<T> {
     /// ```
     pub fn repack(&mut self)
     where
-        T: Clone + Consecutive,
+        T: Consecutive,
+    {
+        self.repack_counted();
+    }
+
+    /// Optimize the set by merging nodes where applicable, like [repack]. Returns the
+    /// number of merged nodes, that is the decrease of the length of the set.
+    ///
+    /// [repack]: #method.repack
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::{RBTreeSet, Span};
+    ///
+    /// let mut set = RBTreeSet::new();
+    /// set.insert(Span(1..3));
+    /// set.insert(Span(3..5));
+    /// set.insert(Span(5..8));
+    /// set.insert(Span(10..12));
+    ///
+    /// assert_eq!(set.repack_counted(), 2);
+    /// assert_eq!(set.repack_counted(), 0);
+    /// assert_eq!(set.len(), 2);
+    /// ```
+    pub fn repack_counted(&mut self) -> usize
+    where
+        T: Consecutive,
+    {
+        self.raw.repack_counted()
+    }
+
+    /// Merges the [consecutive] nodes within the given range, leaving the rest of the
+    /// set untouched. Returns the number of merged nodes.
+    ///
+    /// Merging stays local, a value of the range is not merged with a value outside
+    /// of it. This operation is `O(k log n)` for `k` values in the range.
+    ///
+    /// [consecutive]: trait.Consecutive.html
+    ///
+    /// # Panics
+    ///
+    /// Panics if range `start > end`.
+    /// Panics if range `start == end` and both bounds are `Excluded`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::{Containment, RBTreeSet, Span};
+    ///
+    /// let mut set = RBTreeSet::with_comparator(Containment);
+    /// for i in 0..10 {
+    ///     set.insert(Span(i..i + 1));
+    /// }
+    ///
+    /// assert_eq!(set.repack_range(Span(2..3)..=Span(5..6)), 3);
+    /// assert_eq!(set.len(), 7);
+    /// assert_eq!(set.get(&Span(4..5)).unwrap().0, 2..6);
+    /// ```
+    pub fn repack_range<R>(&mut self, range: R) -> usize
+    where
+        R: RangeBounds<T>,
+        T: Consecutive,
+    {
+        self.raw.repack_range(range)
+    }
+
+    /// Merges the given node with its [consecutive] predecessors and successors.
+    /// Returns the number of merged nodes.
+    ///
+    /// Only the run of consecutive values containing the node is visited, making it
+    /// suitable to repack right after a localized edit. The given node may be detached
+    /// from the set once merged, use [get_node] to find the merged value.
+    ///
+    /// [consecutive]: trait.Consecutive.html
+    /// [get_node]: #method.get_node
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::{RBTreeSet, Span};
+    ///
+    /// let mut set = RBTreeSet::new();
+    /// set.insert(Span(1..3));
+    /// set.insert(Span(5..8));
+    /// set.insert(Span(10..12));
+    /// set.insert(Span(12..14));
+    ///
+    /// let node = set.insert(Span(3..5)).unwrap();
+    /// assert_eq!(set.repack_around(&node), 2);
+    /// assert_eq!(
+    ///     set.values().map(|s| s.0).collect::<Vec<_>>(),
+    ///     vec![1..8, 10..12, 12..14]
+    /// );
+    /// ```
+    pub fn repack_around(&mut self, node: &Node<T, A>) -> usize
+    where
+        T: Consecutive,
+    {
+        self.raw.repack_around(node.duplicate())
+    }
+
+    /// Adds a value to the set, merging it with its [consecutive] and overlapping
+    /// neighbours.
+    ///
+    /// Returns the node holding the merged value, or `None` if the value was already
+    /// covered by a value of the set or is empty, an empty value being one that does
+    /// not overlap itself, see [Splittable]. The values of the set are expected not to
+    /// overlap each other, overlaps being detected with [Splittable]. If the set was
+    /// packed, see [repack], it stays packed.
+    ///
+    /// [consecutive]: trait.Consecutive.html
+    /// [Splittable]: trait.Splittable.html
+    /// [repack]: #method.repack
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::{RBTreeSet, Span};
+    ///
+    /// let mut set = RBTreeSet::new();
+    /// set.insert_coalescing(Span(1..3));
+    /// set.insert_coalescing(Span(5..8));
+    /// set.insert_coalescing(Span(10..12));
+    /// assert_eq!(set.len(), 3);
+    ///
+    /// let node = set.insert_coalescing(Span(8..10)).unwrap();
+    /// assert_eq!(*node.data(), Span(5..12));
+    /// assert_eq!(
+    ///     set.values().collect::<Vec<_>>(),
+    ///     vec![Span(1..3), Span(5..12)]
+    /// );
+    ///
+    /// let node = set.insert_coalescing(Span(2..6)).unwrap();
+    /// assert_eq!(node.data().0, 1..12);
+    /// assert!(set.insert_coalescing(Span(4..7)).is_none());
+    /// assert!(set.insert_coalescing(Span(14..14)).is_none());
+    /// ```
+    pub fn insert_coalescing(&mut self, data: T) -> Option<Node<T, A>>
+    where
+        T: Consecutive + Splittable,
+    {
+        self.raw.insert_coalescing(data)
+    }
+
+    /// Removes the parts of the values of the set covered by the given range. Returns
+    /// whether a value was overlapping the range.
+    ///
+    /// Values straddling a bound of the range are [split], keeping their parts outside
+    /// of the range. The values of the set are expected not to overlap each other,
+    /// which is the case of repacked sets, see [repack].
+    ///
+    /// [split]: trait.Splittable.html
+    /// [repack]: #method.repack
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::{RBTreeSet, Span};
+    ///
+    /// let mut set = RBTreeSet::new();
+    /// set.insert(Span(1..5));
+    /// set.insert(Span(7..9));
+    /// set.insert(Span(11..20));
+    ///
+    /// assert_eq!(set.remove_range(&Span(3..13)), true);
+    /// assert_eq!(
+    ///     set.values().map(|s| s.0).collect::<Vec<_>>(),
+    ///     vec![1..3, 13..20]
+    /// );
+    /// assert_eq!(set.remove_range(&Span(5..10)), false);
+    /// ```
+    pub fn remove_range(&mut self, range: &T) -> bool
+    where
+        T: Splittable,
+    {
+        self.raw.remove_range(range)
+    }
+
+    /// Makes a new `RBTreeSet` from the values of `left`, the pivot and the values of
+    /// `right`, keeping the comparator of `left`.
+    ///
+    /// This operation is `O(log n)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the values of `left` are not all less than the pivot or the values of
+    /// `right` not all greater than the pivot.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let left: RBTreeSet<_> = (0..10).collect();
+    /// let right: RBTreeSet<_> = (11..100).collect();
+    /// let set = RBTreeSet::join(left, 10, right);
+    /// assert_eq!(set.len(), 100);
+    /// assert_eq!(*set.nth(10).unwrap().data(), 10);
+    /// ```
+    pub fn join(mut left: Self, pivot: T, mut right: Self) -> Self {
+        if !left.raw.ordered_around(&pivot, &right.raw) {
+            panic!("values are not ordered around the pivot in RBTreeSet::join");
+        }
+        let (subtree, upper) = (left.raw.take_root(), right.raw.take_root());
+        left.raw.join(subtree, Node::from(pivot), upper);
+        left
+    }
+
+    /// Splits the set in two at the given value. Returns everything after the given
+    /// value, including the value, the set keeping the values before it.
+    ///
+    /// This operation is `O(log n)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let mut a: RBTreeSet<_> = (0..10).collect();
+    /// let b = a.split_off(&4);
+    /// assert_eq!(a.values().collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+    /// assert_eq!(b.values().collect::<Vec<_>>(), vec![4, 5, 6, 7, 8, 9]);
+    /// ```
+    pub fn split_off(&mut self, data: &T) -> Self
+    where
+        C: Clone,
+    {
+        let mut other = self.new_like();
+        for (pivot, subtree) in self.raw.split_off(data) {
+            let left = other.raw.root.take();
+            other.raw.join_root(left, pivot, subtree);
+        }
+        other.raw.reset_bounds();
+        other
+    }
+
+    /// Splits the set in two at the given value, the first set holding the values
+    /// before it and the second one the values after it, including the value.
+    ///
+    /// See [split_off](#method.split_off).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let set: RBTreeSet<_> = (0..10).collect();
+    /// let (a, b) = set.split_at(&4);
+    /// assert_eq!(a.len(), 4);
+    /// assert_eq!(*b.first().unwrap().data(), 4);
+    /// ```
+    pub fn split_at(mut self, data: &T) -> (Self, Self)
+    where
+        C: Clone,
     {
+        let other = self.split_off(data);
+        (self, other)
+    }
+
+    /// Moves all the values of `other` into the set, leaving `other` empty.
+    ///
+    /// When the values of one set are all less than the values of the other one, the
+    /// sets are joined in `O(log n)`. Otherwise the values are inserted one by one,
+    /// the ones matching a value of the set being dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let mut a: RBTreeSet<_> = (0..5).collect();
+    /// let mut b: RBTreeSet<_> = (5..10).collect();
+    /// a.append(&mut b);
+    /// assert_eq!(a.len(), 10);
+    /// assert!(b.is_empty());
+    /// ```
+    pub fn append(&mut self, other: &mut Self) {
+        if other.is_empty() {
+            return;
+        }
         if self.is_empty() {
+            std::mem::swap(&mut self.raw, &mut other.raw);
+            std::mem::swap(&mut self.raw.comparator, &mut other.raw.comparator);
             return;
         }
-        let mut nodes = self.iter();
-        let mut prev = nodes.next().unwrap();
-        while let Some(curr) = nodes.next() {
-            let mut acc = vec![prev.clone_data()];
-            let mut cursor = curr;
-            while prev.data().consecutive(&cursor.data()) {
-                acc.push(cursor.clone_data());
-                prev = cursor.duplicate();
-                if let Some(n) = nodes.next() {
-                    cursor = n;
-                } else {
-                    break;
-                }
-            }
-            if acc.len() > 1 {
-                let new_data = acc.iter().skip(1).fold(acc[0].clone(), |a, b| a.merged(&b));
-                for data in &acc[0..acc.len() - 1] {
-                    self.remove(data);
+        let (self_first, self_last) = (self.first().unwrap(), self.last().unwrap());
+        let (other_first, other_last) = (other.first().unwrap(), other.last().unwrap());
+        if self
+            .raw
+            .comparator
+            .compare(&self_last.data(), &other_first.data())
+            == Ordering::Less
+        {
+            other.raw.unlink(&other_first);
+            self.raw.reset(&other_first);
+            let (left, right) = (self.raw.take_root(), other.raw.take_root());
+            self.raw.join(left, other_first, right);
+        } else if self
+            .raw
+            .comparator
+            .compare(&other_last.data(), &self_first.data())
+            == Ordering::Less
+        {
+            other.raw.unlink(&other_last);
+            self.raw.reset(&other_last);
+            let (left, right) = (other.raw.take_root(), self.raw.take_root());
+            self.raw.join(left, other_last, right);
+        } else {
+            for node in other.raw.take_links() {
+                self.raw.reset(&node);
+                let position = self.raw.insert_position(&node.data());
+                match position {
+                    Some((parent, is_left)) => self.raw.link_at(node, parent, is_left),
+                    None => {
+                        self.raw.nodes.release(node);
+                    }
                 }
-                let last = &acc[acc.len() - 1];
-                let mut last_node = self.get_node(last).expect("get node");
-                last_node.set_data(new_data);
             }
-            prev = cursor;
         }
     }
 
@@ -778,7 +1547,7 @@ impl<T: Ord> RBTreeSet<T> {
                 definitions.push(format!("    NullR{} [shape=point]", node.id()));
                 links.push(format!("    Node{0} -- NullR{0}", node.id()));
             }
-            tmp = Self::successor(node.duplicate());
+            tmp = node.next();
         }
 
         lines.append(&mut definitions);
@@ -791,86 +1560,281 @@ impl<T: Ord> RBTreeSet<T> {
     }
 }
 
-impl<T> fmt::Debug for RBTreeSet<T> {
+impl<T, A, C> fmt::Debug for RBTreeSet<T, A, C> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "RBTreeSet {{ length: {} }}", self.length)
+        write!(f, "RBTreeSet {{ length: {} }}", self.raw.length)
+    }
+}
+
+impl<T, A, C: Default> Default for RBTreeSet<T, A, C> {
+    fn default() -> Self {
+        RBTreeSet {
+            raw: RawTree::new(Nodes::new(), C::default()),
+        }
+    }
+}
+
+impl<T, A, C> Drop for RBTreeSet<T, A, C> {
+    fn drop(&mut self) {
+        self.raw.clear();
+    }
+}
+
+fn clone_subtree<T: Clone, A: Augment<T>>(node: Option<Node<T, A>>) -> Option<Node<T, A>> {
+    let sub = node?;
+
+    let mut cloned = Node::from(sub.clone_data());
+    cloned.set_colour(sub.colour());
+    cloned.set_left(clone_subtree(sub.left()));
+    cloned.set_right(clone_subtree(sub.right()));
+    if let Some(ref mut left) = cloned.left() {
+        left.set_parent(cloned.duplicate());
+    }
+    if let Some(ref mut right) = cloned.right() {
+        right.set_parent(cloned.duplicate());
+    }
+    raw::refresh(&mut Nodes::new(), &cloned);
+    Some(cloned)
+}
+
+impl<T: Clone, A: Augment<T>, C: Clone> Clone for RBTreeSet<T, A, C> {
+    fn clone(&self) -> Self {
+        let mut raw = RawTree::new(Nodes::new(), self.raw.comparator.clone());
+        raw.root = clone_subtree(self.raw.root.as_ref().map(Node::duplicate));
+        raw.reset_bounds();
+        RBTreeSet { raw }
+    }
+}
+
+/// Created with the method [iter] or with [iter_from], [iter_rev_from] and [range] for
+/// partial iterations.
+///
+/// [iter]: struct.RBTreeSet.html#method.iter
+/// [iter_from]: struct.RBTreeSet.html#method.iter_from
+/// [iter_rev_from]: struct.RBTreeSet.html#method.iter_rev_from
+/// [range]: struct.RBTreeSet.html#method.range
+pub struct Iter<T, A = ()> {
+    front: Option<Node<T, A>>,
+    back: Option<Node<T, A>>,
+}
+
+impl<T, A> Iterator for Iter<T, A> {
+    type Item = Node<T, A>;
+
+    fn next(&mut self) -> Option<Node<T, A>> {
+        let node = self.front.take()?;
+        if self.back.as_ref() == Some(&node) {
+            self.back = None;
+        } else {
+            self.front = node.next();
+        }
+        Some(node)
+    }
+}
+
+impl<T, A> DoubleEndedIterator for Iter<T, A> {
+    fn next_back(&mut self) -> Option<Node<T, A>> {
+        let node = self.back.take()?;
+        if self.front.as_ref() == Some(&node) {
+            self.front = None;
+        } else {
+            self.back = node.prev();
+        }
+        Some(node)
+    }
+}
+
+/// Created with the method [values] or with [values_from], [values_rev_from] and
+/// [values_range] for partial iterations.
+///
+/// [values]: struct.RBTreeSet.html#method.values
+/// [values_from]: struct.RBTreeSet.html#method.values_from
+/// [values_rev_from]: struct.RBTreeSet.html#method.values_rev_from
+/// [values_range]: struct.RBTreeSet.html#method.values_range
+pub struct IterValues<T, A = ()> {
+    inner: Iter<T, A>,
+}
+
+impl<T: Clone, A> Iterator for IterValues<T, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.inner.next().as_ref().map(Node::clone_data)
+    }
+}
+
+impl<T: Clone, A> DoubleEndedIterator for IterValues<T, A> {
+    fn next_back(&mut self) -> Option<T> {
+        self.inner.next_back().as_ref().map(Node::clone_data)
+    }
+}
+
+/// Created with the method [drain] or with [drain_range] to remove a range of values.
+///
+/// [drain]: struct.RBTreeSet.html#method.drain
+/// [drain_range]: struct.RBTreeSet.html#method.drain_range
+pub struct Drain<'a, T, A: Augment<T> = (), C: Compare<T> = Natural> {
+    set: &'a mut RBTreeSet<T, A, C>,
+    next: Option<Node<T, A>>,
+    remaining: usize,
+}
+
+impl<'a, T, A: Augment<T>, C: Compare<T>> Drain<'a, T, A, C> {
+    /// Returns the removed values, cloning the values of the nodes on which handles
+    /// are still held.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let mut set: RBTreeSet<_> = (0..5).collect();
+    /// let held = set.get_node(&3).unwrap();
+    /// assert_eq!(set.drain().values().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+    /// assert_eq!(*held.data(), 3);
+    /// ```
+    pub fn values(self) -> RemovedValues<Self>
+    where
+        T: Clone,
+    {
+        RemovedValues { inner: self }
     }
 }
 
-fn clone_subtree<T: Clone>(node: Option<Node<T>>) -> Option<Node<T>> {
-    let sub = node?;
+impl<T, A: Augment<T>, C: Compare<T>> Iterator for Drain<'_, T, A, C> {
+    type Item = Result<T, Node<T, A>>;
 
-    let mut cloned = Node::from(sub.clone_data());
-    cloned.set_colour(sub.colour());
-    cloned.set_left(clone_subtree(sub.left()));
-    cloned.set_right(clone_subtree(sub.right()));
-    if let Some(ref mut left) = cloned.left() {
-        left.set_parent(cloned.duplicate());
+    fn next(&mut self) -> Option<Self::Item> {
+        self.set
+            .raw
+            .drain_next(&mut self.next, &mut self.remaining)
+            .map(Node::try_into_data)
     }
-    if let Some(ref mut right) = cloned.right() {
-        right.set_parent(cloned.duplicate());
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
     }
-    Some(cloned)
 }
 
-impl<T: Clone> Clone for RBTreeSet<T> {
-    fn clone(&self) -> Self {
-        RBTreeSet {
-            root: clone_subtree(self.root.as_ref().map(Node::duplicate)),
-            ..*self
-        }
+impl<T, A: Augment<T>, C: Compare<T>> Drop for Drain<'_, T, A, C> {
+    fn drop(&mut self) {
+        while self
+            .set
+            .raw
+            .drain_next(&mut self.next, &mut self.remaining)
+            .is_some()
+        {}
     }
 }
 
-/// Created with the method [iter] or with [iter_from] for partial iterations.
+/// Created with the method [extract_if].
 ///
-/// [iter]: struct.RBTreeSet.html#method.iter
-/// [iter_from]: struct.RBTreeSet.html#method.iter_from
-pub struct Iter<T> {
-    cursor: Option<Node<T>>,
+/// [extract_if]: struct.RBTreeSet.html#method.extract_if
+pub struct ExtractIf<'a, T, A, C, F> {
+    set: &'a mut RBTreeSet<T, A, C>,
+    next: Option<Node<T, A>>,
+    pred: F,
 }
 
-impl<T: Ord> Iterator for Iter<T> {
-    type Item = Node<T>;
+impl<T, A, C, F> ExtractIf<'_, T, A, C, F>
+where
+    A: Augment<T>,
+    C: Compare<T>,
+    F: FnMut(&T) -> bool,
+{
+    /// Returns the extracted values, cloning the values of the nodes on which handles
+    /// are still held.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rbtset::RBTreeSet;
+    ///
+    /// let mut set: RBTreeSet<_> = (0..6).collect();
+    /// let held = set.get_node(&4).unwrap();
+    /// let evens: Vec<_> = set.extract_if(|v| v % 2 == 0).values().collect();
+    /// assert_eq!(evens, vec![0, 2, 4]);
+    /// assert_eq!(*held.data(), 4);
+    /// ```
+    pub fn values(self) -> RemovedValues<Self>
+    where
+        T: Clone,
+    {
+        RemovedValues { inner: self }
+    }
+}
 
-    fn next(&mut self) -> Option<Node<T>> {
-        let node = self.cursor.as_ref().map(Node::duplicate);
-        if let Some(ref n) = self.cursor {
-            self.cursor = RBTreeSet::successor(n.duplicate());
-        }
-        node
+impl<T, A, C, F> Iterator for ExtractIf<'_, T, A, C, F>
+where
+    A: Augment<T>,
+    C: Compare<T>,
+    F: FnMut(&T) -> bool,
+{
+    type Item = Result<T, Node<T, A>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.set
+            .raw
+            .extract_next(&mut self.next, &mut self.pred)
+            .map(Node::try_into_data)
     }
 }
 
-/// Created with the method [values] or with [values_from] for partial iterations.
+/// Created with the method [values] of [Drain] and of [ExtractIf].
 ///
-/// [values]: struct.RBTreeSet.html#method.values
-/// [values_from]: struct.RBTreeSet.html#method.values_from
-pub struct IterValues<T> {
-    inner: Iter<T>,
+/// [values]: struct.Drain.html#method.values
+/// [Drain]: struct.Drain.html
+/// [ExtractIf]: struct.ExtractIf.html
+pub struct RemovedValues<I> {
+    inner: I,
 }
 
-impl<T: Clone + Ord> Iterator for IterValues<T> {
+impl<T, A, I> Iterator for RemovedValues<I>
+where
+    T: Clone,
+    I: Iterator<Item = Result<T, Node<T, A>>>,
+{
     type Item = T;
 
     fn next(&mut self) -> Option<T> {
-        self.inner.next().as_ref().map(Node::clone_data)
+        self.inner
+            .next()
+            .map(|removed| removed.unwrap_or_else(|node| node.clone_data()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
     }
 }
 
-impl<T: Ord> FromIterator<T> for RBTreeSet<T> {
+impl<T, A: Augment<T>, C: Compare<T> + Default> FromIterator<T> for RBTreeSet<T, A, C> {
+    /// Builds each run of ascending values at once, like [from_sorted_iter], and
+    /// joins the runs when they do not interleave, inserting their values otherwise.
+    ///
+    /// [from_sorted_iter]: struct.RBTreeSet.html#method.from_sorted_iter
     fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
-        let mut s = Self::new();
-        for i in iter {
-            s.insert(i);
-        }
+        let mut s = Self::default();
+        s.raw.extend_from_iter(iter.into_iter());
         s
     }
 }
 
+impl<T, A: Augment<T>, C: Compare<T>> Extend<T> for RBTreeSet<T, A, C> {
+    /// Builds each run of ascending values at once, like [from_sorted_iter], and
+    /// joins it to the set when its values are all less or greater than the values of
+    /// the set, inserting them otherwise.
+    ///
+    /// [from_sorted_iter]: struct.RBTreeSet.html#method.from_sorted_iter
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.raw.extend_from_iter(iter.into_iter());
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::node::Colour;
+    use crate::{Containment, Span};
     use std::cmp::Ordering;
 
     macro_rules! assert_node {
@@ -894,9 +1858,9 @@ mod tests {
         tree.insert(15);
 
         print!("{}", tree.dump_tree_as_dot());
-        assert_node!(tree.root, 11, Colour::Black);
-        assert_node!(tree.root.as_ref().unwrap().left(), 2, Colour::Red);
-        assert_node!(tree.root.as_ref().unwrap().right(), 15, Colour::Red);
+        assert_node!(tree.raw.root, 11, Colour::Black);
+        assert_node!(tree.raw.root.as_ref().unwrap().left(), 2, Colour::Red);
+        assert_node!(tree.raw.root.as_ref().unwrap().right(), 15, Colour::Red);
     }
 
     #[test]
@@ -909,9 +1873,9 @@ mod tests {
         tree.insert(15);
 
         print!("{}", tree.dump_tree_as_dot());
-        assert_node!(tree.root, 3, Colour::Black);
-        assert_node!(tree.root.as_ref().unwrap().left(), 2, Colour::Black);
-        assert_node!(tree.root.as_ref().unwrap().right(), 11, Colour::Black);
+        assert_node!(tree.raw.root, 3, Colour::Black);
+        assert_node!(tree.raw.root.as_ref().unwrap().left(), 2, Colour::Black);
+        assert_node!(tree.raw.root.as_ref().unwrap().right(), 11, Colour::Black);
     }
 
     #[test]
@@ -922,9 +1886,9 @@ mod tests {
         tree.insert(2);
 
         print!("{}", tree.dump_tree_as_dot());
-        assert_node!(tree.root, 6, Colour::Black);
-        assert_node!(tree.root.as_ref().unwrap().left(), 2, Colour::Red);
-        assert_node!(tree.root.as_ref().unwrap().right(), 11, Colour::Red);
+        assert_node!(tree.raw.root, 6, Colour::Black);
+        assert_node!(tree.raw.root.as_ref().unwrap().left(), 2, Colour::Red);
+        assert_node!(tree.raw.root.as_ref().unwrap().right(), 11, Colour::Red);
     }
 
     #[test]
@@ -937,30 +1901,53 @@ mod tests {
         tree.insert(2);
 
         print!("{}", tree.dump_tree_as_dot());
-        assert_node!(tree.root, 11, Colour::Black);
-        assert_node!(tree.root.as_ref().unwrap().left(), 3, Colour::Black);
-        assert_node!(tree.root.as_ref().unwrap().right(), 15, Colour::Black);
+        assert_node!(tree.raw.root, 11, Colour::Black);
+        assert_node!(tree.raw.root.as_ref().unwrap().left(), 3, Colour::Black);
+        assert_node!(tree.raw.root.as_ref().unwrap().right(), 15, Colour::Black);
     }
 
+    #[allow(dead_code)]
     #[derive(Debug)]
     enum InvalidReason<T> {
         RootIsRed,
         RedHasRedChild(T),
         InvalidDepth(T, i64),
+        InvalidSize(T),
+        InvalidSummary(T),
+        InvalidBounds,
+    }
+
+    fn expected_summary<T, A: Augment<T>>(node: &Node<T, A>) -> A {
+        let mut summary = A::summarize(&node.data());
+        if let Some(ref left) = node.left() {
+            summary = left.summary().combine(&summary);
+        }
+        if let Some(ref right) = node.right() {
+            summary = summary.combine(&right.summary());
+        }
+        summary
     }
 
-    fn validate_subtree<T>(
-        node: &Node<T>,
-        leaves: &mut Vec<Node<T>>,
+    fn validate_subtree<T, A>(
+        node: &Node<T, A>,
+        leaves: &mut Vec<Node<T, A>>,
     ) -> Result<(), InvalidReason<T>>
     where
-        T: Clone + fmt::Debug + Ord,
+        T: Clone + fmt::Debug,
+        A: Augment<T> + PartialEq,
     {
         if node.colour() == Colour::Red
             && (node.left().as_ref().map(Node::colour) == Some(Colour::Red)
                 || node.right().as_ref().map(Node::colour) == Some(Colour::Red))
         {
             Err(InvalidReason::RedHasRedChild(node.clone_data()))
+        } else if node.size()
+            != 1 + node.left().as_ref().map_or(0, Node::size)
+                + node.right().as_ref().map_or(0, Node::size)
+        {
+            Err(InvalidReason::InvalidSize(node.clone_data()))
+        } else if *node.summary() != expected_summary(node) {
+            Err(InvalidReason::InvalidSummary(node.clone_data()))
         } else {
             if let Some(ref n) = node.left() {
                 validate_subtree(n, leaves)?;
@@ -975,11 +1962,24 @@ mod tests {
         }
     }
 
-    fn validate_tree<T>(tree: &RBTreeSet<T>) -> Result<(), InvalidReason<T>>
+    fn validate_tree<T, A, C>(tree: &RBTreeSet<T, A, C>) -> Result<(), InvalidReason<T>>
     where
-        T: Clone + fmt::Debug + Ord,
+        T: Clone + fmt::Debug,
+        A: Augment<T> + PartialEq,
     {
-        if let Some(ref root) = tree.root {
+        let mut first = tree.raw.root.as_ref().map(Node::duplicate);
+        while let Some(left) = first.as_ref().and_then(Node::left) {
+            first = Some(left);
+        }
+        let mut last = tree.raw.root.as_ref().map(Node::duplicate);
+        while let Some(right) = last.as_ref().and_then(Node::right) {
+            last = Some(right);
+        }
+        if tree.raw.first != first || tree.raw.last != last {
+            return Err(InvalidReason::InvalidBounds);
+        }
+
+        if let Some(ref root) = tree.raw.root {
             if root.colour() == Colour::Red {
                 Err(InvalidReason::RootIsRed)
             } else {
@@ -1032,6 +2032,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn double_ended_iterator() {
+        let values = vec![2, 6, 7, 8, 10, 11, 13, 18, 22, 26];
+        let set: RBTreeSet<_> = values.iter().cloned().collect();
+
+        let mut reversed = values.clone();
+        reversed.reverse();
+        assert_eq!(set.values().rev().collect::<Vec<i32>>(), reversed);
+
+        for split in 0..=values.len() {
+            let mut iter = set.values();
+            let mut front = Vec::new();
+            let mut back = Vec::new();
+            for _ in 0..split {
+                front.extend(iter.next());
+            }
+            back.extend(iter.by_ref().rev());
+            assert_eq!(iter.next(), None);
+            back.reverse();
+            front.append(&mut back);
+            assert_eq!(front, values);
+        }
+
+        let node = set.get_node(&11).unwrap();
+        assert_eq!(
+            set.values_rev_from(&node).collect::<Vec<i32>>(),
+            vec![11, 10, 8, 7, 6, 2]
+        );
+        assert_eq!(
+            set.values_range(7..=18).rev().collect::<Vec<i32>>(),
+            vec![18, 13, 11, 10, 8, 7]
+        );
+    }
+
     #[test]
     fn insert() {
         let mut set = RBTreeSet::new();
@@ -1111,6 +2145,183 @@ mod tests {
         assert_eq!(tree.len(), keep.len());
     }
 
+    #[test]
+    fn remove_foreign_node() {
+        let mut set: RBTreeSet<_> = (0..10).collect();
+        let mut node = set.get_node(&5).unwrap();
+        set.remove_node(&mut node);
+        assert!(!node.is_attached());
+
+        // a detached node is ignored
+        set.remove_node(&mut node);
+        assert_eq!(set.len(), 9);
+        assert_eq!(
+            set.values().collect::<Vec<_>>(),
+            vec![0, 1, 2, 3, 4, 6, 7, 8, 9]
+        );
+        validate_tree(&set).expect("validate tree");
+
+        // so is a node of another set
+        let other: RBTreeSet<_> = (0..10).collect();
+        let mut node = other.get_node(&3).unwrap();
+        set.remove_node(&mut node);
+        assert!(node.is_attached());
+        assert_eq!(set.len(), 9);
+        assert_eq!(other.len(), 10);
+        validate_tree(&set).expect("validate tree");
+        validate_tree(&other).expect("validate tree");
+    }
+
+    #[test]
+    fn attached_nodes() {
+        let mut set: RBTreeSet<_> = (1..10).collect();
+        let leaf = set.first().unwrap();
+        let other = set.last().unwrap();
+        assert!(leaf.is_attached());
+
+        set.remove(&1);
+        assert!(!leaf.is_attached());
+        assert_eq!(leaf.next(), None);
+        assert!(other.is_attached());
+        validate_tree(&set).expect("validate tree");
+
+        set.clear();
+        assert!(!other.is_attached());
+
+        // removing a node with two children leaves the handle of its successor intact
+        let mut set: RBTreeSet<_> = (1..10).collect();
+        let root = set.root().unwrap();
+        let successor = root.next().unwrap();
+        let (removed, value) = (*root.data(), *successor.data());
+        set.remove(&removed);
+        assert!(!root.is_attached());
+        assert!(successor.is_attached());
+        assert_eq!(*successor.data(), value);
+        assert_eq!(set.get_node(&value), Some(successor));
+        validate_tree(&set).expect("validate tree");
+
+        let mut set: RBTreeSet<_> = (1..10).collect();
+        let node = set.get_node(&5).unwrap();
+        drop(set.clone());
+        assert!(node.is_attached());
+        set.insert(10);
+        drop(set);
+        assert!(!node.is_attached());
+    }
+
+    #[test]
+    fn order_statistics() {
+        let mut set = RBTreeSet::new();
+        let mut oracle = Vec::new();
+        for i in 0..200 {
+            let value = (i * 37) % 101;
+            if set.insert(value).is_some() {
+                oracle.push(value);
+            }
+        }
+        for i in 0..100 {
+            let value = (i * 53) % 101;
+            if set.remove(&value) {
+                oracle.retain(|v| *v != value);
+            }
+        }
+        oracle.sort();
+
+        validate_tree(&set).expect("validate tree");
+        assert_eq!(set.raw.root.as_ref().map_or(0, Node::size), set.len());
+        for (index, value) in oracle.iter().enumerate() {
+            assert_eq!(*set.nth(index).unwrap().data(), *value);
+            assert_eq!(set.rank(value), index);
+            assert_eq!(set.get_node(value).unwrap().index(), index);
+        }
+        assert_eq!(set.nth(oracle.len()), None);
+        assert_eq!(set.rank(&1000), oracle.len());
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct SumMax(i64, i64);
+
+    impl Augment<i64> for SumMax {
+        fn summarize(data: &i64) -> SumMax {
+            SumMax(*data, *data)
+        }
+
+        fn combine(&self, other: &SumMax) -> SumMax {
+            SumMax(self.0 + other.0, self.1.max(other.1))
+        }
+    }
+
+    #[test]
+    fn augmented() {
+        let mut set: RBTreeSet<i64, SumMax> = RBTreeSet::new_augmented();
+        let mut oracle = std::collections::BTreeSet::new();
+        for i in 0..200 {
+            let value = (i * 37) % 101;
+            set.insert(value);
+            oracle.insert(value);
+        }
+        for i in 0..100 {
+            let value = (i * 53) % 101;
+            set.remove(&value);
+            oracle.remove(&value);
+        }
+        validate_tree(&set).expect("validate tree");
+
+        let fold = |values: Vec<i64>| {
+            values
+                .iter()
+                .map(SumMax::summarize)
+                .fold(None, |acc: Option<SumMax>, s| {
+                    Some(acc.map_or(s.clone(), |a| a.combine(&s)))
+                })
+        };
+        for start in -1..103 {
+            for end in start..103 {
+                assert_eq!(
+                    set.fold_range(start..end),
+                    fold(oracle.range(start..end).cloned().collect())
+                );
+            }
+        }
+        assert_eq!(set.summary(), fold(oracle.iter().cloned().collect()));
+
+        let node = set
+            .get_node(&oracle.iter().next().cloned().unwrap())
+            .unwrap();
+        node.apply(|v| *v -= 1000);
+        validate_tree(&set).expect("validate tree");
+        let cloned = set.clone();
+        validate_tree(&cloned).expect("validate tree");
+        assert_eq!(cloned.summary(), set.summary());
+    }
+
+    #[test]
+    fn comparator() {
+        let mut set = RBTreeSet::with_comparator(|a: &f64, b: &f64| b.total_cmp(a));
+        for i in 0..100 {
+            set.insert(((i * 37) % 101) as f64 / 4.0);
+            validate_tree(&set).unwrap();
+        }
+        for i in 0..50 {
+            assert!(set.remove(&(((i * 37) % 101) as f64 / 4.0)));
+            validate_tree(&set).unwrap();
+        }
+        let values: Vec<_> = set.values().collect();
+        assert_eq!(values.len(), 50);
+        assert!(values.windows(2).all(|w| w[0] > w[1]));
+
+        // bounds and ranges follow the comparator order
+        let ceiling = values.iter().find(|v| **v <= 20.1);
+        let floor = values.iter().rev().find(|v| **v >= 20.1);
+        assert_eq!(set.ceiling(&20.1).map(|n| n.clone_data()).as_ref(), ceiling);
+        assert_eq!(set.floor(&20.1).map(|n| n.clone_data()).as_ref(), floor);
+        assert_eq!(
+            set.rank(&20.1),
+            set.iter().filter(|n| *n.data() > 20.1).count()
+        );
+        assert!(set.values_range(20.0..10.0).all(|v| v <= 20.0 && v > 10.0));
+    }
+
     #[test]
     fn clone() {
         let mut set = RBTreeSet::new();
@@ -1132,6 +2343,97 @@ mod tests {
         assert_eq!(set.len(), tree_bis.len() - 1);
     }
 
+    #[test]
+    fn range() {
+        use std::collections::BTreeSet;
+
+        let values = vec![2, 6, 7, 8, 10, 11, 13, 18, 22, 26];
+        let set: RBTreeSet<_> = values.iter().cloned().collect();
+        let oracle: BTreeSet<_> = values.iter().cloned().collect();
+
+        for start in 0..30 {
+            for end in start..30 {
+                assert_eq!(
+                    set.values_range(start..end).collect::<Vec<i32>>(),
+                    oracle.range(start..end).cloned().collect::<Vec<i32>>()
+                );
+                assert_eq!(
+                    set.values_range(start..=end).collect::<Vec<i32>>(),
+                    oracle.range(start..=end).cloned().collect::<Vec<i32>>()
+                );
+                if start != end {
+                    let bounds = (Bound::Excluded(start), Bound::Excluded(end));
+                    assert_eq!(
+                        set.values_range(bounds).collect::<Vec<i32>>(),
+                        oracle.range(bounds).cloned().collect::<Vec<i32>>()
+                    );
+                }
+            }
+            assert_eq!(
+                set.values_range(start..).collect::<Vec<i32>>(),
+                oracle.range(start..).cloned().collect::<Vec<i32>>()
+            );
+            assert_eq!(
+                set.values_range(..start).collect::<Vec<i32>>(),
+                oracle.range(..start).cloned().collect::<Vec<i32>>()
+            );
+        }
+        assert_eq!(set.values_range(..).collect::<Vec<i32>>(), values);
+    }
+
+    #[test]
+    fn bounds() {
+        use std::collections::BTreeSet;
+
+        let values = vec![2, 6, 7, 8, 10, 11, 13, 18, 22, 26];
+        let set: RBTreeSet<_> = values.iter().cloned().collect();
+        let oracle: BTreeSet<_> = values.iter().cloned().collect();
+        let data = |n: Option<Node<i32>>| n.map(|n| n.clone_data());
+
+        for i in 0..30 {
+            assert_eq!(data(set.ceiling(&i)), oracle.range(i..).next().cloned());
+            assert_eq!(data(set.floor(&i)), oracle.range(..=i).next_back().cloned());
+            assert_eq!(
+                data(set.successor_of(&i)),
+                oracle
+                    .range((Bound::Excluded(i), Bound::Unbounded))
+                    .next()
+                    .cloned()
+            );
+            assert_eq!(
+                data(set.predecessor_of(&i)),
+                oracle.range(..i).next_back().cloned()
+            );
+        }
+        assert_eq!(data(set.lower_bound(Bound::<&i32>::Unbounded)), Some(2));
+        assert_eq!(data(set.upper_bound(Bound::<&i32>::Unbounded)), Some(26));
+
+        let empty: RBTreeSet<i32> = RBTreeSet::new();
+        assert_eq!(empty.ceiling(&1), None);
+        assert_eq!(empty.floor(&1), None);
+
+        let words: RBTreeSet<String> = ["b", "d", "f"].iter().map(|s| s.to_string()).collect();
+        let word = |n: Option<Node<String>>| n.map(|n| n.clone_data());
+        assert_eq!(word(words.floor("c")), Some("b".to_owned()));
+        assert_eq!(word(words.successor_of("d")), Some("f".to_owned()));
+        assert_eq!(word(words.predecessor_of("b")), None);
+        assert_eq!(
+            word(words.lower_bound(Bound::Excluded("a"))),
+            Some("b".to_owned())
+        );
+        assert_eq!(
+            word(words.upper_bound(Bound::Included("e"))),
+            Some("d".to_owned())
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn range_inverted() {
+        let set: RBTreeSet<_> = [1, 2, 3].iter().cloned().collect();
+        set.range((Bound::Included(3), Bound::Included(1)));
+    }
+
     #[derive(Debug, Clone, Eq)]
     struct Seq(std::ops::Range<usize>);
 
@@ -1163,6 +2465,19 @@ mod tests {
         }
     }
 
+    /// Returns the runs of covered indexes, the expected values of a packed set.
+    fn coverage(covered: &[bool]) -> Vec<std::ops::Range<usize>> {
+        let mut expected: Vec<std::ops::Range<usize>> = Vec::new();
+        for (index, c) in covered.iter().enumerate() {
+            match expected.last_mut() {
+                Some(range) if *c && range.end == index => range.end += 1,
+                _ if *c => expected.push(index..index + 1),
+                _ => {}
+            }
+        }
+        expected
+    }
+
     #[test]
     fn repack() {
         let mut set = RBTreeSet::new();
@@ -1189,4 +2504,429 @@ mod tests {
         );
         assert_eq!(set.len(), 3);
     }
+
+    #[test]
+    fn insert_coalescing() {
+        let mut set = RBTreeSet::new();
+        let mut covered = [false; 64];
+        for i in 0..64 {
+            let start = (i * 23) % 64;
+            set.insert_coalescing(Span(start..start + 1));
+            covered[start] = true;
+            validate_tree(&set).expect("validate tree");
+
+            let values: Vec<Span<usize>> = set.values().collect();
+            assert!(values.windows(2).all(|w| !w[0].consecutive(&w[1])));
+            let expected = coverage(&covered);
+            assert_eq!(
+                values.into_iter().map(|s| s.0).collect::<Vec<_>>(),
+                expected
+            );
+        }
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn remove_range() {
+        let mut set = RBTreeSet::new();
+        let mut covered = [false; 100];
+        for start in (0..100).step_by(10) {
+            set.insert(Span(start..start + 7));
+            covered[start..start + 7].iter_mut().for_each(|c| *c = true);
+        }
+        for i in 0..20 {
+            let start = (i * 37) % 95;
+            let end = start + 1 + (i * 13) % 5;
+            let overlapping = covered[start..end].iter().any(|c| *c);
+            assert_eq!(set.remove_range(&Span(start..end)), overlapping);
+            covered[start..end].iter_mut().for_each(|c| *c = false);
+            validate_tree(&set).expect("validate tree");
+
+            let expected = coverage(&covered);
+            assert_eq!(set.values().map(|s| s.0).collect::<Vec<_>>(), expected);
+        }
+
+        // the parts kept stay in their node
+        let mut set = RBTreeSet::new();
+        let node = set.insert(Span(1..10)).unwrap();
+        assert!(set.remove_range(&Span(4..6)));
+        assert_eq!(node.data().0, 1..4);
+        assert!(set.remove_range(&Span(0..2)));
+        assert_eq!(node.data().0, 2..4);
+        assert_eq!(
+            set.values().map(|s| s.0).collect::<Vec<_>>(),
+            vec![2..4, 6..10]
+        );
+    }
+
+    #[test]
+    fn repack_range() {
+        let mut set = RBTreeSet::new();
+        let mut values = Vec::new();
+        for i in 0..200 {
+            let value = i * 2..i * 2 + if i % 7 == 0 { 1 } else { 2 };
+            set.insert(Span(value.clone()));
+            values.push(value);
+        }
+        let merged = set.repack_range(Span(20..21)..Span(60..61));
+        validate_tree(&set).expect("validate tree");
+
+        let mut expected: Vec<std::ops::Range<usize>> = Vec::new();
+        let mut last_within = false;
+        for value in values {
+            let within = (20..60).contains(&value.start);
+            match expected.last_mut() {
+                Some(last) if last_within && within && last.end == value.start => {
+                    last.end = value.end
+                }
+                _ => expected.push(value),
+            }
+            last_within = within;
+        }
+        assert_eq!(merged, 200 - expected.len());
+        assert_eq!(set.values().map(|s| s.0).collect::<Vec<_>>(), expected);
+
+        let len = set.len();
+        let merged = set.repack_counted();
+        validate_tree(&set).expect("validate tree");
+        assert_eq!(set.len(), len - merged);
+        let values: Vec<Span<usize>> = set.values().collect();
+        assert!(values.windows(2).all(|w| !w[0].consecutive(&w[1])));
+    }
+
+    #[test]
+    fn repack_around() {
+        let mut set = RBTreeSet::new();
+        let mut covered = [false; 64];
+        for i in 0..64 {
+            let start = (i * 23) % 64;
+            let len = set.len();
+            let node = set.insert(Span(start..start + 1)).unwrap();
+            let merged = set.repack_around(&node);
+            covered[start] = true;
+            validate_tree(&set).expect("validate tree");
+            assert_eq!(set.len(), len + 1 - merged);
+
+            let expected = coverage(&covered);
+            assert_eq!(set.values().map(|s| s.0).collect::<Vec<_>>(), expected);
+        }
+    }
+
+    #[test]
+    fn from_sorted_iter() {
+        for length in 0..100 {
+            let set: RBTreeSet<i64, SumMax> = RBTreeSet::from_sorted_iter(0..length);
+            validate_tree(&set).expect("validate tree");
+            assert_eq!(set.len(), length as usize);
+            assert_eq!(
+                set.values().collect::<Vec<_>>(),
+                (0..length).collect::<Vec<_>>()
+            );
+            let expected = Some(SumMax((0..length).sum(), length - 1)).filter(|_| length > 0);
+            assert_eq!(set.summary(), expected);
+        }
+
+        let set: RBTreeSet<_> = RBTreeSet::from_sorted_iter(vec![1, 1, 2, 3, 3, 3]);
+        validate_tree(&set).expect("validate tree");
+        assert_eq!(set.values().collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "values are not sorted")]
+    fn from_sorted_iter_unsorted() {
+        let _: RBTreeSet<_> = RBTreeSet::from_sorted_iter(vec![1, 3, 2]);
+    }
+
+    #[test]
+    fn from_iter_runs() {
+        let values: Vec<i64> = (0..50).chain((0..100).rev()).chain(200..210).collect();
+        let mut set: RBTreeSet<_> = values.iter().cloned().collect();
+        validate_tree(&set).expect("validate tree");
+        assert_eq!(set.len(), 110);
+
+        set.extend(150..250);
+        validate_tree(&set).expect("validate tree");
+        assert_eq!(set.len(), 200);
+
+        let mut set = RBTreeSet::new();
+        set.extend(values);
+        validate_tree(&set).expect("validate tree");
+        assert_eq!(
+            set.values().collect::<Vec<_>>(),
+            (0..100).chain(200..210).collect::<Vec<_>>()
+        );
+
+        // runs before and after the values of the set are joined
+        let set: RBTreeSet<i64, SumMax> = (100..200).chain(0..50).chain(300..400).collect();
+        validate_tree(&set).expect("validate tree");
+        assert_eq!(
+            set.values().collect::<Vec<_>>(),
+            (0..50).chain(100..200).chain(300..400).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn from_iter_equivalent() {
+        let set: RBTreeSet<_> = vec![Seq(1..5), Seq(3..4)].into_iter().collect();
+        assert_eq!(set.len(), 1);
+
+        let values = vec![Seq(1..5), Seq(2..3), Seq(5..7), Seq(6..8), Seq(0..1)];
+        let mut set = RBTreeSet::new();
+        set.extend(values);
+        validate_tree(&set).expect("validate tree");
+        assert_eq!(
+            set.values().map(|s| s.0).collect::<Vec<_>>(),
+            vec![0..1, 1..5, 5..7]
+        );
+
+        // a run matching the first or last value of the set is not joined
+        let mut set = RBTreeSet::with_comparator(Containment);
+        set.insert(Span(5..10));
+        set.extend(vec![Span(6..7)]);
+        set.extend(vec![Span(5..6)]);
+        set.extend(vec![Span(0..2), Span(3..4)]);
+        validate_tree(&set).expect("validate tree");
+        assert_eq!(
+            set.values().map(|s| s.0).collect::<Vec<_>>(),
+            vec![0..2, 3..4, 5..10]
+        );
+    }
+
+    #[test]
+    fn join_split() {
+        for left_length in [0, 1, 2, 7, 100] {
+            for right_length in [0, 1, 3, 40, 300] {
+                let left: RBTreeSet<i64, SumMax> = (0..left_length).rev().collect();
+                let right: RBTreeSet<i64, SumMax> =
+                    (left_length + 1..left_length + 1 + right_length).collect();
+                let set = RBTreeSet::join(left, left_length, right);
+                validate_tree(&set).expect("validate tree");
+                let length = left_length + 1 + right_length;
+                assert_eq!(set.len(), length as usize);
+                assert_eq!(
+                    set.values().collect::<Vec<_>>(),
+                    (0..length).collect::<Vec<_>>()
+                );
+                assert_eq!(set.summary(), Some(SumMax((0..length).sum(), length - 1)));
+                assert_eq!(*set.nth(left_length as usize).unwrap().data(), left_length);
+
+                for at in [-1, 0, length / 3, length - 1, length + 5] {
+                    let (a, b) = set.clone().split_at(&at);
+                    validate_tree(&a).expect("validate tree");
+                    validate_tree(&b).expect("validate tree");
+                    let at = at.clamp(0, length);
+                    assert_eq!(a.values().collect::<Vec<_>>(), (0..at).collect::<Vec<_>>());
+                    assert_eq!(
+                        b.values().collect::<Vec<_>>(),
+                        (at..length).collect::<Vec<_>>()
+                    );
+                    assert_eq!(a.len() + b.len(), length as usize);
+                    assert_eq!(
+                        b.nth(0).map(|n| n.clone_data()),
+                        Some(at).filter(|at| *at < length)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "values are not ordered")]
+    fn join_unordered() {
+        let left: RBTreeSet<_> = (0..10).collect();
+        let right: RBTreeSet<_> = (5..20).collect();
+        RBTreeSet::join(left, 4, right);
+    }
+
+    #[test]
+    fn append() {
+        let mut a: RBTreeSet<i64, SumMax> = (0..50).collect();
+        let mut b: RBTreeSet<i64, SumMax> = (50..60).collect();
+        a.append(&mut b);
+        let mut c: RBTreeSet<i64, SumMax> = (-300..0).collect();
+        a.append(&mut c);
+        let mut d: RBTreeSet<i64, SumMax> = (-400..400).step_by(7).collect();
+        a.append(&mut d);
+        for set in [&a, &b, &c, &d] {
+            validate_tree(set).expect("validate tree");
+        }
+        assert!(b.is_empty() && c.is_empty() && d.is_empty());
+
+        let mut expected: Vec<i64> = (-300..60).chain((-400..400).step_by(7)).collect();
+        expected.sort_unstable();
+        expected.dedup();
+        assert_eq!(a.values().collect::<Vec<_>>(), expected);
+        assert_eq!(a.len(), expected.len());
+        assert_eq!(a.summary(), Some(SumMax(expected.iter().sum(), 398)));
+
+        let mut e = RBTreeSet::new_augmented();
+        e.append(&mut a);
+        assert_eq!(e.len(), expected.len());
+        assert!(a.is_empty());
+    }
+
+    #[test]
+    fn retain() {
+        for modulo in 1..8 {
+            let mut set: RBTreeSet<i64, SumMax> = (0..200).collect();
+            set.retain(|v| v % modulo == 1);
+            validate_tree(&set).expect("validate tree");
+            let expected: Vec<i64> = (0..200).filter(|v| v % modulo == 1).collect();
+            assert_eq!(set.values().collect::<Vec<_>>(), expected);
+            assert_eq!(set.len(), expected.len());
+        }
+    }
+
+    #[test]
+    fn drain() {
+        let mut set: RBTreeSet<i64, SumMax> = (0..100).collect();
+        let handle = set.get_node(&20).unwrap();
+        let mut drained = set.drain_range(10..30);
+        assert_eq!(drained.size_hint(), (20, Some(20)));
+        assert_eq!(
+            drained.by_ref().take(5).collect::<Result<Vec<_>, _>>(),
+            Ok(vec![10, 11, 12, 13, 14])
+        );
+        drop(drained);
+        validate_tree(&set).expect("validate tree");
+        assert_eq!(set.len(), 80);
+        assert!(!handle.is_attached());
+        assert_eq!(
+            set.values_range(..35).collect::<Vec<_>>(),
+            (0..10).chain(30..35).collect::<Vec<_>>()
+        );
+
+        assert_eq!(set.drain_range(31..=31).collect::<Vec<_>>(), vec![Ok(31)]);
+        assert_eq!(set.drain_range(10..30).count(), 0);
+        assert_eq!(set.drain().count(), 79);
+        assert!(set.is_empty());
+        assert_eq!(set.first(), None);
+    }
+
+    #[test]
+    fn drain_held_handles() {
+        // not Clone, the removed values can only be moved out
+        #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+        struct Job(Box<u32>);
+
+        let mut set: RBTreeSet<_> = (0..10).map(|i| Job(Box::new(i))).collect();
+        let drained = set.get_node(&Job(Box::new(2))).unwrap();
+        let extracted = set.get_node(&Job(Box::new(7))).unwrap();
+
+        let removed: Vec<_> = set.drain_range(..Job(Box::new(5))).collect();
+        assert_eq!(removed.len(), 5);
+        assert_eq!(removed[1], Ok(Job(Box::new(1))));
+        assert_eq!(removed[2].as_ref().err(), Some(&drained));
+        assert!(!drained.is_attached());
+        assert_eq!(*drained.data(), Job(Box::new(2)));
+
+        let odds: Vec<_> = set
+            .extract_if(|v| *v.0 % 2 == 1)
+            .filter_map(Result::ok)
+            .collect();
+        assert_eq!(odds, vec![Job(Box::new(5)), Job(Box::new(9))]);
+        assert!(!extracted.is_attached());
+        assert_eq!(*extracted.data(), Job(Box::new(7)));
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn drain_values() {
+        let mut set: RBTreeSet<String> = (0..10).map(|i| i.to_string()).collect();
+        let drained = set.get_node("2").unwrap();
+        let extracted = set.get_node("7").unwrap();
+        let kept = set.get_node("8").unwrap();
+
+        let values: Vec<_> = set.drain_range(.."5".to_string()).values().collect();
+        assert_eq!(values, vec!["0", "1", "2", "3", "4"]);
+        assert_eq!(*drained.data(), "2");
+
+        let odds: Vec<_> = set
+            .extract_if(|v| v.parse::<u32>().unwrap() % 2 == 1)
+            .values()
+            .collect();
+        assert_eq!(odds, vec!["5", "7", "9"]);
+        assert!(!extracted.is_attached());
+        assert_eq!(*extracted.data(), "7");
+
+        assert_eq!(set.drain().values().collect::<Vec<_>>(), vec!["6", "8"]);
+        assert_eq!(*kept.data(), "8");
+    }
+
+    #[test]
+    fn extract_if() {
+        let mut set: RBTreeSet<i64, SumMax> = (0..100).collect();
+        let mut extracted = set.extract_if(|v| v % 3 == 0).values();
+        assert_eq!(
+            extracted.by_ref().take(10).collect::<Vec<_>>(),
+            (0..30).step_by(3).collect::<Vec<_>>()
+        );
+        drop(extracted);
+        validate_tree(&set).expect("validate tree");
+        let expected: Vec<i64> = (0..100).filter(|v| v % 3 != 0 || *v >= 30).collect();
+        assert_eq!(set.values().collect::<Vec<_>>(), expected);
+
+        let extracted: Result<Vec<_>, _> = set.extract_if(|v| v % 2 == 0).collect();
+        validate_tree(&set).expect("validate tree");
+        assert_eq!(
+            extracted,
+            Ok(expected
+                .iter()
+                .cloned()
+                .filter(|v| v % 2 == 0)
+                .collect::<Vec<_>>())
+        );
+        assert!(set.values().all(|v| v % 2 == 1));
+    }
+
+    #[test]
+    fn pop() {
+        let mut set: RBTreeSet<i64, SumMax> = RBTreeSet::new_augmented();
+        let mut oracle = std::collections::BTreeSet::new();
+        for i in 0..300 {
+            let value = (i * 37) % 101;
+            match i % 5 {
+                0 => assert_eq!(set.pop_first().and_then(Result::ok), oracle.pop_first()),
+                1 => assert_eq!(set.pop_last().and_then(Result::ok), oracle.pop_last()),
+                2 => assert_eq!(set.remove(&value), oracle.remove(&value)),
+                _ => assert_eq!(set.insert(value).is_some(), oracle.insert(value)),
+            }
+            validate_tree(&set).expect("validate tree");
+            assert_eq!(set.peek_first().as_deref(), oracle.first());
+            assert_eq!(set.peek_last().as_deref(), oracle.last());
+        }
+        while let Some(Ok(value)) = set.pop_first() {
+            assert_eq!(Some(value), oracle.pop_first());
+            validate_tree(&set).expect("validate tree");
+        }
+        assert!(oracle.is_empty());
+        assert_eq!(set.last(), None);
+    }
+
+    #[test]
+    fn pop_held_handles() {
+        // not Clone, the popped values can only be moved out
+        #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+        struct Job(Box<u32>);
+
+        let mut set: RBTreeSet<_> = (0..10).map(|i| Job(Box::new(i))).collect();
+        assert_eq!(set.pop_first().unwrap().ok(), Some(Job(Box::new(0))));
+        assert_eq!(*set.peek_first().unwrap(), Job(Box::new(1)));
+
+        let last = set.last().unwrap();
+        let node = set.pop_last().unwrap().unwrap_err();
+        assert_eq!(node, last);
+        assert!(!last.is_attached());
+        assert_eq!(*last.data(), Job(Box::new(9)));
+        let node = node.try_into_data().unwrap_err();
+        drop(last);
+        assert_eq!(node.try_into_data().ok(), Some(Job(Box::new(9))));
+
+        let mut jobs = Vec::new();
+        while let Some(Ok(job)) = set.pop_first() {
+            jobs.push(*job.0);
+        }
+        assert_eq!(jobs, (1..9).collect::<Vec<_>>());
+        assert!(set.is_empty());
+    }
 }